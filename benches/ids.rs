@@ -0,0 +1,98 @@
+use std::hint::black_box;
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use country_boundaries::{CountryBoundaries, LatLon};
+
+// Run with `cargo bench --bench ids`.
+//
+// Compares `ids_iter` to `ids` on the default data and on a cell with an unusually large number of
+// regions, in which `ids_iter` has to sort more ids than fit on the stack.
+
+fn ids(c: &mut Criterion) {
+    let buf = std::fs::read("./data/boundaries360x180.ser").unwrap();
+    let boundaries = CountryBoundaries::from_reader(buf.as_slice()).unwrap();
+    let positions = positions();
+
+    let mut group = c.benchmark_group("default data");
+    group.bench_function("ids", |b| b.iter(|| {
+        for &position in &positions {
+            black_box(boundaries.ids(black_box(position)));
+        }
+    }));
+    group.bench_function("ids_iter", |b| b.iter(|| {
+        for &position in &positions {
+            black_box(boundaries.ids_iter(black_box(position)).count());
+        }
+    }));
+    group.finish();
+
+    let mut group = c.benchmark_group("many ids in cell");
+    for count in [16, 64, 255] {
+        let buf = cell_with_many_ids(count);
+        let boundaries = CountryBoundaries::from_reader(buf.as_slice()).unwrap();
+        let position = LatLon::new(0.0, 0.0).unwrap();
+        group.bench_with_input(BenchmarkId::new("ids", count), &position, |b, &position| {
+            b.iter(|| black_box(boundaries.ids(black_box(position))))
+        });
+        group.bench_with_input(BenchmarkId::new("ids_iter", count), &position, |b, &position| {
+            b.iter(|| black_box(boundaries.ids_iter(black_box(position)).count()))
+        });
+    }
+    group.finish();
+}
+
+/// Positions spread over the whole world
+fn positions() -> Vec<LatLon> {
+    let mut positions = Vec::new();
+    let mut latitude = -89.5;
+    while latitude < 90.0 {
+        let mut longitude = -179.5;
+        while longitude < 180.0 {
+            positions.push(LatLon::new(latitude, longitude).unwrap());
+            longitude += 7.3;
+        }
+        latitude += 3.1;
+    }
+    positions
+}
+
+/// Serialized boundaries of a single cell that is covered by `count` regions of different sizes,
+/// half of them contain the cell and half of them only intersect with it
+fn cell_with_many_ids(count: usize) -> Vec<u8> {
+    let id = |i: usize| format!("R{i}");
+    let mut buf = Vec::new();
+    let string = |buf: &mut Vec<u8>, value: &str| {
+        buf.extend((value.len() as u16).to_be_bytes());
+        buf.extend(value.as_bytes());
+    };
+    buf.extend(2u16.to_be_bytes());
+    buf.extend((count as u32).to_be_bytes());
+    for i in 0..count {
+        string(&mut buf, &id(i));
+        buf.extend((((i * 37) % count) as f64).to_be_bytes());
+    }
+    buf.extend(1u32.to_be_bytes());
+    buf.extend(1u32.to_be_bytes());
+
+    let containing = count / 2;
+    buf.push(containing as u8);
+    for i in 0..containing {
+        string(&mut buf, &id(i));
+    }
+    buf.push((count - containing) as u8);
+    for i in containing..count {
+        string(&mut buf, &id(i));
+        // outer polygons: the whole cell
+        buf.push(1);
+        buf.extend(4u32.to_be_bytes());
+        for (x, y) in [(0u16, 0u16), (0xffff, 0), (0xffff, 0xffff), (0, 0xffff)] {
+            buf.extend(x.to_be_bytes());
+            buf.extend(y.to_be_bytes());
+        }
+        // inner polygons
+        buf.push(0);
+    }
+    buf
+}
+
+criterion_group!(benches, ids);
+criterion_main!(benches);
@@ -0,0 +1,44 @@
+#![no_main]
+
+use std::collections::HashSet;
+use country_boundaries::{BoundingBox, CountryBoundaries, CountryBoundariesRef, LatLon, LoadOptions};
+use libfuzzer_sys::fuzz_target;
+
+// Run with `cargo +nightly fuzz run from_reader` from the root of the repository.
+//
+// Loading arbitrary data must neither panic nor allocate more than the limits allow. Whatever is
+// loaded successfully, even without validation, must not panic when queried. Inputs that were
+// found to break this are kept as regression tests in tests/integration_test.rs.
+fuzz_target!(|data: &[u8]| {
+    for validate in [true, false] {
+        let options = LoadOptions {
+            validate,
+            max_total_bytes: 1 << 20,
+            max_cells: 1 << 16,
+            max_ring_length: 1 << 12,
+            max_id_length: 64,
+        };
+        if let Ok(boundaries) = CountryBoundaries::from_reader_with_options(data, &options) {
+            query(|position| boundaries.ids(position).len());
+            query(|position| boundaries.is_in_any(position, &HashSet::from(["A", "B"])) as usize);
+            boundaries.intersecting_ids(bbox());
+            boundaries.containing_ids(bbox());
+        }
+        if let Ok(boundaries) = CountryBoundariesRef::from_slice_with_options(data, &options) {
+            query(|position| boundaries.ids(position).len());
+            query(|position| boundaries.is_in_any(position, &HashSet::from(["A", "B"])) as usize);
+            boundaries.intersecting_ids(bbox());
+            boundaries.containing_ids(bbox());
+        }
+    }
+});
+
+fn query(f: impl Fn(LatLon) -> usize) {
+    for (latitude, longitude) in [(0.0, 0.0), (-90.0, -180.0), (90.0, 180.0), (45.5, -120.25)] {
+        f(LatLon::new(latitude, longitude).unwrap());
+    }
+}
+
+fn bbox() -> BoundingBox {
+    BoundingBox::new(-10.0, 170.0, 10.0, -170.0).unwrap()
+}
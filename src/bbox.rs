@@ -1,6 +1,6 @@
 use crate::error::Error;
 
-#[derive(Debug, Copy, Clone)]
+#[derive(Debug, Copy, Clone, PartialEq)]
 pub struct BoundingBox {
     min_latitude: f64,
     min_longitude: f64,
@@ -0,0 +1,489 @@
+use std::cmp::Ordering;
+use std::collections::{BinaryHeap, HashMap, HashSet};
+use crate::cell::Cell;
+use crate::cell::point::Point;
+use crate::latlon::EARTH_RADIUS;
+use crate::raster::{normalize, Raster};
+use crate::{CountryBoundaries, LatLon, RegionIndex};
+
+/// The point on the border of a region that is closest to a given position, see
+/// [`CountryBoundaries::distance_to_border`]
+#[derive(Debug, Clone)]
+pub struct BorderPoint<'a> {
+    /// The id of the region whose border it is
+    pub id: &'a str,
+    /// Where on the border the closest point is
+    pub position: LatLon,
+    /// Great-circle distance in meters from the given position to the closest point
+    pub distance: f64
+}
+
+/// The regions a position is in, split by whether that is certain, see
+/// [`CountryBoundaries::ids_with_confidence`]
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct IdsWithConfidence<'a> {
+    /// The ids of the regions that contain the position and whose border is farther away than the
+    /// tolerance, ordered by size like [`CountryBoundaries::ids`]
+    pub certain: Vec<&'a str>,
+    /// The ids of the regions whose border is within the tolerance, so the position might or
+    /// might not be in them, ordered by size
+    pub uncertain: Vec<&'a str>
+}
+
+/// A longitude and latitude in degrees
+type Position = (f64, f64);
+
+/// Border segments longer than this in degrees are split, so that the difference between the
+/// straight line in longitude and latitude and the great circle between its ends stays small
+const MAX_SEGMENT_LENGTH: f64 = 0.1;
+
+/// The closest point of a region's border found so far
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Border {
+    pub id: RegionIndex,
+    pub position: Position,
+    /// in meters
+    pub distance: f64
+}
+
+/// Returns the closest point on the border of any region to the given `position`, or only on the
+/// border of the region `only`
+pub(crate) fn nearest_border(
+    boundaries: &CountryBoundaries,
+    position: LatLon,
+    only: Option<RegionIndex>
+) -> Option<Border> {
+    search(boundaries, position, only, f64::INFINITY, Some(0.0)).into_iter().next()
+}
+
+/// Returns the closest point on the border of the nearest region to the given `position` and of
+/// all other regions whose border is at most `margin` meters farther away, if the nearest is at
+/// most `max_distance` meters away. Ordered by distance.
+pub(crate) fn nearest_borders(
+    boundaries: &CountryBoundaries,
+    position: LatLon,
+    max_distance: f64,
+    margin: f64
+) -> Vec<Border> {
+    search(boundaries, position, None, max_distance, Some(margin))
+}
+
+/// Returns the closest point on the border of each region that is at most `max_distance` meters
+/// away from the given `position`, ordered by distance
+pub(crate) fn borders_within(
+    boundaries: &CountryBoundaries,
+    position: LatLon,
+    max_distance: f64
+) -> Vec<Border> {
+    search(boundaries, position, None, max_distance, None)
+}
+
+/// Returns the closest point on the border of each region that is at most `max_distance` meters
+/// away from the given `position`, or only of the region `only`, ordered by distance. If
+/// `nearest_margin` is given, only the nearest border and those at most that much farther away
+/// are returned.
+///
+/// The cells are searched outward from the cell the position is in, in the order of their
+/// distance to the position, until no unvisited cell can be close enough.
+fn search(
+    boundaries: &CountryBoundaries,
+    position: LatLon,
+    only: Option<RegionIndex>,
+    max_distance: f64,
+    nearest_margin: Option<f64>
+) -> Vec<Border> {
+    let raster = boundaries.raster();
+    if boundaries.raster.is_empty() { return Vec::new(); }
+
+    let origin = to_vector((position.longitude(), position.latitude()));
+    let (start, _) = raster.cell_and_local_point(position);
+    let mut visited: HashSet<usize> = HashSet::from([start]);
+    let mut queue = BinaryHeap::from([Candidate { distance: 0.0, index: start }]);
+    let mut borders: HashMap<RegionIndex, Border> = HashMap::new();
+    let mut max_distance = max_distance;
+
+    while let Some(Candidate { distance, index }) = queue.pop() {
+        if distance > max_distance { break; }
+
+        let cell = &boundaries.raster[index];
+        for id in cell.get_all_ids() {
+            if only.is_some_and(|only| only != id) { continue; }
+            for (a, b) in border_segments(boundaries, &raster, index, id) {
+                let (position, distance) = closest_point(origin, a, b);
+                if distance > max_distance { continue; }
+                if borders.get(&id).is_none_or(|border| distance < border.distance) {
+                    borders.insert(id, Border { id, position, distance });
+                }
+                if let Some(margin) = nearest_margin {
+                    max_distance = max_distance.min(distance + margin);
+                }
+            }
+        }
+
+        for neighbour in neighbours(&raster, index).into_iter().flatten() {
+            if visited.insert(neighbour) {
+                let distance = distance_to_cell(origin, raster.cell_bounds(neighbour));
+                queue.push(Candidate { distance, index: neighbour });
+            }
+        }
+    }
+
+    let mut borders: Vec<Border> = borders.into_values()
+        .filter(|border| border.distance <= max_distance)
+        .collect();
+    borders.sort_by(|a, b| a.distance.total_cmp(&b.distance));
+    borders
+}
+
+/// Returns the parts of the border of the region `id` within the cell with the given `index` as
+/// segments in longitude and latitude.
+///
+/// The border of an area that only partly covers the cell are the edges of its polygons, the
+/// border of an area that completely covers the cell are the sides of the cell. In both cases,
+/// parts of the sides of the cell only count where the region does not continue in the
+/// neighbouring cell.
+fn border_segments(
+    boundaries: &CountryBoundaries,
+    raster: &Raster,
+    index: usize,
+    id: RegionIndex
+) -> Vec<(Position, Position)> {
+    let cell = &boundaries.raster[index];
+    let [left, right, below, above, ..] = neighbours(raster, index);
+    // the sides of the neighbours are opposite to the sides of this cell
+    let covered = [(left, 1), (right, 0), (below, 3), (above, 2)].map(|(neighbour, side)| match neighbour {
+        Some(neighbour) => covered_side(&boundaries.raster[neighbour], id, side),
+        // the poles are no borders
+        None => vec![(0, 0xffff)]
+    });
+    let to_position = |point: &Point| raster.longitude_latitude_of(index, point);
+
+    let mut edges: Vec<(Point, Point)> = Vec::new();
+    if cell.containing_ids.contains(&id) {
+        edges.extend((0..4).map(|side| (point_on_side(side, 0), point_on_side(side, 0xffff))));
+    }
+    for (_, multipolygon) in cell.intersecting_areas.iter().filter(|(other, _)| *other == id) {
+        for ring in multipolygon.outer.iter().chain(multipolygon.inner.iter()) {
+            edges.extend(ring.iter().copied().zip(ring.iter().copied().cycle().skip(1)));
+        }
+    }
+
+    let mut segments = Vec::new();
+    for (a, b) in edges {
+        let Some(side) = side_of(&a, &b) else {
+            segments.push((to_position(&a), to_position(&b)));
+            continue;
+        };
+        let (start, end) = along_side(side, &a, &b);
+        for (start, end) in subtract((start.min(end), start.max(end)), &covered[side]) {
+            segments.push((to_position(&point_on_side(side, start)), to_position(&point_on_side(side, end))));
+        }
+    }
+    segments
+}
+
+/// Returns the parts of the given `side` of the `cell` that the region `id` covers, as ranges
+/// along that side. See [`side_of`] for the numbering of the sides.
+fn covered_side(cell: &Cell, id: RegionIndex, side: usize) -> Vec<(u16, u16)> {
+    if cell.containing_ids.contains(&id) {
+        return vec![(0, 0xffff)];
+    }
+    let on_side = |rings: &[Vec<Point>]| -> Vec<(u16, u16)> {
+        rings.iter()
+            // the data contains slivers without area that lie on the side of a cell. They cover
+            // nothing of the side, the region merely touches it
+            .filter(|ring| !is_without_area(ring))
+            .flat_map(|ring| ring.iter().zip(ring.iter().cycle().skip(1)))
+            .filter(|(a, b)| side_of(a, b) == Some(side))
+            .map(|(a, b)| along_side(side, a, b))
+            .map(|(start, end)| (start.min(end), start.max(end)))
+            .collect()
+    };
+    let mut covered = Vec::new();
+    for (_, multipolygon) in cell.intersecting_areas.iter().filter(|(other, _)| *other == id) {
+        // holes that touch the side leave it uncovered there
+        let holes = on_side(&multipolygon.inner);
+        for range in on_side(&multipolygon.outer) {
+            covered.extend(subtract(range, &holes));
+        }
+    }
+    covered
+}
+
+/// Returns whether the given `ring` encloses no area, i.e. all its points are on one line
+fn is_without_area(ring: &[Point]) -> bool {
+    let doubled_area: i64 = ring.iter()
+        .zip(ring.iter().cycle().skip(1))
+        .map(|(a, b)| a.x as i64 * b.y as i64 - b.x as i64 * a.y as i64)
+        .sum();
+    doubled_area == 0
+}
+
+/// Returns on which side of the cell the edge from `a` to `b` lies, if any: 0 is left, 1 is
+/// right, 2 is below and 3 is above, like the first four [`neighbours`]
+fn side_of(a: &Point, b: &Point) -> Option<usize> {
+    if a.x == 0 && b.x == 0 { Some(0) }
+    else if a.x == 0xffff && b.x == 0xffff { Some(1) }
+    else if a.y == 0 && b.y == 0 { Some(2) }
+    else if a.y == 0xffff && b.y == 0xffff { Some(3) }
+    else { None }
+}
+
+/// Returns where the edge from `a` to `b` on the given `side` starts and ends along that side
+fn along_side(side: usize, a: &Point, b: &Point) -> (u16, u16) {
+    if side < 2 { (a.y, b.y) } else { (a.x, b.x) }
+}
+
+/// Returns the point at `value` along the given `side` of the cell
+fn point_on_side(side: usize, value: u16) -> Point {
+    match side {
+        0 => Point { x: 0, y: value },
+        1 => Point { x: 0xffff, y: value },
+        2 => Point { x: value, y: 0 },
+        _ => Point { x: value, y: 0xffff },
+    }
+}
+
+/// Returns the parts of the `range` that are not in any of the `ranges`, which may overlap
+fn subtract(range: (u16, u16), ranges: &[(u16, u16)]) -> Vec<(u16, u16)> {
+    let mut ranges = ranges.to_vec();
+    ranges.sort_unstable();
+    let mut result = Vec::new();
+    let (mut start, end) = range;
+    for (other_start, other_end) in ranges {
+        if other_end <= start { continue; }
+        if other_start >= end { break; }
+        if other_start > start { result.push((start, other_start)); }
+        start = start.max(other_end);
+    }
+    if start < end { result.push((start, end)); }
+    result
+}
+
+/// Returns the indices of the cells left, right, below and above the cell with the given `index`,
+/// and then the cells diagonal to it. There are no cells beyond the poles.
+fn neighbours(raster: &Raster, index: usize) -> [Option<usize>; 8] {
+    let x = index % raster.width;
+    let y = index / raster.width;
+    let left = (x + raster.width - 1) % raster.width;
+    let right = (x + 1) % raster.width;
+    let below = (y + 1 < raster.height).then_some(y + 1);
+    let above = y.checked_sub(1);
+    [
+        Some(raster.cell_index(left, y)),
+        Some(raster.cell_index(right, y)),
+        below.map(|y| raster.cell_index(x, y)),
+        above.map(|y| raster.cell_index(x, y)),
+        below.map(|y| raster.cell_index(left, y)),
+        below.map(|y| raster.cell_index(right, y)),
+        above.map(|y| raster.cell_index(left, y)),
+        above.map(|y| raster.cell_index(right, y)),
+    ]
+}
+
+/// A cell in the queue of cells to search, ordered so that the closest comes first
+struct Candidate {
+    /// the least distance from the position to any point in the cell, in meters
+    distance: f64,
+    index: usize
+}
+
+impl PartialEq for Candidate {
+    fn eq(&self, other: &Self) -> bool { self.cmp(other) == Ordering::Equal }
+}
+
+impl Eq for Candidate {}
+
+impl PartialOrd for Candidate {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> { Some(self.cmp(other)) }
+}
+
+impl Ord for Candidate {
+    fn cmp(&self, other: &Self) -> Ordering {
+        // reversed, because BinaryHeap is a max-heap
+        other.distance.total_cmp(&self.distance)
+    }
+}
+
+/// Returns the least distance in meters from `origin` to any point within the given cell bounds
+fn distance_to_cell(origin: [f64; 3], [min_longitude, min_latitude, max_longitude, max_latitude]: [f64; 4]) -> f64 {
+    let (longitude, latitude) = to_position(origin);
+    let is_in_longitudes = normalize(longitude - min_longitude, 0.0, 360.0) <= max_longitude - min_longitude;
+    if is_in_longitudes && (min_latitude..=max_latitude).contains(&latitude) {
+        return 0.0;
+    }
+
+    // along the parallels, the closest point is at the closest longitude
+    let closest_longitude = if is_in_longitudes {
+        longitude
+    } else if normalize(min_longitude - longitude, -180.0, 360.0).abs() <= normalize(max_longitude - longitude, -180.0, 360.0).abs() {
+        min_longitude
+    } else {
+        max_longitude
+    };
+    let mut candidates = vec![(closest_longitude, min_latitude), (closest_longitude, max_latitude)];
+    // along the meridians, the distance is smallest either at their ends or where the derivative
+    // of the distance is 0
+    for meridian in [min_longitude, max_longitude] {
+        let delta = (meridian - longitude).to_radians();
+        let latitude = latitude.to_radians().sin().atan2(latitude.to_radians().cos() * delta.cos()).to_degrees();
+        if (min_latitude..=max_latitude).contains(&latitude) {
+            candidates.push((meridian, latitude));
+        }
+        candidates.push((meridian, min_latitude));
+        candidates.push((meridian, max_latitude));
+    }
+    candidates.into_iter()
+        .map(|position| angle(origin, to_vector(position)) * EARTH_RADIUS)
+        .fold(f64::INFINITY, f64::min)
+}
+
+/// Returns the point on the segment from `a` to `b` that is closest to `origin` and its distance
+/// in meters
+fn closest_point(origin: [f64; 3], a: Position, b: Position) -> (Position, f64) {
+    let pieces = ((b.0 - a.0).abs().max((b.1 - a.1).abs()) / MAX_SEGMENT_LENGTH).ceil().max(1.0) as usize;
+    let mut closest = (a, f64::INFINITY);
+    for i in 0..pieces {
+        let start = interpolate(a, b, i as f64 / pieces as f64);
+        let end = interpolate(a, b, (i + 1) as f64 / pieces as f64);
+        // closest point on the chord between the two ends, projected back onto the sphere
+        let start = to_vector(start);
+        let end = to_vector(end);
+        let direction = sub(end, start);
+        let length = dot(direction, direction);
+        let t = if length > 0.0 { (dot(sub(origin, start), direction) / length).clamp(0.0, 1.0) } else { 0.0 };
+        let point = [start[0] + t * direction[0], start[1] + t * direction[1], start[2] + t * direction[2]];
+        let distance = angle(origin, point) * EARTH_RADIUS;
+        if distance < closest.1 {
+            closest = (to_position(point), distance);
+        }
+    }
+    closest
+}
+
+fn interpolate(a: Position, b: Position, t: f64) -> Position {
+    (a.0 + (b.0 - a.0) * t, a.1 + (b.1 - a.1) * t)
+}
+
+/// Returns the unit vector of the given position on the sphere
+fn to_vector((longitude, latitude): Position) -> [f64; 3] {
+    let (longitude, latitude) = (longitude.to_radians(), latitude.to_radians());
+    [latitude.cos() * longitude.cos(), latitude.cos() * longitude.sin(), latitude.sin()]
+}
+
+/// Returns the position of the given vector, which does not need to be of unit length
+fn to_position(v: [f64; 3]) -> Position {
+    (v[1].atan2(v[0]).to_degrees(), v[2].atan2(v[0].hypot(v[1])).to_degrees())
+}
+
+/// Returns the angle between the two vectors in radians
+fn angle(a: [f64; 3], b: [f64; 3]) -> f64 {
+    let cross = [a[1] * b[2] - a[2] * b[1], a[2] * b[0] - a[0] * b[2], a[0] * b[1] - a[1] * b[0]];
+    dot(cross, cross).sqrt().atan2(dot(a, b))
+}
+
+fn dot(a: [f64; 3], b: [f64; 3]) -> f64 {
+    a[0] * b[0] + a[1] * b[1] + a[2] * b[2]
+}
+
+fn sub(a: [f64; 3], b: [f64; 3]) -> [f64; 3] {
+    [a[0] - b[0], a[1] - b[1], a[2] - b[2]]
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::cell::multipolygon::Multipolygon;
+    use crate::regions::Regions;
+    use super::*;
+
+    const ONE_DEGREE: f64 = EARTH_RADIUS * std::f64::consts::PI / 180.0;
+
+    #[test]
+    fn distance_to_cell_bounds() {
+        let cell = [10.0, 40.0, 11.0, 41.0];
+        assert_eq!(0.0, distance_to_cell(to_vector((10.5, 40.5)), cell));
+        assert!((distance_to_cell(to_vector((10.5, 39.0)), cell) - ONE_DEGREE).abs() < 1.0);
+        assert!((distance_to_cell(to_vector((10.5, 43.0)), cell) - 2.0 * ONE_DEGREE).abs() < 1.0);
+        // on the equator, the closest point of a meridian is on the equator
+        let cell = [10.0, -1.0, 11.0, 1.0];
+        assert!((distance_to_cell(to_vector((8.0, 0.0)), cell) - 2.0 * ONE_DEGREE).abs() < 1.0);
+        // across the 180th meridian
+        let cell = [-180.0, -1.0, -179.0, 1.0];
+        assert!((distance_to_cell(to_vector((179.0, 0.0)), cell) - ONE_DEGREE).abs() < 1.0);
+    }
+
+    #[test]
+    fn distance_to_cell_is_never_more_than_to_points_in_it() {
+        let cell = [20.0, 60.0, 30.0, 70.0];
+        for (longitude, latitude) in [(0.0, 80.0), (50.0, 62.0), (-150.0, 65.0), (25.0, -10.0)] {
+            let origin = to_vector((longitude, latitude));
+            let distance = distance_to_cell(origin, cell);
+            for x in 0..=10 {
+                for y in 0..=10 {
+                    let point = to_vector((20.0 + x as f64, 60.0 + y as f64));
+                    assert!(distance <= angle(origin, point) * EARTH_RADIUS + 1e-6);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn closest_point_on_segment() {
+        let (position, distance) = closest_point(to_vector((0.5, 1.0)), (0.0, 0.0), (1.0, 0.0));
+        assert!((position.0 - 0.5).abs() < 1e-4);
+        assert!(position.1.abs() < 1e-9);
+        assert!((distance - ONE_DEGREE).abs() < 1.0);
+
+        let (position, _) = closest_point(to_vector((2.0, 1.0)), (0.0, 0.0), (1.0, 0.0));
+        assert!((position.0 - 1.0).abs() < 1e-4);
+    }
+
+    #[test]
+    fn closest_point_on_long_segment_along_parallel() {
+        // a parallel is not a great circle, the long segment must be followed closely anyway
+        let (position, distance) = closest_point(to_vector((5.0, 50.0)), (0.0, 60.0), (10.0, 60.0));
+        assert!((position.1 - 60.0).abs() < 0.01);
+        assert!((distance - 10.0 * ONE_DEGREE).abs() < 1000.0);
+    }
+
+    #[test]
+    fn subtract_ranges() {
+        assert_eq!(vec![(0, 10)], subtract((0, 10), &[]));
+        assert_eq!(vec![(0, 2), (5, 6), (8, 10)], subtract((0, 10), &[(7, 8), (2, 5), (6, 7)]));
+        assert_eq!(vec![(3, 4)], subtract((0, 10), &[(4, 12), (0, 2), (1, 3)]));
+        assert!(subtract((2, 8), &[(0, 10)]).is_empty());
+    }
+
+    #[test]
+    fn covered_side_of_cell() {
+        let mut regions = Regions::default();
+        let a = regions.insert("A");
+        let square = |min: u16, max: u16| vec![
+            Point { x: 0, y: min },
+            Point { x: 0x1000, y: min },
+            Point { x: 0x1000, y: max },
+            Point { x: 0, y: max },
+        ];
+        let cell = |containing_ids, outer, inner| Cell {
+            containing_ids,
+            intersecting_areas: vec![(a, Multipolygon { outer, inner })]
+        };
+
+        assert_eq!(vec![(0, 0xffff)], covered_side(&cell(vec![a], vec![], vec![]), a, 0));
+        let partly = cell(vec![], vec![square(0x100, 0x800)], vec![square(0x200, 0x300)]);
+        assert_eq!(vec![(0x100, 0x200), (0x300, 0x800)], covered_side(&partly, a, 0));
+        assert!(covered_side(&partly, a, 1).is_empty());
+        // a sliver without area lying on the side covers nothing
+        let sliver = vec![Point { x: 0, y: 0x100 }, Point { x: 0, y: 0x800 }, Point { x: 0, y: 0x400 }];
+        assert!(covered_side(&cell(vec![], vec![sliver], vec![]), a, 0).is_empty());
+    }
+
+    #[test]
+    fn neighbours_wrap_around_180th_meridian() {
+        let raster = Raster::new(4, 8);
+        assert_eq!(
+            [Some(3), Some(1), Some(4), None, Some(7), Some(5), None, None],
+            neighbours(&raster, 0)
+        );
+    }
+}
@@ -0,0 +1,577 @@
+use std::collections::HashSet;
+use std::io;
+use std::io::ErrorKind;
+use crate::validation::{check_id, check_raster, check_ring};
+use crate::cell::multipolygon::is_point_in_ring;
+use crate::cell::point::Point;
+use crate::raster::Raster;
+use crate::regions::Regions;
+use crate::{BoundingBox, LatLon, Limit, LoadError, LoadOptions};
+
+/// A view on country boundaries data that queries the serialized bytes in place.
+///
+/// Creating it only validates the data once and remembers where each cell starts. Contrary to
+/// [`CountryBoundaries`](crate::CountryBoundaries), the cells are not copied to the heap, which
+/// makes it faster to create and use less memory, at the cost of slightly slower queries.
+///
+/// # Example
+/// ```
+/// # use country_boundaries::{CountryBoundariesRef, LatLon};
+/// #
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let buf = std::fs::read("./data/boundaries360x180.ser")?;
+/// let boundaries = CountryBoundariesRef::from_slice(&buf)?;
+///
+/// assert_eq!(
+///     vec!["US-TX", "US"],
+///     boundaries.ids(LatLon::new(33.0, -97.0)?)
+/// );
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct CountryBoundariesRef<'a> {
+    data: &'a [u8],
+    index: Index
+}
+
+impl<'a> CountryBoundariesRef<'a> {
+
+    /// Create a CountryBoundariesRef from the given bytes, in the same format as read by
+    /// [`CountryBoundaries::from_reader`](crate::CountryBoundaries::from_reader).
+    ///
+    /// Returns an error if the data is not a valid boundaries file.
+    pub fn from_slice(data: &'a [u8]) -> Result<CountryBoundariesRef<'a>, LoadError> {
+        Self::from_slice_with_options(data, &LoadOptions::default())
+    }
+
+    /// Create a CountryBoundariesRef from the given bytes, with the given `options`.
+    ///
+    /// See [`from_slice`](CountryBoundariesRef::from_slice) and [`LoadOptions`]
+    pub fn from_slice_with_options(
+        data: &'a [u8],
+        options: &LoadOptions
+    ) -> Result<CountryBoundariesRef<'a>, LoadError> {
+        Ok(CountryBoundariesRef { data, index: Index::new(data, options)? })
+    }
+
+    /// Returns whether the given `position` is in the region with the given `id`
+    ///
+    /// See [`CountryBoundaries::is_in`](crate::CountryBoundaries::is_in)
+    pub fn is_in(&self, position: LatLon, id: &str) -> bool {
+        self.view().is_in(position, id)
+    }
+
+    /// Returns whether the given `position` is in any of the regions with the given `ids`.
+    ///
+    /// See [`CountryBoundaries::is_in_any`](crate::CountryBoundaries::is_in_any)
+    pub fn is_in_any(&self, position: LatLon, ids: &HashSet<&str>) -> bool {
+        self.view().is_in_any(position, ids)
+    }
+
+    /// Returns the ids of the regions the given `position` is contained in, ordered by size of
+    /// the region ascending
+    ///
+    /// See [`CountryBoundaries::ids`](crate::CountryBoundaries::ids)
+    pub fn ids(&self, position: LatLon) -> Vec<&'a str> {
+        self.view().ids(position)
+    }
+
+    /// Returns the ids of the regions that fully contain the given bounding box `bounds`.
+    ///
+    /// See [`CountryBoundaries::containing_ids`](crate::CountryBoundaries::containing_ids)
+    pub fn containing_ids(&self, bounds: BoundingBox) -> HashSet<&'a str> {
+        self.view().containing_ids(bounds)
+    }
+
+    /// Returns the ids of the regions that contain or at lest intersect with the given bounding box
+    /// `bounds`.
+    ///
+    /// See [`CountryBoundaries::intersecting_ids`](crate::CountryBoundaries::intersecting_ids)
+    pub fn intersecting_ids(&self, bounds: BoundingBox) -> HashSet<&'a str> {
+        self.view().intersecting_ids(bounds)
+    }
+
+    fn view(&self) -> View<'a, '_> {
+        View { data: self.data, index: &self.index }
+    }
+}
+
+/// Where to find what in serialized country boundaries data
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct Index {
+    /// offsets in the data at which each cell of the raster starts
+    cell_offsets: Vec<usize>,
+    raster: Raster,
+    /// the ids of all regions in the data and the sizes of the different countries contained
+    regions: Regions,
+    /// the rank of each region when ordered by size, see [`Regions::size_ranks`]
+    ranks: Vec<u32>
+}
+
+impl Index {
+
+    /// Validate the given `data` and create an index for it
+    pub fn new(data: &[u8], options: &LoadOptions) -> Result<Index, LoadError> {
+        options.check_limit(0, Limit::TotalBytes, data.len())?;
+        let mut reader = Reader { data, offset: 0 };
+
+        let version = reader.u16()?;
+        if version != 2 {
+            return Err(LoadError::UnsupportedVersion { found: version, expected: 2 })
+        }
+
+        let geometry_sizes_count = reader.usize32()?;
+        let mut regions = Regions::with_capacity(geometry_sizes_count.min(data.len()));
+        for _ in 0..geometry_sizes_count {
+            let id = read_id(&mut reader, options)?;
+            let size = reader.f64()?;
+            regions.insert_with_size(id, size);
+        }
+        let raster_width = reader.usize32()?;
+        let raster_size_offset = reader.offset;
+        let raster_size = reader.usize32()?;
+        options.check_limit(raster_size_offset, Limit::Cells, raster_size)?;
+        check_raster(raster_width, raster_size)?;
+        // each cell is at least 2 bytes long
+        let mut cell_offsets = Vec::with_capacity(raster_size.min(data.len() / 2));
+        for _ in 0..raster_size {
+            cell_offsets.push(reader.offset);
+            skip_cell(&mut reader, options, &mut regions)?;
+        }
+
+        let ranks = regions.size_ranks();
+        Ok(Index { cell_offsets, raster: Raster::new(raster_width, raster_size), regions, ranks })
+    }
+
+    fn size_rank(&self, id: &str) -> u32 {
+        // all ids in the data have been added to the table while indexing
+        self.regions.index(id).map_or(0, |index| self.ranks[index.index()])
+    }
+}
+
+/// Serialized country boundaries data together with its index, on which queries can be done
+#[derive(Debug, Copy, Clone)]
+pub(crate) struct View<'a, 'i> {
+    pub data: &'a [u8],
+    pub index: &'i Index
+}
+
+impl<'a> View<'a, '_> {
+    pub fn is_in(&self, position: LatLon, id: &str) -> bool {
+        let (cell, point) = self.cell_and_local_point(position);
+        cell.containing_ids().any(|containing_id| containing_id == id) ||
+        cell.intersecting_areas().any(|area| area.id == id && area.covers(&point))
+    }
+
+    pub fn is_in_any(&self, position: LatLon, ids: &HashSet<&str>) -> bool {
+        let (cell, point) = self.cell_and_local_point(position);
+        cell.containing_ids().any(|containing_id| ids.contains(containing_id)) ||
+        cell.intersecting_areas().any(|area| ids.contains(area.id) && area.covers(&point))
+    }
+
+    pub fn ids(&self, position: LatLon) -> Vec<&'a str> {
+        let (cell, point) = self.cell_and_local_point(position);
+        let mut result: Vec<&'a str> = cell.containing_ids().collect();
+        result.extend(
+            cell.intersecting_areas()
+                .filter(|area| area.covers(&point))
+                .map(|area| area.id)
+        );
+        result.sort_by_cached_key(|id| self.index.size_rank(id));
+        result
+    }
+
+    pub fn containing_ids(&self, bounds: BoundingBox) -> HashSet<&'a str> {
+        let mut ids: HashSet<&'a str> = HashSet::new();
+        let mut first_cell = true;
+        for cell in self.cells(&bounds) {
+            if first_cell {
+                ids.extend(cell.containing_ids());
+                first_cell = false;
+            } else {
+                ids.retain(|&id| cell.containing_ids().any(|containing_id| containing_id == id));
+                if ids.is_empty() { return ids; }
+            }
+        }
+        ids
+    }
+
+    pub fn intersecting_ids(&self, bounds: BoundingBox) -> HashSet<&'a str> {
+        let mut ids: HashSet<&'a str> = HashSet::new();
+        for cell in self.cells(&bounds) {
+            ids.extend(cell.containing_ids());
+            ids.extend(cell.intersecting_areas().map(|area| area.id));
+        }
+        ids
+    }
+
+    fn cell_and_local_point(&self, position: LatLon) -> (CellRef<'a>, Point) {
+        let (index, point) = self.index.raster.cell_and_local_point(position);
+        (self.cell(index), point)
+    }
+
+    fn cells(&self, bounds: &BoundingBox) -> impl Iterator<Item = CellRef<'a>> + '_ {
+        self.index.raster.cells(bounds).map(|index| self.cell(index))
+    }
+
+    fn cell(&self, index: usize) -> CellRef<'a> {
+        let offset = self.index.cell_offsets.get(index).copied().unwrap_or(usize::MAX);
+        CellRef { data: self.data.get(offset..).unwrap_or_default() }
+    }
+}
+
+/// One cell in the serialized country boundaries grid. The data has been validated when the
+/// `CountryBoundariesRef` was created, so reading it cannot fail.
+#[derive(Debug, Copy, Clone)]
+struct CellRef<'a> {
+    /// data starting at this cell
+    data: &'a [u8]
+}
+
+impl<'a> CellRef<'a> {
+    fn containing_ids(self) -> impl Iterator<Item = &'a str> {
+        let mut reader = Reader { data: self.data, offset: 0 };
+        let count = reader.u8().unwrap_or(0);
+        (0..count).map(move |_| reader.str().unwrap_or_default())
+    }
+
+    fn intersecting_areas(self) -> impl Iterator<Item = AreaRef<'a>> {
+        let mut reader = Reader { data: self.data, offset: 0 };
+        let count = reader.u8().unwrap_or(0);
+        for _ in 0..count {
+            reader.str().unwrap_or_default();
+        }
+        let count = reader.u8().unwrap_or(0);
+        (0..count).map(move |_| {
+            let id = reader.str().unwrap_or_default();
+            let outer = reader.offset;
+            skip_polygons(&mut reader, None).unwrap_or_default();
+            let inner = reader.offset;
+            skip_polygons(&mut reader, None).unwrap_or_default();
+            AreaRef { id, outer: &self.data[outer..inner], inner: &self.data[inner..reader.offset] }
+        })
+    }
+}
+
+/// Id + area that only partly covers a cell, see `Multipolygon`
+struct AreaRef<'a> {
+    id: &'a str,
+    outer: &'a [u8],
+    inner: &'a [u8]
+}
+
+impl AreaRef<'_> {
+    fn covers(&self, point: &Point) -> bool {
+        let mut insides = 0;
+        for ring in rings(self.outer) {
+            if is_point_in_ring(point, ring) {
+                insides += 1;
+            }
+        }
+        for ring in rings(self.inner) {
+            if is_point_in_ring(point, ring) {
+                insides -= 1;
+            }
+        }
+        insides > 0
+    }
+}
+
+/// Iterate the rings of the serialized polygons in `data`
+fn rings(data: &[u8]) -> impl Iterator<Item = impl DoubleEndedIterator<Item = Point> + Clone + '_> {
+    let mut reader = Reader { data, offset: 0 };
+    let count = reader.u8().unwrap_or(0);
+    (0..count).map(move |_| {
+        let size = reader.usize32().unwrap_or(0);
+        reader.bytes(size.saturating_mul(4)).unwrap_or_default()
+            .chunks_exact(4)
+            .map(|p| Point {
+                x: u16::from_be_bytes([p[0], p[1]]),
+                y: u16::from_be_bytes([p[2], p[3]])
+            })
+    })
+}
+
+/// Skip the cell at the current position of the `reader`, while checking it according to the
+/// `options` and adding the ids in it to the `regions`
+fn skip_cell<'a>(
+    reader: &mut Reader<'a>,
+    options: &LoadOptions,
+    regions: &mut Regions
+) -> Result<(), LoadError> {
+    let mut cell_ids = Vec::new();
+    let mut skip_id = |reader: &mut Reader<'a>| -> Result<(), LoadError> {
+        let offset = reader.offset;
+        let id = read_id(reader, options)?;
+        let index = regions.insert(id);
+        if options.validate {
+            check_id(offset, id, regions.size(index).is_some(), &mut cell_ids)?;
+        }
+        Ok(())
+    };
+    let containing_ids_size = reader.u8()?;
+    for _ in 0..containing_ids_size {
+        skip_id(reader)?;
+    }
+    let intersecting_areas_size = reader.u8()?;
+    for _ in 0..intersecting_areas_size {
+        skip_id(reader)?;
+        skip_polygons(reader, Some(options))?;
+        skip_polygons(reader, Some(options))?;
+    }
+    Ok(())
+}
+
+/// Skip the polygons at the current position of the `reader`, while checking them according to
+/// the `options`, if any
+fn skip_polygons(reader: &mut Reader, options: Option<&LoadOptions>) -> Result<(), LoadError> {
+    let size = reader.u8()?;
+    for _ in 0..size {
+        let offset = reader.offset;
+        let ring_size = reader.usize32()?;
+        if let Some(options) = options {
+            options.check_limit(offset, Limit::RingLength, ring_size)?;
+            if options.validate {
+                check_ring(offset, ring_size)?;
+            }
+        }
+        reader.bytes(ring_size.saturating_mul(4))?;
+    }
+    Ok(())
+}
+
+fn read_id<'a>(reader: &mut Reader<'a>, options: &LoadOptions) -> Result<&'a str, LoadError> {
+    let offset = reader.offset;
+    let id = reader.str()?;
+    options.check_limit(offset, Limit::IdLength, id.len())?;
+    Ok(id)
+}
+
+/// Reads big-endian values from a byte slice without copying
+#[derive(Debug)]
+struct Reader<'a> {
+    data: &'a [u8],
+    offset: usize
+}
+
+impl<'a> Reader<'a> {
+    fn bytes(&mut self, length: usize) -> Result<&'a [u8], LoadError> {
+        let bytes = self.offset.checked_add(length)
+            .and_then(|end| self.data.get(self.offset..end))
+            .ok_or(LoadError::Truncated { offset: self.offset })?;
+        self.offset += length;
+        Ok(bytes)
+    }
+
+    fn array<const N: usize>(&mut self) -> Result<[u8; N], LoadError> {
+        Ok(self.bytes(N)?.try_into().unwrap_or([0; N]))
+    }
+
+    fn u8(&mut self) -> Result<u8, LoadError> {
+        Ok(u8::from_be_bytes(self.array()?))
+    }
+
+    fn u16(&mut self) -> Result<u16, LoadError> {
+        Ok(u16::from_be_bytes(self.array()?))
+    }
+
+    fn usize32(&mut self) -> Result<usize, LoadError> {
+        usize::try_from(u32::from_be_bytes(self.array()?))
+            .map_err(|e| LoadError::Io(io::Error::new(ErrorKind::Unsupported, e)))
+    }
+
+    fn f64(&mut self) -> Result<f64, LoadError> {
+        Ok(f64::from_be_bytes(self.array()?))
+    }
+
+    fn str(&mut self) -> Result<&'a str, LoadError> {
+        let length = usize::from(self.u16()?);
+        let offset = self.offset;
+        std::str::from_utf8(self.bytes(length)?)
+            .map_err(|e| LoadError::InvalidUtf8 { offset: offset + e.valid_up_to() })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn latlon(latitude: f64, longitude: f64) -> LatLon {
+        LatLon::new(latitude, longitude).unwrap()
+    }
+
+    fn bbox(min_latitude: f64, min_longitude: f64, max_latitude: f64, max_longitude: f64) -> BoundingBox {
+        BoundingBox::new(min_latitude, min_longitude, max_latitude, max_longitude).unwrap()
+    }
+
+    const BASIC: [u8; 68] = [
+        0x00, 0x02,                                     // version number
+        0x00, 0x00, 0x00, 0x02,                         // geometry sizes map length
+        0x00, 0x01, 0x41,                               // "A"
+        0x40, 0x29, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, // 12.5
+        0x00, 0x01, 0x42,                               // "B"
+        0x40, 0x24, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, // 10.0
+        0x00, 0x00, 0x00, 0x02,                         // raster width
+        0x00, 0x00, 0x00, 0x02,                         // raster size
+        // cell 1
+        0x01,                                           // containing ids length
+        0x00, 0x01, 0x41,                               // "A"
+        0x01,                                           // intersecting areas length
+        0x00, 0x01, 0x42,                               // "B"
+        0x01,                                           // outer polygons length
+        0x00, 0x00, 0x00, 0x04,                         // ring length
+        0x00, 0x00, 0x00, 0x00,                         // 0,0
+        0x7f, 0xff, 0x00, 0x00,                         // 0x7fff,0
+        0x7f, 0xff, 0xff, 0xff,                         // 0x7fff,0xffff
+        0x00, 0x00, 0xff, 0xff,                         // 0,0xffff
+        0x00,                                           // inner polygons length
+        // cell 2
+        0x00,                                           // containing ids length
+        0x00,                                           // intersecting areas length
+    ];
+
+    #[test]
+    fn read_basic() {
+        let index = Index::new(&BASIC, &LoadOptions::default()).unwrap();
+        assert_eq!(Raster { width: 2, height: 1 }, index.raster);
+        assert_eq!(vec![36, 66], index.cell_offsets);
+        assert_eq!(
+            vec![("A", Some(12.5)), ("B", Some(10.0))],
+            index.regions.iter().collect::<Vec<_>>()
+        );
+        assert_eq!(1, index.size_rank("A"));
+        assert_eq!(0, index.size_rank("B"));
+    }
+
+    #[test]
+    fn read_truncated_is_error() {
+        for i in 0..BASIC.len() - 1 {
+            assert!(matches!(
+                CountryBoundariesRef::from_slice(&BASIC[0..i]),
+                Err(LoadError::Truncated { offset }) if offset <= i
+            ));
+        }
+    }
+
+    #[test]
+    fn read_empty_raster_is_error() {
+        for raster_width in [0x00, 0x01] {
+            let data = [
+                0x00, 0x02,                         // version number
+                0x00, 0x00, 0x00, 0x00,             // geometry sizes map length
+                0x00, 0x00, 0x00, raster_width,     // raster width
+                0x00, 0x00, 0x00, 0x00,             // raster size
+            ];
+            assert!(matches!(CountryBoundariesRef::from_slice(&data), Err(LoadError::EmptyRaster)));
+        }
+    }
+
+    #[test]
+    fn read_wrong_version_is_error() {
+        let mut data = BASIC;
+        data[1] = 0x03;
+        assert!(matches!(
+            CountryBoundariesRef::from_slice(&data),
+            Err(LoadError::UnsupportedVersion { found: 3, expected: 2 })
+        ));
+    }
+
+    #[test]
+    fn read_invalid_utf8_is_error() {
+        let mut data = BASIC;
+        data[8] = 0xff;
+        assert!(matches!(
+            CountryBoundariesRef::from_slice(&data),
+            Err(LoadError::InvalidUtf8 { offset: 8 })
+        ));
+    }
+
+    #[test]
+    fn validate() {
+        let mut data = BASIC;
+        data[43] = 0x43; // "B" -> "C"
+        assert!(matches!(
+            CountryBoundariesRef::from_slice(&data),
+            Err(LoadError::UnknownId { offset: 41, id }) if id == "C"
+        ));
+        let options = LoadOptions { validate: false, ..LoadOptions::default() };
+        assert!(CountryBoundariesRef::from_slice_with_options(&data, &options).is_ok());
+
+        data[43] = 0x41; // "B" -> "A"
+        assert!(matches!(
+            CountryBoundariesRef::from_slice(&data),
+            Err(LoadError::DuplicateId { offset: 41, id }) if id == "A"
+        ));
+
+        let mut data = BASIC;
+        data[48] = 0x02; // ring length
+        assert!(matches!(
+            CountryBoundariesRef::from_slice(&data),
+            Err(LoadError::InvalidRing { offset: 45, length: 2 })
+        ));
+    }
+
+    #[test]
+    fn limits() {
+        let options = LoadOptions { max_total_bytes: 67, ..LoadOptions::default() };
+        assert!(matches!(
+            CountryBoundariesRef::from_slice_with_options(&BASIC, &options),
+            Err(LoadError::LimitExceeded { offset: 0, limit: Limit::TotalBytes, value: 68, max: 67 })
+        ));
+
+        let options = LoadOptions { max_cells: 1, ..LoadOptions::default() };
+        assert!(matches!(
+            CountryBoundariesRef::from_slice_with_options(&BASIC, &options),
+            Err(LoadError::LimitExceeded { offset: 32, limit: Limit::Cells, value: 2, max: 1 })
+        ));
+
+        let options = LoadOptions { max_id_length: 0, ..LoadOptions::default() };
+        assert!(matches!(
+            CountryBoundariesRef::from_slice_with_options(&BASIC, &options),
+            Err(LoadError::LimitExceeded { offset: 6, limit: Limit::IdLength, value: 1, max: 0 })
+        ));
+
+        let options = LoadOptions { max_ring_length: 3, ..LoadOptions::default() };
+        assert!(matches!(
+            CountryBoundariesRef::from_slice_with_options(&BASIC, &options),
+            Err(LoadError::LimitExceeded { offset: 45, limit: Limit::RingLength, value: 4, max: 3 })
+        ));
+    }
+
+    #[test]
+    fn ids() {
+        let boundaries = CountryBoundariesRef::from_slice(&BASIC).unwrap();
+        assert_eq!(vec!["B", "A"], boundaries.ids(latlon(0.0, -135.0)));
+        assert_eq!(vec!["A"], boundaries.ids(latlon(0.0, -45.0)));
+        assert!(boundaries.ids(latlon(0.0, 45.0)).is_empty());
+    }
+
+    #[test]
+    fn is_in() {
+        let boundaries = CountryBoundariesRef::from_slice(&BASIC).unwrap();
+        assert!(boundaries.is_in(latlon(0.0, -135.0), "A"));
+        assert!(boundaries.is_in(latlon(0.0, -135.0), "B"));
+        assert!(!boundaries.is_in(latlon(0.0, -45.0), "B"));
+        assert!(!boundaries.is_in(latlon(0.0, 45.0), "A"));
+    }
+
+    #[test]
+    fn is_in_any() {
+        let boundaries = CountryBoundariesRef::from_slice(&BASIC).unwrap();
+        assert!(boundaries.is_in_any(latlon(0.0, -135.0), &HashSet::from(["B", "C"])));
+        assert!(!boundaries.is_in_any(latlon(0.0, -45.0), &HashSet::from(["B", "C"])));
+    }
+
+    #[test]
+    fn containing_and_intersecting_ids() {
+        let boundaries = CountryBoundariesRef::from_slice(&BASIC).unwrap();
+        assert_eq!(HashSet::from(["A"]), boundaries.containing_ids(bbox(0.0, -170.0, 1.0, -10.0)));
+        assert!(boundaries.containing_ids(bbox(0.0, -10.0, 1.0, 10.0)).is_empty());
+        assert_eq!(
+            HashSet::from(["A", "B"]),
+            boundaries.intersecting_ids(bbox(0.0, -10.0, 1.0, 10.0))
+        );
+        assert!(boundaries.intersecting_ids(bbox(0.0, 10.0, 1.0, 20.0)).is_empty());
+    }
+}
@@ -1,84 +1,98 @@
-use std::collections::HashSet;
-
 use point::Point;
 use multipolygon::Multipolygon;
+use rect::Rect;
+use crate::RegionIndex;
 
 pub mod multipolygon;
 pub mod point;
+pub mod rect;
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 /// One cell in the country boundaries grid
 pub struct Cell {
     /// Areas that completely cover this cell
-    pub containing_ids: Vec<String>,
+    pub containing_ids: Vec<RegionIndex>,
     /// Id + Areas that only partly cover this cell
-    pub intersecting_areas: Vec<(String, Multipolygon)>
+    pub intersecting_areas: Vec<(RegionIndex, Multipolygon)>
 }
 
 impl Cell {
     /// Returns whether the given `position` is in the area with the given `id`
-    pub fn is_in(&self, point: Point, id: &str) -> bool {
-        for containing_id in self.containing_ids.iter() {
+    pub fn is_in(&self, point: Point, id: RegionIndex) -> bool {
+        for &containing_id in self.containing_ids.iter() {
             if id == containing_id { return true }
         }
-        if !self.intersecting_areas.is_empty() {
-            for country in self.intersecting_areas.iter() {
-                if id == country.0 {
-                    if country.1.covers(&point) { return true }
-                }
-            }
+        for country in self.intersecting_areas.iter() {
+            if id == country.0 && country.1.covers(&point) { return true }
         }
         false
     }
 
-    /// Returns whether the given position is in any area with the given `ids`
-    pub fn is_in_any(&self, point: Point, ids: &HashSet<&str>) -> bool {
-        for containing_id in self.containing_ids.iter() {
-            if ids.contains(containing_id.as_str()) { return true }
+    /// Returns whether the given position is in any area for whose id `is_any` returns true
+    pub fn is_in_any(&self, point: Point, is_any: impl Fn(RegionIndex) -> bool) -> bool {
+        for &containing_id in self.containing_ids.iter() {
+            if is_any(containing_id) { return true }
         }
-        if !self.intersecting_areas.is_empty() {
-            for country in self.intersecting_areas.iter() {
-                if ids.contains(country.0.as_str()) {
-                    if country.1.covers(&point) { return true }
-                }
-            }
+        for country in self.intersecting_areas.iter() {
+            if is_any(country.0) && country.1.covers(&point) { return true }
         }
         false
     }
 
     /// Return all ids of areas that cover the given `position`
-    pub fn get_ids(&self, point: Point) -> Vec<&str> {
-        let mut result: Vec<&str> = Vec::with_capacity(self.containing_ids.len());
-        result.extend(self.containing_ids.iter().map(|s| s.as_str()));
-        if !self.intersecting_areas.is_empty() {
-            for country in self.intersecting_areas.iter() {
-                if country.1.covers(&point) {
-                    result.push(country.0.as_str());
-                }
+    pub fn get_ids(&self, point: Point) -> Vec<RegionIndex> {
+        let mut result: Vec<RegionIndex> = Vec::with_capacity(self.containing_ids.len());
+        result.extend(self.containing_ids.iter().copied());
+        for country in self.intersecting_areas.iter() {
+            if country.1.covers(&point) {
+                result.push(country.0);
             }
         }
         result
     }
 
+    /// Return all ids of areas that intersect with the given rectangle
+    pub fn get_intersecting_ids(&self, rect: Rect) -> impl Iterator<Item = RegionIndex> + '_ {
+        self.containing_ids.iter().copied()
+            .chain(
+                self.intersecting_areas.iter()
+                    .filter(move |area| area.1.intersects(&rect))
+                    .map(|area| area.0)
+            )
+    }
+
+    /// Return all ids of areas that cover the whole given rectangle
+    pub fn get_covering_ids(&self, rect: Rect) -> impl Iterator<Item = RegionIndex> + '_ {
+        self.containing_ids.iter().copied()
+            .chain(
+                self.intersecting_areas.iter()
+                    .filter(move |area| area.1.covers_rect(&rect))
+                    .map(|area| area.0)
+            )
+    }
+
     /// Return all ids of areas that completely cover or partly cover this cell
-    pub fn get_all_ids(&self) -> Vec<&str> {
-        let mut result: Vec<&str> = Vec::with_capacity(self.containing_ids.len() + self.intersecting_areas.len());
-        result.extend(self.containing_ids.iter().map(|s| s.as_str()));
-        result.extend(self.intersecting_areas.iter().map(|s| s.0.as_str()));
-        result
+    pub fn get_all_ids(&self) -> impl Iterator<Item = RegionIndex> + '_ {
+        self.containing_ids.iter().copied()
+            .chain(self.intersecting_areas.iter().map(|area| area.0))
     }
 }
 
 #[cfg(test)]
 mod tests {
+    use std::collections::HashSet;
     use super::*;
 
+    const A: RegionIndex = RegionIndex(0);
+    const B: RegionIndex = RegionIndex(1);
+    const C: RegionIndex = RegionIndex(2);
+
     #[test]
     fn get_definite_ids() {
         assert_eq!(
-            vec!["A", "C"],
+            vec![A, C],
             Cell { 
-                containing_ids: vec![String::from("A"), String::from("C")],
+                containing_ids: vec![A, C],
                 intersecting_areas: vec![]
             }.get_ids(p(0,0))
         );
@@ -87,7 +101,7 @@ mod tests {
     #[test]
     fn get_in_geometry_ids() {
         assert_eq!(
-            vec!["B"],
+            vec![B],
             Cell { containing_ids: vec![], intersecting_areas: vec![b()] }.get_ids(p(1,1))
         )
     }
@@ -104,9 +118,9 @@ mod tests {
     #[test]
     fn get_definite_and_in_geometry_ids() {
         assert_eq!(
-            vec!["A", "B"],
+            vec![A, B],
             Cell {
-                containing_ids: vec![String::from("A")],
+                containing_ids: vec![A],
                 intersecting_areas: vec![b()]
             }.get_ids(p(1,1))
         );
@@ -115,11 +129,11 @@ mod tests {
     #[test]
     fn get_ally_ids() {
         assert_eq!(
-            vec!["A", "B"],
+            vec![A, B],
             Cell {
-                containing_ids: vec![String::from("A")],
+                containing_ids: vec![A],
                 intersecting_areas: vec![b()]
-            }.get_all_ids()
+            }.get_all_ids().collect::<Vec<_>>()
         );
     }
 
@@ -127,9 +141,9 @@ mod tests {
     fn is_any_definitely() {
         assert!(
             Cell {
-                containing_ids: vec![String::from("A")],
+                containing_ids: vec![A],
                 intersecting_areas: vec![]
-            }.is_in_any(p(0,0), &HashSet::from(["B", "A"]))
+            }.is_in_any(p(0,0), |id| HashSet::from([B, A]).contains(&id))
         );
     }
 
@@ -137,9 +151,9 @@ mod tests {
     fn is_any_definitely_not() {
         assert!(!
             Cell {
-                containing_ids: vec![String::from("A")],
+                containing_ids: vec![A],
                 intersecting_areas: vec![]
-            }.is_in_any(p(0,0), &HashSet::from(["B"]))
+            }.is_in_any(p(0,0), |id| HashSet::from([B]).contains(&id))
         );
     }
 
@@ -149,7 +163,7 @@ mod tests {
             Cell {
                 containing_ids: vec![],
                 intersecting_areas: vec![b()]
-            }.is_in_any(p(1,1), &HashSet::from(["B"]))
+            }.is_in_any(p(1,1), |id| id == B)
         );
     }
 
@@ -159,12 +173,12 @@ mod tests {
             Cell {
                 containing_ids: vec![],
                 intersecting_areas: vec![b()]
-            }.is_in_any(p(4,4), &HashSet::from(["B"]))
+            }.is_in_any(p(4,4), |id| id == B)
         );
     }
 
-    fn b() -> (String, Multipolygon) {
-        (String::from("B"), Multipolygon {
+    fn b() -> (RegionIndex, Multipolygon) {
+        (B, Multipolygon {
             outer: vec![vec![p(0, 0), p(0, 2), p(2, 2), p(2, 0)]],
             inner: vec![]
         })
@@ -1,4 +1,5 @@
 use crate::cell::point::Point;
+use crate::cell::rect::Rect;
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Multipolygon {
@@ -21,6 +22,27 @@ impl Multipolygon {
         }
         insides > 0
     }
+
+    /// Returns whether this multipolygon intersects with the given rectangle, including its border
+    pub fn intersects(&self, rect: &Rect) -> bool {
+        // if no edge touches the rectangle, it is either completely inside or completely outside
+        self.edges().any(|(a, b)| rect.intersects_segment(a, b)) || self.covers(&rect.min)
+    }
+
+    /// Returns whether this multipolygon covers the whole given rectangle
+    pub fn covers_rect(&self, rect: &Rect) -> bool {
+        // if no edge crosses the interior of the rectangle, it is either completely inside or
+        // completely outside. Edges on the border of the rectangle are fine, e.g. where the
+        // rectangle touches the border of the cell
+        !self.edges().any(|(a, b)| rect.interior_intersects_segment(a, b)) &&
+        self.covers(&rect.center())
+    }
+
+    /// Iterate over the edges of all rings
+    fn edges(&self) -> impl Iterator<Item = (&Point, &Point)> {
+        self.outer.iter().chain(self.inner.iter())
+            .flat_map(|ring| ring.iter().zip(ring.iter().cycle().skip(1)))
+    }
 }
 
 // modified from:
@@ -33,23 +55,27 @@ impl Multipolygon {
 // http://geomalgorithms.com/a03-_inclusion.html
 
 fn is_point_in_polygon(p: &Point, v: &[Point]) -> bool {
+    is_point_in_ring(p, v.iter().copied())
+}
+
+/// Same as `is_point_in_polygon`, but for rings that are not stored as a slice of `Point`s
+pub(crate) fn is_point_in_ring<I>(p: &Point, ring: I) -> bool
+where
+    I: IntoIterator<Item = Point>,
+    I::IntoIter: DoubleEndedIterator + Clone
+{
+    let ring = ring.into_iter();
+    let Some(mut vi) = ring.clone().next_back() else { return false };
     let mut wn = 0;
-    let mut i = v.len() - 1;
-    for j in 0 .. v.len() {
-        if v[i].y <= p.y {
-            if v[j].y > p.y {
-                if is_left(&v[i], &v[j], p) > 0 {
-                    wn += 1;
-                }
-            }
-        } else {
-            if v[j].y <= p.y {
-                if is_left(&v[i], &v[j], p) < 0 {
-                    wn -= 1;
-                }
+    for vj in ring {
+        if vi.y <= p.y {
+            if vj.y > p.y && is_left(&vi, &vj, p) > 0 {
+                wn += 1;
             }
+        } else if vj.y <= p.y && is_left(&vi, &vj, p) < 0 {
+            wn -= 1;
         }
-        i = j;
+        vi = vj;
     }
     wn != 0
 }
@@ -109,6 +135,40 @@ mod tests {
         assert!(!polygon.covers(&p(10, 10)));
     }
 
+    #[test]
+    fn intersects_rect() {
+        let polygon = Multipolygon { outer: vec![big_square()], inner: vec![hole()] };
+        let rect = |min_x, min_y, max_x, max_y| Rect { min: p(min_x, min_y), max: p(max_x, max_y) };
+
+        // crossing the outer or the inner ring
+        assert!(polygon.intersects(&rect(8, 8, 12, 12)));
+        assert!(polygon.intersects(&rect(1, 1, 3, 3)));
+        // completely inside the polygon, completely containing the polygon
+        let simple_polygon = Multipolygon { outer: vec![big_square()], inner: vec![] };
+        assert!(simple_polygon.intersects(&rect(4, 4, 6, 6)));
+        assert!(polygon.intersects(&rect(0, 0, 20, 20)));
+        // touching it
+        assert!(polygon.intersects(&rect(10, 10, 12, 12)));
+        // outside the polygon and in its hole
+        assert!(!polygon.intersects(&rect(11, 11, 20, 20)));
+        assert!(!polygon.intersects(&rect(3, 3, 7, 7)));
+    }
+
+    #[test]
+    fn covers_rect() {
+        let polygon = Multipolygon { outer: vec![big_square()], inner: vec![hole()] };
+        let rect = |min_x, min_y, max_x, max_y| Rect { min: p(min_x, min_y), max: p(max_x, max_y) };
+
+        assert!(polygon.covers_rect(&rect(0, 0, 2, 10)));
+        assert!(polygon.covers_rect(&rect(8, 2, 10, 8)));
+        // crossing the outer or the inner ring
+        assert!(!polygon.covers_rect(&rect(8, 8, 12, 12)));
+        assert!(!polygon.covers_rect(&rect(1, 1, 3, 3)));
+        // outside the polygon and in its hole
+        assert!(!polygon.covers_rect(&rect(11, 11, 20, 20)));
+        assert!(!polygon.covers_rect(&rect(3, 3, 7, 7)));
+    }
+
     fn p(x: u16, y: u16) -> Point {
         Point { x, y }
     }
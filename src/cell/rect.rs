@@ -0,0 +1,117 @@
+use crate::cell::point::Point;
+
+/// Axis-aligned rectangle in coordinates local to a cell
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Rect {
+    pub min: Point,
+    pub max: Point
+}
+
+impl Rect {
+    /// Returns whether the segment from `a` to `b` intersects with this rectangle, including its
+    /// border
+    pub fn intersects_segment(&self, a: &Point, b: &Point) -> bool {
+        // separating axis test: the rectangle and the segment do not intersect if their
+        // projections onto the x-axis, the y-axis or the normal of the segment do not overlap
+        a.x.max(b.x) >= self.min.x && a.x.min(b.x) <= self.max.x &&
+        a.y.max(b.y) >= self.min.y && a.y.min(b.y) <= self.max.y && {
+            let (left, right) = self.corner_sides(a, b);
+            left < 4 && right < 4
+        }
+    }
+
+    /// Returns whether the segment from `a` to `b` intersects with the interior of this rectangle,
+    /// i.e. the rectangle without its border
+    pub fn interior_intersects_segment(&self, a: &Point, b: &Point) -> bool {
+        a.x.max(b.x) > self.min.x && a.x.min(b.x) < self.max.x &&
+        a.y.max(b.y) > self.min.y && a.y.min(b.y) < self.max.y && {
+            let (left, right) = self.corner_sides(a, b);
+            left > 0 && right > 0
+        }
+    }
+
+    /// Returns the point in the middle of this rectangle, rounded down
+    pub fn center(&self) -> Point {
+        Point {
+            x: ((self.min.x as u32 + self.max.x as u32) / 2) as u16,
+            y: ((self.min.y as u32 + self.max.y as u32) / 2) as u16
+        }
+    }
+
+    /// Returns how many corners of this rectangle are strictly left and how many are strictly
+    /// right of the line through `a` and `b`
+    fn corner_sides(&self, a: &Point, b: &Point) -> (usize, usize) {
+        let corners = [
+            self.min,
+            Point { x: self.max.x, y: self.min.y },
+            self.max,
+            Point { x: self.min.x, y: self.max.y }
+        ];
+        let mut left = 0;
+        let mut right = 0;
+        for corner in corners.iter() {
+            // must cast to 64 because otherwise there could be an integer overflow
+            let side = (b.x as i64 - a.x as i64) * (corner.y as i64 - a.y as i64)
+                - (corner.x as i64 - a.x as i64) * (b.y as i64 - a.y as i64);
+            if side > 0 { left += 1; }
+            if side < 0 { right += 1; }
+        }
+        (left, right)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn p(x: u16, y: u16) -> Point {
+        Point { x, y }
+    }
+
+    fn rect() -> Rect {
+        Rect { min: p(10, 10), max: p(20, 20) }
+    }
+
+    #[test]
+    fn segment_intersects() {
+        // inside
+        assert!(rect().intersects_segment(&p(12, 12), &p(18, 14)));
+        // crossing
+        assert!(rect().intersects_segment(&p(0, 15), &p(30, 15)));
+        assert!(rect().intersects_segment(&p(5, 0), &p(25, 30)));
+        // touching the border or a corner
+        assert!(rect().intersects_segment(&p(0, 20), &p(30, 20)));
+        assert!(rect().intersects_segment(&p(15, 25), &p(25, 15)));
+        // single point
+        assert!(rect().intersects_segment(&p(15, 15), &p(15, 15)));
+    }
+
+    #[test]
+    fn segment_does_not_intersect() {
+        assert!(!rect().intersects_segment(&p(0, 0), &p(30, 0)));
+        assert!(!rect().intersects_segment(&p(0, 0), &p(9, 30)));
+        // bounding boxes overlap, but the segment passes the corner
+        assert!(!rect().intersects_segment(&p(16, 25), &p(25, 16)));
+    }
+
+    #[test]
+    fn segment_intersects_interior() {
+        assert!(rect().interior_intersects_segment(&p(12, 12), &p(18, 14)));
+        assert!(rect().interior_intersects_segment(&p(0, 15), &p(30, 15)));
+        assert!(rect().interior_intersects_segment(&p(10, 10), &p(20, 20)));
+    }
+
+    #[test]
+    fn segment_on_border_does_not_intersect_interior() {
+        assert!(!rect().interior_intersects_segment(&p(0, 20), &p(30, 20)));
+        assert!(!rect().interior_intersects_segment(&p(10, 12), &p(10, 18)));
+        assert!(!rect().interior_intersects_segment(&p(15, 25), &p(25, 15)));
+        assert!(!rect().interior_intersects_segment(&p(15, 15), &p(15, 15)));
+    }
+
+    #[test]
+    fn center() {
+        assert_eq!(p(15, 15), rect().center());
+        assert_eq!(p(0x7fff, 0x7fff), Rect { min: p(0, 0), max: p(0xffff, 0xffff) }.center());
+    }
+}
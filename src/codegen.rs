@@ -0,0 +1,281 @@
+//! Generate Rust code for a [`StaticCountryBoundaries`](crate::StaticCountryBoundaries) at build
+//! time, so that the boundaries are compiled into the binary as static arrays and do not need to
+//! be parsed at runtime at all.
+//!
+//! # Example
+//!
+//! In the `build.rs` of your crate, read the boundaries file and write the Rust code into
+//! `OUT_DIR`:
+//!
+//! ```no_run
+//! # use std::{env, fs, io};
+//! # use std::path::Path;
+//! # use country_boundaries::{codegen, CountryBoundaries};
+//! #
+//! # fn main() -> Result<(), Box<dyn std::error::Error>> {
+//! let buf = fs::read("data/boundaries360x180.ser")?;
+//! let boundaries = CountryBoundaries::from_reader(buf.as_slice())?;
+//! let path = Path::new(&env::var("OUT_DIR")?).join("boundaries.rs");
+//! codegen::write_rust(&boundaries, io::BufWriter::new(fs::File::create(path)?))?;
+//! println!("cargo:rerun-if-changed=data/boundaries360x180.ser");
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! The generated code is a single expression, so it can be included in a static:
+//!
+//! ```ignore
+//! use country_boundaries::{LatLon, StaticCountryBoundaries};
+//!
+//! static BOUNDARIES: StaticCountryBoundaries =
+//!     include!(concat!(env!("OUT_DIR"), "/boundaries.rs"));
+//!
+//! assert!(BOUNDARIES.is_in(LatLon::new(47.6973, 8.6910)?, "DE"));
+//! ```
+
+use std::fmt::{Debug, Display};
+use std::io;
+use std::io::{ErrorKind, Write};
+use crate::cell::point::Point;
+use crate::{CountryBoundaries, Error};
+
+/// Write the given `boundaries` as Rust code that evaluates to an equivalent
+/// [`StaticCountryBoundaries`](crate::StaticCountryBoundaries).
+///
+/// The code refers to the type by its absolute path `::country_boundaries::StaticCountryBoundaries`,
+/// so it can be included anywhere in a crate that depends on this crate.
+///
+/// This function will not buffer the output, see [`io::BufWriter`].
+pub fn write_rust(boundaries: &CountryBoundaries, mut writer: impl Write) -> io::Result<()> {
+    let parts = Parts::new(boundaries)?;
+    writeln!(writer, "// generated by country_boundaries::codegen::write_rust, do not edit")?;
+    writeln!(writer, "::country_boundaries::StaticCountryBoundaries::from_parts(")?;
+    writeln!(writer, "    {},", parts.raster_width)?;
+    write_array(&mut writer, &parts.ids, |w, id| write!(w, "{id:?}"))?;
+    write_array(&mut writer, &parts.sizes, write_f64)?;
+    write_array(&mut writer, &parts.cells, write_tuple)?;
+    write_array(&mut writer, &parts.containing_ids, |w, id| write!(w, "{id}"))?;
+    write_array(&mut writer, &parts.intersecting_areas, write_tuple)?;
+    write_array(&mut writer, &parts.rings, |w, start| write!(w, "{start}"))?;
+    write_array(&mut writer, &parts.points, write_tuple)?;
+    writeln!(writer, ")")
+}
+
+/// The arrays a StaticCountryBoundaries consists of, see its fields for what they contain
+#[derive(Debug, Default, PartialEq)]
+pub(crate) struct Parts {
+    pub raster_width: usize,
+    pub ids: Vec<String>,
+    pub sizes: Vec<f64>,
+    pub cells: Vec<[u32; 2]>,
+    pub containing_ids: Vec<u32>,
+    pub intersecting_areas: Vec<[u32; 4]>,
+    pub rings: Vec<u32>,
+    pub points: Vec<[u16; 2]>
+}
+
+impl Parts {
+    pub fn new(boundaries: &CountryBoundaries) -> io::Result<Parts> {
+        // the static id table has the same order as the id table of the boundaries, so the
+        // indices can be taken over as they are. Regions that have no geometry size are still
+        // included (with a size of 0)
+        let mut parts = Parts {
+            raster_width: boundaries.raster_width,
+            ids: boundaries.regions.iter().map(|(id, _)| id.to_string()).collect(),
+            sizes: boundaries.regions.iter().map(|(_, size)| size.unwrap_or(0.0)).collect(),
+            rings: vec![0],
+            ..Parts::default()
+        };
+
+        for cell in boundaries.raster.iter() {
+            parts.cells.push([to_u32(parts.containing_ids.len())?, to_u32(parts.intersecting_areas.len())?]);
+            for id in cell.containing_ids.iter() {
+                parts.containing_ids.push(id.0);
+            }
+            for (id, multipolygon) in cell.intersecting_areas.iter() {
+                let outer_start = to_u32(parts.rings.len() - 1)?;
+                parts.push_rings(&multipolygon.outer)?;
+                let inner_start = to_u32(parts.rings.len() - 1)?;
+                parts.push_rings(&multipolygon.inner)?;
+                let end = to_u32(parts.rings.len() - 1)?;
+                parts.intersecting_areas.push([id.0, outer_start, inner_start, end]);
+            }
+        }
+        parts.cells.push([to_u32(parts.containing_ids.len())?, to_u32(parts.intersecting_areas.len())?]);
+
+        Ok(parts)
+    }
+
+    fn push_rings(&mut self, rings: &[Vec<Point>]) -> io::Result<()> {
+        for ring in rings.iter() {
+            self.points.extend(ring.iter().map(|point| [point.x, point.y]));
+            self.rings.push(to_u32(self.points.len())?);
+        }
+        Ok(())
+    }
+}
+
+fn write_array<T>(
+    writer: &mut impl Write,
+    values: &[T],
+    write_value: impl Fn(&mut dyn Write, &T) -> io::Result<()>
+) -> io::Result<()> {
+    write!(writer, "    &[")?;
+    for (i, value) in values.iter().enumerate() {
+        if i % 16 == 0 {
+            write!(writer, "\n        ")?;
+        } else {
+            write!(writer, " ")?;
+        }
+        write_value(writer, value)?;
+        write!(writer, ",")?;
+    }
+    if !values.is_empty() {
+        write!(writer, "\n    ")?;
+    }
+    writeln!(writer, "],")
+}
+
+fn write_tuple<T: Display, const N: usize>(writer: &mut dyn Write, values: &[T; N]) -> io::Result<()> {
+    write!(writer, "[")?;
+    for (i, value) in values.iter().enumerate() {
+        if i > 0 {
+            write!(writer, ", ")?;
+        }
+        write!(writer, "{value}")?;
+    }
+    write!(writer, "]")
+}
+
+fn write_f64(writer: &mut dyn Write, value: &f64) -> io::Result<()> {
+    // the debug representation of finite floats is a valid float literal that round-trips
+    if value.is_nan() {
+        write!(writer, "f64::NAN")
+    } else if value.is_infinite() {
+        write!(writer, "{}", if *value > 0.0 { "f64::INFINITY" } else { "f64::NEG_INFINITY" })
+    } else {
+        write!(writer, "{value:?}")
+    }
+}
+
+fn to_u32<T: TryInto<u32> + Copy + Debug>(value: T) -> io::Result<u32> {
+    value.try_into().map_err(|_| io::Error::new(ErrorKind::InvalidInput,
+        Error::new(format!("Cannot write '{value:?}' as static data, it must not be greater than '{}'", u32::MAX))
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+    use crate::cell::multipolygon::Multipolygon;
+    use crate::{BoundingBox, LatLon, StaticCountryBoundaries};
+    use super::*;
+
+    /// The dataset that is generated into tests/static_boundaries.in
+    fn basic() -> CountryBoundaries {
+        basic_with_sizes(&[("A", 2.0), ("B", 0.5)])
+    }
+
+    fn basic_with_sizes(geometry_sizes: &[(&str, f64)]) -> CountryBoundaries {
+        CountryBoundaries::from_cells(
+            vec![
+                (vec!["A"], vec![]),
+                (vec![], vec![("B", Multipolygon {
+                    outer: vec![vec![p(0, 0), p(0xffff, 0), p(0, 0xffff)]],
+                    inner: vec![]
+                })])
+            ],
+            2,
+            geometry_sizes
+        )
+    }
+
+    fn p(x: u16, y: u16) -> Point {
+        Point { x, y }
+    }
+
+    fn leak(parts: Parts) -> StaticCountryBoundaries {
+        let ids: Vec<&'static str> = parts.ids.into_iter().map(|id| &*id.leak()).collect();
+        StaticCountryBoundaries::from_parts(
+            parts.raster_width,
+            ids.leak(),
+            parts.sizes.leak(),
+            parts.cells.leak(),
+            parts.containing_ids.leak(),
+            parts.intersecting_areas.leak(),
+            parts.rings.leak(),
+            parts.points.leak()
+        )
+    }
+
+    #[test]
+    fn parts_of_basic() {
+        assert_eq!(
+            Parts {
+                raster_width: 2,
+                ids: vec![String::from("A"), String::from("B")],
+                sizes: vec![2.0, 0.5],
+                cells: vec![[0, 0], [1, 0], [1, 1]],
+                containing_ids: vec![0],
+                intersecting_areas: vec![[1, 0, 1, 1]],
+                rings: vec![0, 3],
+                points: vec![[0, 0], [0xffff, 0], [0, 0xffff]]
+            },
+            Parts::new(&basic()).unwrap()
+        );
+    }
+
+    #[test]
+    fn ids_without_size_are_included() {
+        let parts = Parts::new(&basic_with_sizes(&[("A", 2.0)])).unwrap();
+        assert_eq!(vec![String::from("A"), String::from("B")], parts.ids);
+        assert_eq!(vec![2.0, 0.0], parts.sizes);
+    }
+
+    #[test]
+    fn write_basic() {
+        let mut written = Vec::new();
+        write_rust(&basic(), &mut written).unwrap();
+        assert_eq!(
+            include_str!("../tests/static_boundaries.in"),
+            String::from_utf8(written).unwrap()
+        );
+    }
+
+    #[test]
+    fn write_floats() {
+        let written = |value: f64| {
+            let mut buf = Vec::new();
+            write_f64(&mut buf, &value).unwrap();
+            String::from_utf8(buf).unwrap()
+        };
+        assert_eq!("2.0", written(2.0));
+        assert_eq!("0.1", written(0.1));
+        assert_eq!("1e-7", written(1e-7));
+        assert_eq!("f64::NAN", written(f64::NAN));
+        assert_eq!("f64::NEG_INFINITY", written(f64::NEG_INFINITY));
+    }
+
+    #[test]
+    fn static_boundaries_return_same_results() {
+        let buf = std::fs::read("./data/boundaries60x30.ser").unwrap();
+        let boundaries = CountryBoundaries::from_reader(buf.as_slice()).unwrap();
+        let static_boundaries = leak(Parts::new(&boundaries).unwrap());
+
+        for latitude in (-90..=90).step_by(3) {
+            for longitude in (-180..180).step_by(3) {
+                let latitude = latitude as f64 + 0.3;
+                let longitude = longitude as f64 + 0.7;
+                let position = LatLon::new(latitude.min(90.0), longitude).unwrap();
+                assert_eq!(boundaries.ids(position), static_boundaries.ids(position));
+                assert_eq!(boundaries.is_in(position, "US"), static_boundaries.is_in(position, "US"));
+                let ids = HashSet::from(["DE", "FR", "US-TX"]);
+                assert_eq!(boundaries.is_in_any(position, &ids), static_boundaries.is_in_any(position, &ids));
+
+                let bounds = BoundingBox::new(latitude.min(90.0), longitude, (latitude + 5.0).min(90.0), longitude + 5.0).unwrap();
+                assert_eq!(boundaries.containing_ids(bounds), static_boundaries.containing_ids(bounds));
+                assert_eq!(boundaries.intersecting_ids(bounds), static_boundaries.intersecting_ids(bounds));
+            }
+        }
+    }
+}
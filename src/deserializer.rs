@@ -1,10 +1,11 @@
-use std::collections::HashMap;
 use std::io;
 use std::io::{ErrorKind, Read};
 use crate::cell::Cell;
 use crate::cell::multipolygon::Multipolygon;
 use crate::cell::point::Point;
-use crate::{CountryBoundaries, Error};
+use crate::regions::Regions;
+use crate::validation::{check_id, check_raster, check_ring};
+use crate::{CountryBoundaries, Limit, LoadError, LoadOptions, RegionIndex};
 
 /// Deserialize a `CountryBoundaries` from an IO stream.
 ///
@@ -14,56 +15,124 @@ use crate::{CountryBoundaries, Error};
 /// When reading from a source against which short reads are not efficient, such as a [`File`],
 /// you will want to apply your own buffering because this function will not buffer the input. See
 /// [`io::BufReader`].
-pub fn from_reader(mut reader: impl Read) -> io::Result<CountryBoundaries> {
-    let version = read_u16(&mut reader)?;
+pub fn from_reader(reader: impl Read, options: &LoadOptions) -> Result<CountryBoundaries, LoadError> {
+    let mut reader = Reader { inner: reader, offset: 0, options: options.clone() };
+    let reader = &mut reader;
+
+    let version = read_u16(reader)?;
     if version != 2 {
-        return Err(io::Error::new(ErrorKind::InvalidData,
-            Error::new(format!(
-                "Wrong version number '{}' of the boundaries file (expected: '2').\
-                 You may need to get the current version of the data.", version
-            ))
-        ))
+        return Err(LoadError::UnsupportedVersion { found: version, expected: 2 })
     }
 
-    let geometry_sizes_count = read_usize32(&mut reader)?;
-    let mut geometry_sizes = HashMap::with_capacity(geometry_sizes_count);
+    let geometry_sizes_count = read_usize32(reader)?;
+    // each geometry size is at least 10 bytes long
+    let mut regions = Regions::with_capacity(reader.capacity(geometry_sizes_count, 10));
     for _ in 0..geometry_sizes_count {
-        let id = read_string(&mut reader)?;
-        let size = read_f64(&mut reader)?;
-        geometry_sizes.insert(id, size);
+        let id = read_string(reader)?;
+        let size = read_f64(reader)?;
+        regions.insert_with_size(&id, size);
     }
-    let raster_width = read_usize32(&mut reader)?;
-    let raster_size = read_usize32(&mut reader)?;
-    let mut raster = Vec::with_capacity(raster_size);
+    let raster_width = read_usize32(reader)?;
+    let raster_size_offset = reader.offset;
+    let raster_size = read_usize32(reader)?;
+    options.check_limit(raster_size_offset, Limit::Cells, raster_size)?;
+    check_raster(raster_width, raster_size)?;
+    // each cell is at least 2 bytes long
+    let mut raster = Vec::with_capacity(reader.capacity(raster_size, 2));
     for _ in 0..raster_size {
-        raster.push(read_cell(&mut reader)?);
+        let offset = reader.offset;
+        let cell = read_cell(reader, &mut regions)?;
+        if options.validate {
+            validate_cell(&cell, offset, &regions)?;
+        }
+        raster.push(cell);
+    }
+
+    Ok(CountryBoundaries::new(raster, raster_width, regions))
+}
+
+/// Validate the given `cell` that was read from the given byte `offset`
+fn validate_cell(cell: &Cell, mut offset: usize, regions: &Regions) -> Result<(), LoadError> {
+    let mut cell_ids = Vec::new();
+    offset += 1;
+    for &index in cell.containing_ids.iter() {
+        let id = regions.id(index);
+        check_id(offset, id, regions.size(index).is_some(), &mut cell_ids)?;
+        offset += 2 + id.len();
+    }
+    offset += 1;
+    for &(index, ref multipolygon) in cell.intersecting_areas.iter() {
+        let id = regions.id(index);
+        check_id(offset, id, regions.size(index).is_some(), &mut cell_ids)?;
+        offset += 2 + id.len();
+        for polygons in [&multipolygon.outer, &multipolygon.inner] {
+            offset += 1;
+            for ring in polygons.iter() {
+                check_ring(offset, ring.len())?;
+                offset += 4 + 4 * ring.len();
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Up to how many elements are allocated in advance for the number of elements given in the data,
+/// so that a file that claims to contain a huge number of elements but is actually much smaller
+/// does not lead to a huge allocation
+const MAX_PREALLOCATED: usize = 0x10000;
+
+/// Reader that keeps track of the byte offset it is at, for error reporting and to enforce the
+/// limits of the `options`
+struct Reader<R> {
+    inner: R,
+    offset: usize,
+    options: LoadOptions
+}
+
+impl<R: Read> Reader<R> {
+    fn read_exact(&mut self, buf: &mut [u8]) -> Result<(), LoadError> {
+        self.options.check_limit(self.offset, Limit::TotalBytes, self.offset.saturating_add(buf.len()))?;
+        self.inner.read_exact(buf).map_err(|e| match e.kind() {
+            ErrorKind::UnexpectedEof => LoadError::Truncated { offset: self.offset },
+            _ => LoadError::Io(e)
+        })?;
+        self.offset += buf.len();
+        Ok(())
     }
 
-    Ok(CountryBoundaries { raster, raster_width, geometry_sizes })
+    /// The capacity to allocate in advance for the given `count` of elements that are each at
+    /// least `min_bytes` long
+    fn capacity(&self, count: usize, min_bytes: usize) -> usize {
+        let remaining_bytes = self.options.max_total_bytes.saturating_sub(self.offset);
+        count.min(remaining_bytes / min_bytes).min(MAX_PREALLOCATED)
+    }
 }
 
-fn read_cell(reader: &mut impl Read) -> io::Result<Cell> {
+fn read_cell(reader: &mut Reader<impl Read>, regions: &mut Regions) -> Result<Cell, LoadError> {
     let containing_ids_size = usize::from(read_u8(reader)?);
     let mut containing_ids = Vec::with_capacity(containing_ids_size);
     for _ in 0..containing_ids_size {
-        containing_ids.push(read_string(reader)?);
+        containing_ids.push(regions.insert(&read_string(reader)?));
     }
     let intersecting_areas_size = usize::from(read_u8(reader)?);
     let mut intersecting_areas = Vec::with_capacity(intersecting_areas_size);
     for _ in 0..intersecting_areas_size {
-        intersecting_areas.push(read_areas(reader)?);
+        intersecting_areas.push(read_areas(reader, regions)?);
     }
     Ok(Cell { containing_ids, intersecting_areas })
 }
 
-fn read_areas(reader: &mut impl Read) -> io::Result<(String, Multipolygon)> {
-    let id = read_string(reader)?;
+fn read_areas(
+    reader: &mut Reader<impl Read>,
+    regions: &mut Regions
+) -> Result<(RegionIndex, Multipolygon), LoadError> {
+    let id = regions.insert(&read_string(reader)?);
     let outer = read_polygons(reader)?;
     let inner = read_polygons(reader)?;
     Ok((id, Multipolygon { outer, inner }))
 }
 
-fn read_polygons(reader: &mut impl Read) -> io::Result<Vec<Vec<Point>>> {
+fn read_polygons(reader: &mut Reader<impl Read>) -> Result<Vec<Vec<Point>>, LoadError> {
     let size = usize::from(read_u8(reader)?);
     let mut polygons: Vec<Vec<Point>> = Vec::with_capacity(size);
     for _ in 0..size {
@@ -72,137 +141,150 @@ fn read_polygons(reader: &mut impl Read) -> io::Result<Vec<Vec<Point>>> {
     Ok(polygons)
 }
 
-fn read_ring(reader: &mut impl Read) -> io::Result<Vec<Point>> {
+fn read_ring(reader: &mut Reader<impl Read>) -> Result<Vec<Point>, LoadError> {
+    let offset = reader.offset;
     let size = read_usize32(reader)?;
-    let mut ring = Vec::with_capacity(size);
+    reader.options.check_limit(offset, Limit::RingLength, size)?;
+    let mut ring = Vec::with_capacity(reader.capacity(size, 4));
     for _ in 0..size {
         ring.push(read_point(reader)?);
     }
     Ok(ring)
 }
 
-fn read_point(reader: &mut impl Read) -> io::Result<Point> {
+fn read_point(reader: &mut Reader<impl Read>) -> Result<Point, LoadError> {
     let x = read_u16(reader)?;
     let y = read_u16(reader)?;
     Ok(Point { x, y })
 }
 
-fn read_u8(reader: &mut impl Read) -> io::Result<u8> {
+fn read_u8(reader: &mut Reader<impl Read>) -> Result<u8, LoadError> {
     let mut buf = [0; 1];
     reader.read_exact(&mut buf)?;
     Ok(u8::from_be_bytes(buf))
 }
 
-fn read_u16(reader: &mut impl Read) -> io::Result<u16> {
+fn read_u16(reader: &mut Reader<impl Read>) -> Result<u16, LoadError> {
     let mut buf = [0; 2];
     reader.read_exact(&mut buf)?;
     Ok(u16::from_be_bytes(buf))
 }
 
-fn read_u32(reader: &mut impl Read) -> io::Result<u32> {
+fn read_u32(reader: &mut Reader<impl Read>) -> Result<u32, LoadError> {
     let mut buf = [0; 4];
     reader.read_exact(&mut buf)?;
     Ok(u32::from_be_bytes(buf))
 }
 
-fn read_usize32(reader: &mut impl Read) -> io::Result<usize> {
-    usize::try_from(read_u32(reader)?).map_err(|e| io::Error::new(ErrorKind::Unsupported, e))
+fn read_usize32(reader: &mut Reader<impl Read>) -> Result<usize, LoadError> {
+    usize::try_from(read_u32(reader)?)
+        .map_err(|e| LoadError::Io(io::Error::new(ErrorKind::Unsupported, e)))
 }
 
-fn read_f64(reader: &mut impl Read) -> io::Result<f64> {
+fn read_f64(reader: &mut Reader<impl Read>) -> Result<f64, LoadError> {
     let mut buf = [0; 8];
     reader.read_exact(&mut buf)?;
     Ok(f64::from_be_bytes(buf))
 }
 
-fn read_string(reader: &mut impl Read) -> io::Result<String> {
+fn read_string(reader: &mut Reader<impl Read>) -> Result<String, LoadError> {
+    let offset = reader.offset;
     let length = usize::from(read_u16(reader)?);
+    reader.options.check_limit(offset, Limit::IdLength, length)?;
     let mut vec: Vec<u8> = vec![0; length];
     reader.read_exact(vec.as_mut_slice())?;
-    String::from_utf8(vec).map_err(|e| io::Error::new(ErrorKind::InvalidData, e))
+    String::from_utf8(vec).map_err(|e| LoadError::InvalidUtf8 {
+        offset: reader.offset - length + e.utf8_error().valid_up_to()
+    })
 }
 
 
 #[cfg(test)]
 mod tests {
+    use std::collections::HashSet;
+    use crate::BoundingBox;
     use super::*;
 
+    fn reader(data: &[u8]) -> Reader<&[u8]> {
+        Reader { inner: data, offset: 0, options: LoadOptions::default() }
+    }
+
     #[test]
     fn test_read_string() {
-        assert!(read_string(&mut [0x00].as_slice()).is_err());
-        assert!(read_string(&mut [0x00, 0x01].as_slice()).is_err());
-        assert!(read_string(&mut [0x00, 0x02, 0x41].as_slice()).is_err());
+        assert!(read_string(&mut reader(&[0x00])).is_err());
+        assert!(read_string(&mut reader(&[0x00, 0x01])).is_err());
+        assert!(read_string(&mut reader(&[0x00, 0x02, 0x41])).is_err());
 
-        assert!(read_string(&mut [0x00, 0x00].as_slice()).unwrap().is_empty());
-        assert_eq!("A", read_string(&mut [0x00, 0x01, 0x41].as_slice()).unwrap());
-        assert_eq!("AB", read_string(&mut [0x00, 0x02, 0x41, 0x42].as_slice()).unwrap());
+        assert!(read_string(&mut reader(&[0x00, 0x00])).unwrap().is_empty());
+        assert_eq!("A", read_string(&mut reader(&[0x00, 0x01, 0x41])).unwrap());
+        assert_eq!("AB", read_string(&mut reader(&[0x00, 0x02, 0x41, 0x42])).unwrap());
     }
 
     #[test]
     fn read_float() {
-        assert!(read_f64(&mut [0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00].as_slice()).is_err());
+        assert!(read_f64(&mut reader(&[0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00])).is_err());
         
         assert_eq!(
             12.5, 
-            read_f64(&mut [0x40, 0x29, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00].as_slice()).unwrap()
+            read_f64(&mut reader(&[0x40, 0x29, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00])).unwrap()
         );
     }
 
     #[test]
     fn test_read_u8() {
-        assert!(read_u8(&mut [].as_slice()).is_err());
+        assert!(read_u8(&mut reader(&[])).is_err());
         
-        assert_eq!(17, read_u8(&mut [0x11].as_slice()).unwrap());
-        assert_eq!(u8::MIN, read_u8(&mut [0x00].as_slice()).unwrap());
-        assert_eq!(u8::MAX, read_u8(&mut [0xff].as_slice()).unwrap());
+        assert_eq!(17, read_u8(&mut reader(&[0x11])).unwrap());
+        assert_eq!(u8::MIN, read_u8(&mut reader(&[0x00])).unwrap());
+        assert_eq!(u8::MAX, read_u8(&mut reader(&[0xff])).unwrap());
     }
 
     #[test]
     fn test_read_u16() {
-        assert!(read_u16(&mut [0x00].as_slice()).is_err());
+        assert!(read_u16(&mut reader(&[0x00])).is_err());
 
-        assert_eq!(17, read_u16(&mut [0x00, 0x11].as_slice()).unwrap());
-        assert_eq!(u16::MIN, read_u16(&mut [0x00, 0x00].as_slice()).unwrap());
-        assert_eq!(u16::MAX, read_u16(&mut [0xff, 0xff].as_slice()).unwrap());
+        assert_eq!(17, read_u16(&mut reader(&[0x00, 0x11])).unwrap());
+        assert_eq!(u16::MIN, read_u16(&mut reader(&[0x00, 0x00])).unwrap());
+        assert_eq!(u16::MAX, read_u16(&mut reader(&[0xff, 0xff])).unwrap());
     }
 
     #[test]
     fn test_read_u32() {
-        assert!(read_u32(&mut [0x00, 0x00, 0x00].as_slice()).is_err());
+        assert!(read_u32(&mut reader(&[0x00, 0x00, 0x00])).is_err());
 
-        assert_eq!(17, read_u32(&mut [0x00, 0x00, 0x00, 0x11].as_slice()).unwrap());
-        assert_eq!(u32::MIN, read_u32(&mut [0x00, 0x00, 0x00, 0x00].as_slice()).unwrap());
-        assert_eq!(u32::MAX, read_u32(&mut [0xff, 0xff, 0xff, 0xff].as_slice()).unwrap());
+        assert_eq!(17, read_u32(&mut reader(&[0x00, 0x00, 0x00, 0x11])).unwrap());
+        assert_eq!(u32::MIN, read_u32(&mut reader(&[0x00, 0x00, 0x00, 0x00])).unwrap());
+        assert_eq!(u32::MAX, read_u32(&mut reader(&[0xff, 0xff, 0xff, 0xff])).unwrap());
     }
 
     #[test]
     fn test_read_usize32() {
-        assert!(read_usize32(&mut [0x00, 0x00, 0x00].as_slice()).is_err());
+        assert!(read_usize32(&mut reader(&[0x00, 0x00, 0x00])).is_err());
 
-        assert_eq!(17, read_usize32(&mut [0x00, 0x00, 0x00, 0x11].as_slice()).unwrap());
-        assert_eq!(0, read_usize32(&mut [0x00, 0x00, 0x00, 0x00].as_slice()).unwrap());
-        assert_eq!(0xffff, read_usize32(&mut [0x00, 0x00, 0xff, 0xff].as_slice()).unwrap());
+        assert_eq!(17, read_usize32(&mut reader(&[0x00, 0x00, 0x00, 0x11])).unwrap());
+        assert_eq!(0, read_usize32(&mut reader(&[0x00, 0x00, 0x00, 0x00])).unwrap());
+        assert_eq!(0xffff, read_usize32(&mut reader(&[0x00, 0x00, 0xff, 0xff])).unwrap());
     }
 
     #[test]
     #[cfg(target_pointer_width = "16")]
     fn read_usize32_on_16_bit_machines_results_in_error_if_number_too_big() {
-        assert!(read_usize32(&mut [0x00, 0xff, 0xff, 0xff].as_slice()).is_err());
+        assert!(read_usize32(&mut reader(&[0x00, 0xff, 0xff, 0xff])).is_err());
     }
 
     #[test]
     fn test_read_point() {
         assert_eq!(
             Point {x: 1, y: 2} ,
-            read_point(&mut [0x00, 0x01, 0x00, 0x02].as_slice()).unwrap()
+            read_point(&mut reader(&[0x00, 0x01, 0x00, 0x02])).unwrap()
         );
     }
 
     #[test]
     fn test_read_ring() {
         let empty = [0x00, 0x00, 0x00, 0x00];
-        for i in 0..empty.len() - 1 { assert!(read_ring(&mut &empty[0..i]).is_err()); }
-        assert!(read_ring(&mut empty.as_slice()).unwrap().is_empty());
+        for i in 0..empty.len() - 1 { assert!(read_ring(&mut reader(&empty[0..i])).is_err()); }
+        assert!(read_ring(&mut reader(&empty)).unwrap().is_empty());
 
         let two_points = [
             0x00, 0x00, 0x00, 0x02, // length
@@ -211,16 +293,16 @@ mod tests {
             0x00, 0x03,             // p2.x
             0x00, 0x04              // p2.y
         ];
-        for i in 0..two_points.len() - 1 { assert!(read_ring(&mut &two_points[0..i]).is_err()); }
+        for i in 0..two_points.len() - 1 { assert!(read_ring(&mut reader(&two_points[0..i])).is_err()); }
         assert_eq!(
             vec![Point {x: 1, y: 2}, Point {x: 3, y: 4}],
-            read_ring(&mut two_points.as_slice()).unwrap()
+            read_ring(&mut reader(&two_points)).unwrap()
         );
     }
 
     #[test]
     fn test_read_polygons() {
-        assert!(read_polygons(&mut [0x00].as_slice()).unwrap().is_empty());
+        assert!(read_polygons(&mut reader(&[0x00])).unwrap().is_empty());
         
         let two_rings = [
             0x02,                   // polygons length
@@ -231,10 +313,10 @@ mod tests {
             0x00, 0x03,             // p2.x
             0x00, 0x04              // p2.y
         ];
-        for i in 0..two_rings.len() - 1 { assert!(read_polygons(&mut &two_rings[0..i]).is_err()); }
+        for i in 0..two_rings.len() - 1 { assert!(read_polygons(&mut reader(&two_rings[0..i])).is_err()); }
         assert_eq!(
             vec![vec![Point {x: 1, y: 2}], vec![Point {x: 3, y: 4}]],
-            read_polygons(&mut two_rings.as_slice()).unwrap()
+            read_polygons(&mut reader(&two_rings)).unwrap()
         );
     }
 
@@ -242,7 +324,7 @@ mod tests {
     fn test_read_cell() {
         assert_eq!(
             Cell { containing_ids: vec![], intersecting_areas: vec![] },
-            read_cell(&mut [0x00, 0x00].as_slice()).unwrap()
+            read_cell(&mut reader(&[0x00, 0x00]), &mut Regions::default()).unwrap()
         );
         
         let cell = [
@@ -252,15 +334,15 @@ mod tests {
             0x00, 0x01, 0x42, // "B"
             0x00, 0x00        // empty multipolygon
         ];
-        for i in 0..cell.len() - 1 { assert!(read_polygons(&mut &cell[0..i]).is_err()); }
+        for i in 0..cell.len() - 1 { assert!(read_polygons(&mut reader(&cell[0..i])).is_err()); }
         assert_eq!(
             Cell { 
-                containing_ids: vec![String::from("A")],
+                containing_ids: vec![RegionIndex(0)],
                 intersecting_areas: vec![
-                    (String::from("B"), Multipolygon { inner: vec![], outer: vec![] })
+                    (RegionIndex(1), Multipolygon { inner: vec![], outer: vec![] })
                 ]
             },
-            read_cell(&mut cell.as_slice()).unwrap()
+            read_cell(&mut reader(&cell), &mut Regions::default()).unwrap()
         );
     }
 
@@ -272,7 +354,7 @@ mod tests {
             0x00, 0x00, 0x00, 0x00,                         // raster width
             0x00, 0x00, 0x00, 0x00,                         // raster size
         ];
-        assert!(from_reader(&mut minimum.as_slice()).is_err());
+        assert!(from_reader(minimum.as_slice(), &LoadOptions::default()).is_err());
     }
 
     #[test]
@@ -280,16 +362,50 @@ mod tests {
         let minimum = [
             0x00, 0x02,             // version number
             0x00, 0x00, 0x00, 0x00, // geometry sizes map length
-            0x00, 0x00, 0x00, 0x00, // raster width
-            0x00, 0x00, 0x00, 0x00, // raster size
+            0x00, 0x00, 0x00, 0x01, // raster width
+            0x00, 0x00, 0x00, 0x01, // raster size
+            0x00,                   // cell containing ids length
+            0x00,                   // intersecting areas length
         ];
-        for i in 0..minimum.len() - 1 { assert!(from_reader(&mut &minimum[0..i]).is_err()); }
+        for i in 0..minimum.len() - 1 { assert!(from_reader(&minimum[0..i], &LoadOptions::default()).is_err()); }
         assert_eq!(
-            CountryBoundaries { raster: vec![], raster_width: 0, geometry_sizes: HashMap::new() },
-            from_reader(&mut minimum.as_slice()).unwrap()
+            CountryBoundaries::from_cells(vec![(vec![], vec![])], 1, &[]),
+            from_reader(minimum.as_slice(), &LoadOptions::default()).unwrap()
         );
     }
 
+    #[test]
+    fn empty_raster_error() {
+        let data = [
+            0x00, 0x02,             // version number
+            0x00, 0x00, 0x00, 0x00, // geometry sizes map length
+            0x00, 0x00, 0x00, 0x01, // raster width
+            0x00, 0x00, 0x00, 0x00, // raster size
+        ];
+        assert!(matches!(
+            from_reader(data.as_slice(), &LoadOptions::default()),
+            Err(LoadError::EmptyRaster)
+        ));
+        assert!(matches!(
+            from_reader(data.as_slice(), &LoadOptions { validate: false, ..LoadOptions::default() }),
+            Err(LoadError::EmptyRaster)
+        ));
+    }
+
+    #[test]
+    fn zero_raster_width_error() {
+        let data = [
+            0x00, 0x02,             // version number
+            0x00, 0x00, 0x00, 0x00, // geometry sizes map length
+            0x00, 0x00, 0x00, 0x00, // raster width
+            0x00, 0x00, 0x00, 0x00, // raster size
+        ];
+        assert!(matches!(
+            from_reader(data.as_slice(), &LoadOptions::default()),
+            Err(LoadError::EmptyRaster)
+        ));
+    }
+
     #[test]
     fn test_read_basic() {
         let basic = [
@@ -303,17 +419,198 @@ mod tests {
             0x00, 0x01, 0x41,                               // "A"
             0x00,                                           // intersecting areas length
         ];
-        for i in 0..basic.len() - 1 { assert!(from_reader(&mut &basic[0..i]).is_err()); }
+        for i in 0..basic.len() - 1 { assert!(from_reader(&basic[0..i], &LoadOptions::default()).is_err()); }
         assert_eq!(
-            CountryBoundaries { 
-                raster: vec![Cell { 
-                    containing_ids: vec![String::from("A")],
-                    intersecting_areas: vec![]
-                }],
-                raster_width: 1,
-                geometry_sizes: HashMap::from([(String::from("A"), 12.5)])
-            },
-            from_reader(&mut basic.as_slice()).unwrap()
+            CountryBoundaries::from_cells(vec![(vec!["A"], vec![])], 1, &[("A", 12.5)]),
+            from_reader(basic.as_slice(), &LoadOptions::default()).unwrap()
         );
     }
+
+    #[test]
+    fn wrong_version_error() {
+        let data = [0x00, 0x03];
+        assert!(matches!(
+            from_reader(data.as_slice(), &LoadOptions::default()),
+            Err(LoadError::UnsupportedVersion { found: 3, expected: 2 })
+        ));
+    }
+
+    #[test]
+    fn truncated_error_has_offset_of_incomplete_value() {
+        let data = [
+            0x00, 0x02,             // version number
+            0x00, 0x00, 0x00, 0x00, // geometry sizes map length
+            0x00, 0x00,             // raster width, incomplete
+        ];
+        assert!(matches!(
+            from_reader(data.as_slice(), &LoadOptions::default()),
+            Err(LoadError::Truncated { offset: 6 })
+        ));
+    }
+
+    #[test]
+    fn invalid_utf8_error_has_offset_of_invalid_byte() {
+        let data = [
+            0x00, 0x02,             // version number
+            0x00, 0x00, 0x00, 0x01, // geometry sizes map length
+            0x00, 0x02, 0x41, 0xff, // "A" followed by an invalid byte
+        ];
+        assert!(matches!(
+            from_reader(data.as_slice(), &LoadOptions::default()),
+            Err(LoadError::InvalidUtf8 { offset: 9 })
+        ));
+    }
+
+    #[test]
+    fn inconsistent_raster_error() {
+        let data = [
+            0x00, 0x02,             // version number
+            0x00, 0x00, 0x00, 0x00, // geometry sizes map length
+            0x00, 0x00, 0x00, 0x02, // raster width
+            0x00, 0x00, 0x00, 0x03, // raster size
+        ];
+        assert!(matches!(
+            from_reader(data.as_slice(), &LoadOptions::default()),
+            Err(LoadError::InconsistentRaster { width: 2, size: 3 })
+        ));
+
+        let data = [
+            0x00, 0x02,             // version number
+            0x00, 0x00, 0x00, 0x00, // geometry sizes map length
+            0x00, 0x00, 0x00, 0x00, // raster width
+            0x00, 0x00, 0x00, 0x01, // raster size
+        ];
+        assert!(matches!(
+            from_reader(data.as_slice(), &LoadOptions::default()),
+            Err(LoadError::InconsistentRaster { width: 0, size: 1 })
+        ));
+    }
+
+    #[test]
+    fn io_error_is_passed_on() {
+        struct FailingReader;
+        impl Read for FailingReader {
+            fn read(&mut self, _: &mut [u8]) -> io::Result<usize> {
+                Err(io::Error::from(ErrorKind::PermissionDenied))
+            }
+        }
+        assert!(matches!(
+            from_reader(FailingReader, &LoadOptions::default()),
+            Err(LoadError::Io(e)) if e.kind() == ErrorKind::PermissionDenied
+        ));
+    }
+
+    /// boundaries with one cell that contains "A" and intersects with a "C" that has no size and
+    /// one ring of only one point
+    const INVALID: [u8; 54] = [
+        0x00, 0x02,                                     // version number
+        0x00, 0x00, 0x00, 0x02,                         // geometry sizes map length
+        0x00, 0x01, 0x41,                               // "A"
+        0x40, 0x29, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, // 12.5
+        0x00, 0x01, 0x42,                               // "B"
+        0x40, 0x24, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, // 10.0
+        0x00, 0x00, 0x00, 0x01,                         // raster width
+        0x00, 0x00, 0x00, 0x01,                         // raster size
+        0x01,                                           // cell containing ids length
+        0x00, 0x01, 0x41,                               // "A"
+        0x01,                                           // intersecting areas length
+        0x00, 0x01, 0x43,                               // "C"
+        0x01,                                           // outer polygons length
+        0x00, 0x00, 0x00, 0x01,                         // ring length
+        0x00, 0x01, 0x00, 0x02,                         // 1,2
+        0x00,                                           // inner polygons length
+    ];
+
+    #[test]
+    fn validate_unknown_id() {
+        assert!(matches!(
+            from_reader(INVALID.as_slice(), &LoadOptions::default()),
+            Err(LoadError::UnknownId { offset: 41, id }) if id == "C"
+        ));
+    }
+
+    #[test]
+    fn validate_duplicate_id() {
+        let mut data = INVALID;
+        data[43] = 0x41; // "C" -> "A"
+        assert!(matches!(
+            from_reader(data.as_slice(), &LoadOptions::default()),
+            Err(LoadError::DuplicateId { offset: 41, id }) if id == "A"
+        ));
+    }
+
+    #[test]
+    fn validate_ring() {
+        let mut data = INVALID;
+        data[43] = 0x42; // "C" -> "B"
+        assert!(matches!(
+            from_reader(data.as_slice(), &LoadOptions::default()),
+            Err(LoadError::InvalidRing { offset: 45, length: 1 })
+        ));
+    }
+
+    #[test]
+    fn read_invalid_without_validation() {
+        let options = LoadOptions { validate: false, ..LoadOptions::default() };
+        let boundaries = from_reader(INVALID.as_slice(), &options).unwrap();
+        assert_eq!(
+            HashSet::from(["A", "C"]),
+            boundaries.intersecting_ids(BoundingBox::new(0.0, 0.0, 1.0, 1.0).unwrap())
+        );
+    }
+
+    #[test]
+    fn huge_counts_in_small_file_are_error() {
+        let data = [
+            0x00, 0x02,             // version number
+            0x00, 0x00, 0x00, 0x00, // geometry sizes map length
+            0x00, 0x00, 0x00, 0x01, // raster width
+            0xff, 0xff, 0xff, 0xff, // raster size
+        ];
+        assert!(matches!(
+            from_reader(data.as_slice(), &LoadOptions::default()),
+            Err(LoadError::Truncated { offset: 14 })
+        ));
+
+        let data = [
+            0x00, 0x02,             // version number
+            0xff, 0xff, 0xff, 0xff, // geometry sizes map length
+        ];
+        assert!(matches!(
+            from_reader(data.as_slice(), &LoadOptions::default()),
+            Err(LoadError::Truncated { offset: 6 })
+        ));
+
+        assert!(matches!(
+            read_ring(&mut reader(&[0xff, 0xff, 0xff, 0xff])),
+            Err(LoadError::Truncated { offset: 4 })
+        ));
+    }
+
+    #[test]
+    fn limits() {
+        let options = LoadOptions { max_total_bytes: 53, ..LoadOptions::default() };
+        assert!(matches!(
+            from_reader(INVALID.as_slice(), &options),
+            Err(LoadError::LimitExceeded { offset: 53, limit: Limit::TotalBytes, value: 54, max: 53 })
+        ));
+
+        let options = LoadOptions { max_cells: 0, ..LoadOptions::default() };
+        assert!(matches!(
+            from_reader(INVALID.as_slice(), &options),
+            Err(LoadError::LimitExceeded { offset: 32, limit: Limit::Cells, value: 1, max: 0 })
+        ));
+
+        let options = LoadOptions { max_id_length: 0, ..LoadOptions::default() };
+        assert!(matches!(
+            from_reader(INVALID.as_slice(), &options),
+            Err(LoadError::LimitExceeded { offset: 6, limit: Limit::IdLength, value: 1, max: 0 })
+        ));
+
+        let options = LoadOptions { validate: false, max_ring_length: 0, ..LoadOptions::default() };
+        assert!(matches!(
+            from_reader(INVALID.as_slice(), &options),
+            Err(LoadError::LimitExceeded { offset: 45, limit: Limit::RingLength, value: 1, max: 0 })
+        ));
+    }
 }
@@ -0,0 +1,62 @@
+use std::sync::OnceLock;
+use crate::CountryBoundaries;
+
+/// Defines a function on `CountryBoundaries` that returns the given embedded default dataset,
+/// which is deserialized once on first use
+macro_rules! embedded_boundaries {
+    ($feature: literal, $name: ident, $file: literal) => {
+        impl CountryBoundaries {
+            #[doc = concat!(
+                "Returns the default boundaries dataset `", $file, "` that is embedded in the ",
+                "binary with the `", $feature, "` feature.\n\n",
+                "It is deserialized on the first call, subsequent calls return the same instance."
+            )]
+            pub fn $name() -> &'static CountryBoundaries {
+                static BOUNDARIES: OnceLock<CountryBoundaries> = OnceLock::new();
+                BOUNDARIES.get_or_init(|| {
+                    let buf: &[u8] = include_bytes!(concat!("../data/", $file));
+                    CountryBoundaries::from_reader(buf).expect("embedded boundaries are valid")
+                })
+            }
+        }
+    };
+}
+
+#[cfg(feature = "embed-360x180")]
+embedded_boundaries!("embed-360x180", default_360x180, "boundaries360x180.ser");
+#[cfg(feature = "embed-180x90")]
+embedded_boundaries!("embed-180x90", default_180x90, "boundaries180x90.ser");
+#[cfg(feature = "embed-60x30")]
+embedded_boundaries!("embed-60x30", default_60x30, "boundaries60x30.ser");
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    #[cfg(feature = "embed-360x180")]
+    fn default_360x180() {
+        let buf = std::fs::read("./data/boundaries360x180.ser").unwrap();
+        let boundaries = CountryBoundaries::default_360x180();
+        assert_eq!(&CountryBoundaries::from_reader(buf.as_slice()).unwrap(), boundaries);
+        assert!(std::ptr::eq(boundaries, CountryBoundaries::default_360x180()));
+    }
+
+    #[test]
+    #[cfg(feature = "embed-180x90")]
+    fn default_180x90() {
+        let buf = std::fs::read("./data/boundaries180x90.ser").unwrap();
+        let boundaries = CountryBoundaries::default_180x90();
+        assert_eq!(&CountryBoundaries::from_reader(buf.as_slice()).unwrap(), boundaries);
+        assert!(std::ptr::eq(boundaries, CountryBoundaries::default_180x90()));
+    }
+
+    #[test]
+    #[cfg(feature = "embed-60x30")]
+    fn default_60x30() {
+        let buf = std::fs::read("./data/boundaries60x30.ser").unwrap();
+        let boundaries = CountryBoundaries::default_60x30();
+        assert_eq!(&CountryBoundaries::from_reader(buf.as_slice()).unwrap(), boundaries);
+        assert!(std::ptr::eq(boundaries, CountryBoundaries::default_60x30()));
+    }
+}
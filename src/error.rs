@@ -1,3 +1,6 @@
+use std::io;
+use crate::Limit;
+
 #[derive(Debug, Clone)]
 pub struct Error {
     message: String
@@ -16,3 +19,93 @@ impl std::fmt::Display for Error {
 }
 
 impl std::error::Error for Error {}
+
+/// Error that occurs when loading a boundaries file
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum LoadError {
+    /// The file has a version of the file format that is not supported
+    UnsupportedVersion { found: u16, expected: u16 },
+    /// The data ended in the middle of the value that starts at the byte `offset`
+    Truncated { offset: usize },
+    /// The string at the byte `offset` is not valid UTF-8
+    InvalidUtf8 { offset: usize },
+    /// The raster `size` is not a multiple of the raster `width`
+    InconsistentRaster { width: usize, size: usize },
+    /// The raster has no cells, so no position could be looked up in it
+    EmptyRaster,
+    /// The ring at the byte `offset` has only `length` points, less than the 3 of a polygon
+    InvalidRing { offset: usize, length: usize },
+    /// The `id` at the byte `offset` has no geometry size
+    UnknownId { offset: usize, id: String },
+    /// The `id` at the byte `offset` occurs more than once in the same cell
+    DuplicateId { offset: usize, id: String },
+    /// The `value` at the byte `offset` exceeds the given `limit` of the
+    /// [`LoadOptions`](crate::LoadOptions), which is `max`
+    LimitExceeded { offset: usize, limit: Limit, value: usize, max: usize },
+    /// Reading the data failed
+    Io(io::Error)
+}
+
+impl std::fmt::Display for LoadError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LoadError::UnsupportedVersion { found, expected } => write!(f,
+                "Wrong version number '{found}' of the boundaries file (expected: '{expected}'). \
+                 You may need to get the current version of the data."
+            ),
+            LoadError::Truncated { offset } => write!(f,
+                "The boundaries file ended unexpectedly in the value at byte {offset}"
+            ),
+            LoadError::InvalidUtf8 { offset } => write!(f,
+                "Invalid UTF-8 at byte {offset} of the boundaries file"
+            ),
+            LoadError::InconsistentRaster { width, size } => write!(f,
+                "The raster size '{size}' of the boundaries file is not a multiple of its width '{width}'"
+            ),
+            LoadError::EmptyRaster => write!(f,
+                "The raster of the boundaries file has no cells"
+            ),
+            LoadError::InvalidRing { offset, length } => write!(f,
+                "The ring at byte {offset} of the boundaries file has only {length} points"
+            ),
+            LoadError::UnknownId { offset, id } => write!(f,
+                "The id '{id}' at byte {offset} of the boundaries file has no geometry size"
+            ),
+            LoadError::DuplicateId { offset, id } => write!(f,
+                "The id '{id}' at byte {offset} of the boundaries file occurs more than once in the same cell"
+            ),
+            LoadError::LimitExceeded { offset, limit, value, max } => write!(f,
+                "The value '{value}' at byte {offset} of the boundaries file exceeds the limit \
+                 {limit:?} of '{max}'"
+            ),
+            LoadError::Io(e) => write!(f, "Reading the boundaries file failed: {e}")
+        }
+    }
+}
+
+impl std::error::Error for LoadError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            LoadError::Io(e) => Some(e),
+            _ => None
+        }
+    }
+}
+
+impl From<io::Error> for LoadError {
+    fn from(e: io::Error) -> Self {
+        LoadError::Io(e)
+    }
+}
+
+impl From<LoadError> for io::Error {
+    fn from(e: LoadError) -> Self {
+        match e {
+            LoadError::Io(e) => e,
+            LoadError::Truncated { .. } => io::Error::new(io::ErrorKind::UnexpectedEof, e),
+            _ => io::Error::new(io::ErrorKind::InvalidData, e)
+        }
+    }
+}
+
@@ -0,0 +1,502 @@
+//! Generate a `CountryBoundaries` dataset from polygons given in degrees.
+//!
+//! This is a Rust replacement for the Java shell application in the `/generator/` folder of the
+//! [Java project](https://github.com/westnordost/countryboundaries). The boundaries can be read
+//! from GeoJSON with [`geojson::read`] or, with the `osm` feature, from OSM XML with `osm::read`.
+//! The world is divided into a
+//! raster of cells, each region is clipped to every cell it intersects and stored in cell-local
+//! coordinates. The result can then be written with
+//! [`CountryBoundaries::to_writer`](crate::CountryBoundaries::to_writer).
+//!
+//! # Example
+//! ```
+//! # use country_boundaries::{CountryBoundaries, LatLon};
+//! # use country_boundaries::generator::{generate, Boundary};
+//! #
+//! # fn main() -> Result<(), Box<dyn std::error::Error>> {
+//! let square = vec![
+//!     LatLon::new(10.0, 10.0)?, LatLon::new(10.0, 20.0)?,
+//!     LatLon::new(20.0, 20.0)?, LatLon::new(20.0, 10.0)?
+//! ];
+//! let boundaries = generate(&[Boundary::new("A", vec![square], vec![])], 360, 180)?;
+//!
+//! assert!(boundaries.is_in(LatLon::new(15.0, 15.0)?, "A"));
+//! assert!(!boundaries.is_in(LatLon::new(25.0, 15.0)?, "A"));
+//! # Ok(())
+//! # }
+//! ```
+
+use std::collections::HashMap;
+use crate::cell::Cell;
+use crate::cell::multipolygon::Multipolygon;
+use crate::cell::point::Point;
+use crate::regions::Regions;
+use crate::{CountryBoundaries, Error, LatLon};
+
+pub mod geojson;
+#[cfg(feature = "osm")]
+pub mod osm;
+
+/// A region with an id and its geometry, given in degrees.
+///
+/// Rings may or may not repeat the first position at the end. Rings that cross the 180th meridian
+/// are split there, i.e. each edge is taken to go the shorter way around the world.
+#[derive(Debug, Clone)]
+pub struct Boundary {
+    pub id: String,
+    pub outer: Vec<Vec<LatLon>>,
+    pub inner: Vec<Vec<LatLon>>
+}
+
+impl Boundary {
+    pub fn new(id: &str, outer: Vec<Vec<LatLon>>, inner: Vec<Vec<LatLon>>) -> Boundary {
+        Boundary { id: String::from(id), outer, inner }
+    }
+}
+
+/// Generate a `CountryBoundaries` with a raster of `raster_width` x `raster_height` cells from the
+/// given `boundaries`.
+///
+/// Boundaries that share the same id are merged into one region. The size stored for each region
+/// is its area in square degrees.
+///
+/// Returns an error if the raster is empty or if a cell would contain more areas than can be
+/// stored in the file format.
+pub fn generate(
+    boundaries: &[Boundary],
+    raster_width: usize,
+    raster_height: usize
+) -> Result<CountryBoundaries, Error> {
+    if raster_width == 0 || raster_height == 0 {
+        return Err(Error::new(format!(
+            "raster size {raster_width}x{raster_height} must not be empty"
+        )))
+    }
+
+    let merged = merge_by_id(boundaries);
+
+    let mut regions = Regions::with_capacity(merged.len());
+    let mut raster: Vec<Cell> = (0..raster_width * raster_height)
+        .map(|_| Cell { containing_ids: vec![], intersecting_areas: vec![] })
+        .collect();
+
+    for (id, outer, inner) in merged.iter() {
+        let index = regions.insert_with_size(id, area(outer) - area(inner));
+        let Some(bounds) = Bounds::of(outer) else { continue };
+
+        let min_x = cell_x(bounds.min_x, raster_width);
+        let max_x = cell_x(bounds.max_x, raster_width);
+        let min_y = cell_y(bounds.max_y, raster_height);
+        let max_y = cell_y(bounds.min_y, raster_height);
+
+        for y in min_y..=max_y {
+            let row = Bounds {
+                min_x: f64::NEG_INFINITY,
+                min_y: 90.0 - 180.0 * (y + 1) as f64 / raster_height as f64,
+                max_x: f64::INFINITY,
+                max_y: 90.0 - 180.0 * y as f64 / raster_height as f64,
+            };
+            let row_outer = clip_all(outer, &row);
+            if row_outer.is_empty() { continue }
+            let row_inner = clip_all(inner, &row);
+
+            for x in min_x..=max_x {
+                let cell_bounds = Bounds {
+                    min_x: -180.0 + 360.0 * x as f64 / raster_width as f64,
+                    max_x: -180.0 + 360.0 * (x + 1) as f64 / raster_width as f64,
+                    ..row
+                };
+                let cell_outer = clip_all(&row_outer, &cell_bounds);
+                if cell_outer.is_empty() { continue }
+                let cell_inner = clip_all(&row_inner, &cell_bounds);
+
+                let cell = &mut raster[y * raster_width + x];
+                let cell_area = cell_bounds.area();
+                let covered_area = area(&cell_outer) - area(&cell_inner);
+                if covered_area >= cell_area * (1.0 - EPSILON) {
+                    cell.containing_ids.push(index);
+                } else if covered_area > cell_area * EPSILON {
+                    let multipolygon = Multipolygon {
+                        outer: to_local_rings(&cell_outer, &cell_bounds),
+                        inner: to_local_rings(&cell_inner, &cell_bounds),
+                    };
+                    if !multipolygon.outer.is_empty() {
+                        cell.intersecting_areas.push((index, multipolygon));
+                    }
+                }
+            }
+        }
+    }
+
+    for cell in raster.iter() {
+        let too_many = cell.containing_ids.len().max(cell.intersecting_areas.len());
+        if too_many > u8::MAX as usize {
+            return Err(Error::new(format!(
+                "a cell must not contain more than {} areas, but has {too_many}", u8::MAX
+            )))
+        }
+    }
+
+    Ok(CountryBoundaries::new(raster, raster_width, regions))
+}
+
+/// Relative tolerance below which a cell is considered not or fully covered by a region
+const EPSILON: f64 = 1e-9;
+
+/// A ring in degrees as x = longitude, y = latitude
+type Ring = Vec<(f64, f64)>;
+
+fn merge_by_id(boundaries: &[Boundary]) -> Vec<(&str, Vec<Ring>, Vec<Ring>)> {
+    let mut regions: Vec<(&str, Vec<Ring>, Vec<Ring>)> = Vec::new();
+    let mut indices: HashMap<&str, usize> = HashMap::new();
+    for boundary in boundaries.iter() {
+        let index = *indices.entry(boundary.id.as_str()).or_insert_with(|| {
+            regions.push((boundary.id.as_str(), vec![], vec![]));
+            regions.len() - 1
+        });
+        let region = &mut regions[index];
+        region.1.extend(boundary.outer.iter().flat_map(|ring| split_at_180th_meridian(to_ring(ring))));
+        region.2.extend(boundary.inner.iter().flat_map(|ring| split_at_180th_meridian(to_ring(ring))));
+    }
+    regions
+}
+
+fn to_ring(positions: &[LatLon]) -> Ring {
+    let mut ring: Ring = positions.iter().map(|p| (p.longitude(), p.latitude())).collect();
+    if ring.len() > 1 && ring.first() == ring.last() {
+        ring.pop();
+    }
+    ring
+}
+
+/// Split the given ring into the parts west and east of the 180th meridian, if it crosses it.
+/// Rings that go around a pole are left as they are, as they don't have any side to split into.
+fn split_at_180th_meridian(ring: Ring) -> Vec<Ring> {
+    // make the longitudes continuous, so that a ring that crosses the 180th meridian goes beyond ±180
+    let mut continuous: Ring = Vec::with_capacity(ring.len());
+    for &(x, y) in ring.iter() {
+        let x = match continuous.last() {
+            Some(&(previous_x, _)) => previous_x + normalize(x - previous_x),
+            None => x
+        };
+        continuous.push((x, y));
+    }
+    let (Some(first), Some(last)) = (continuous.first(), continuous.last()) else { return vec![ring] };
+    if (last.0 - first.0).abs() > 180.0 {
+        return vec![ring];
+    }
+    let Some(bounds) = Bounds::of(std::slice::from_ref(&continuous)) else { return vec![ring] };
+    let min_shift = ((bounds.min_x + 180.0) / 360.0).floor() as i32;
+    let max_shift = ((bounds.max_x - 180.0) / 360.0).ceil() as i32;
+    if min_shift >= 0 && max_shift <= 0 {
+        return vec![ring];
+    }
+
+    let world = Bounds { min_x: -180.0, min_y: f64::NEG_INFINITY, max_x: 180.0, max_y: f64::INFINITY };
+    (min_shift..=max_shift)
+        .map(|shift| {
+            let shifted: Ring = continuous.iter().map(|&(x, y)| (x - 360.0 * shift as f64, y)).collect();
+            clip(&shifted, &world)
+        })
+        .filter(|ring| ring.len() >= 3)
+        .collect()
+}
+
+/// Normalize the given longitude difference to -180..180
+fn normalize(delta: f64) -> f64 {
+    (delta + 180.0).rem_euclid(360.0) - 180.0
+}
+
+fn cell_x(longitude: f64, raster_width: usize) -> usize {
+    let x = ((raster_width as f64) * (180.0 + longitude) / 360.0).floor();
+    (x.max(0.0) as usize).min(raster_width - 1)
+}
+
+fn cell_y(latitude: f64, raster_height: usize) -> usize {
+    let y = ((raster_height as f64) * (90.0 - latitude) / 180.0).floor();
+    (y.max(0.0) as usize).min(raster_height - 1)
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+struct Bounds {
+    min_x: f64,
+    min_y: f64,
+    max_x: f64,
+    max_y: f64
+}
+
+impl Bounds {
+    fn of(rings: &[Ring]) -> Option<Bounds> {
+        let mut points = rings.iter().flatten();
+        let &(x, y) = points.next()?;
+        let mut bounds = Bounds { min_x: x, min_y: y, max_x: x, max_y: y };
+        for &(x, y) in points {
+            bounds.min_x = bounds.min_x.min(x);
+            bounds.min_y = bounds.min_y.min(y);
+            bounds.max_x = bounds.max_x.max(x);
+            bounds.max_y = bounds.max_y.max(y);
+        }
+        Some(bounds)
+    }
+
+    fn area(&self) -> f64 {
+        (self.max_x - self.min_x) * (self.max_y - self.min_y)
+    }
+}
+
+/// Sum of the absolute areas of the given rings
+fn area(rings: &[Ring]) -> f64 {
+    rings.iter().map(|ring| signed_area(ring).abs()).sum()
+}
+
+fn signed_area(ring: &[(f64, f64)]) -> f64 {
+    let Some(&last) = ring.last() else { return 0.0 };
+    let mut sum = 0.0;
+    let mut previous = last;
+    for &point in ring.iter() {
+        sum += previous.0 * point.1 - point.0 * previous.1;
+        previous = point;
+    }
+    sum / 2.0
+}
+
+fn clip_all(rings: &[Ring], bounds: &Bounds) -> Vec<Ring> {
+    rings.iter()
+        .map(|ring| clip(ring, bounds))
+        .filter(|ring| ring.len() >= 3)
+        .collect()
+}
+
+/// Clip the given ring to the given bounds with the Sutherland–Hodgman algorithm. For concave
+/// rings, the result may contain degenerate edges along the bounds, which do not change its area
+/// nor which points it covers.
+fn clip(ring: &[(f64, f64)], bounds: &Bounds) -> Ring {
+    let ring = clip_edge(ring, |p| p.0 >= bounds.min_x, |a, b| at_x(a, b, bounds.min_x));
+    let ring = clip_edge(&ring, |p| p.0 <= bounds.max_x, |a, b| at_x(a, b, bounds.max_x));
+    let ring = clip_edge(&ring, |p| p.1 >= bounds.min_y, |a, b| at_y(a, b, bounds.min_y));
+    clip_edge(&ring, |p| p.1 <= bounds.max_y, |a, b| at_y(a, b, bounds.max_y))
+}
+
+fn clip_edge(
+    ring: &[(f64, f64)],
+    is_inside: impl Fn(&(f64, f64)) -> bool,
+    intersection: impl Fn(&(f64, f64), &(f64, f64)) -> (f64, f64)
+) -> Ring {
+    let mut result = Vec::with_capacity(ring.len());
+    let Some(mut previous) = ring.last() else { return result };
+    for current in ring.iter() {
+        match (is_inside(previous), is_inside(current)) {
+            (true, true) => result.push(*current),
+            (true, false) => result.push(intersection(previous, current)),
+            (false, true) => {
+                result.push(intersection(previous, current));
+                result.push(*current);
+            },
+            (false, false) => {}
+        }
+        previous = current;
+    }
+    result
+}
+
+fn at_x(a: &(f64, f64), b: &(f64, f64), x: f64) -> (f64, f64) {
+    (x, a.1 + (b.1 - a.1) * (x - a.0) / (b.0 - a.0))
+}
+
+fn at_y(a: &(f64, f64), b: &(f64, f64), y: f64) -> (f64, f64) {
+    (a.0 + (b.0 - a.0) * (y - a.1) / (b.1 - a.1), y)
+}
+
+fn to_local_rings(rings: &[Ring], bounds: &Bounds) -> Vec<Vec<Point>> {
+    rings.iter()
+        .map(|ring| to_local_ring(ring, bounds))
+        .filter(|ring| ring.len() >= 3)
+        .collect()
+}
+
+fn to_local_ring(ring: &[(f64, f64)], bounds: &Bounds) -> Vec<Point> {
+    let mut result: Vec<Point> = Vec::with_capacity(ring.len());
+    for &(x, y) in ring.iter() {
+        let point = Point {
+            x: to_local(x, bounds.min_x, bounds.max_x),
+            y: to_local(y, bounds.min_y, bounds.max_y)
+        };
+        if result.last() != Some(&point) {
+            result.push(point);
+        }
+    }
+    while result.len() > 1 && result.first() == result.last() {
+        result.pop();
+    }
+    result
+}
+
+fn to_local(value: f64, min: f64, max: f64) -> u16 {
+    ((value - min) / (max - min) * 0xffff as f64).round().clamp(0.0, 0xffff as f64) as u16
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ring(coordinates: &[(f64, f64)]) -> Vec<LatLon> {
+        coordinates.iter().map(|&(lon, lat)| LatLon::new(lat, lon).unwrap()).collect()
+    }
+
+    fn latlon(latitude: f64, longitude: f64) -> LatLon {
+        LatLon::new(latitude, longitude).unwrap()
+    }
+
+    fn square(min: f64, max: f64) -> Ring {
+        vec![(min, min), (max, min), (max, max), (min, max)]
+    }
+
+    #[test]
+    fn clip_square() {
+        let bounds = Bounds { min_x: 0.0, min_y: 0.0, max_x: 2.0, max_y: 2.0 };
+        assert_eq!(4, clip(&square(1.0, 3.0), &bounds).len());
+        assert_eq!(1.0, signed_area(&clip(&square(1.0, 3.0), &bounds)));
+        assert_eq!(square(0.0, 2.0).len(), clip(&square(-1.0, 3.0), &bounds).len());
+        assert_eq!(4.0, signed_area(&clip(&square(-1.0, 3.0), &bounds)));
+        assert!(clip(&square(3.0, 4.0), &bounds).is_empty());
+    }
+
+    #[test]
+    fn area_of_ring() {
+        assert_eq!(4.0, signed_area(&square(0.0, 2.0)));
+        assert_eq!(-4.0, signed_area(&square(0.0, 2.0).into_iter().rev().collect::<Ring>()));
+        assert_eq!(0.0, signed_area(&[]));
+    }
+
+    #[test]
+    fn closing_position_is_removed() {
+        assert_eq!(
+            vec![(0.0, 0.0), (1.0, 0.0), (1.0, 1.0)],
+            to_ring(&ring(&[(0.0, 0.0), (1.0, 0.0), (1.0, 1.0), (0.0, 0.0)]))
+        );
+    }
+
+    #[test]
+    fn empty_raster_is_error() {
+        assert!(generate(&[], 0, 1).is_err());
+        assert!(generate(&[], 1, 0).is_err());
+    }
+
+    #[test]
+    fn generate_containing_and_intersecting_cells() {
+        // the world:
+        // ┌─┬─┬─┬─┐
+        // │ │ │ │ │
+        // ├─┼─┼─┼─┤
+        // │ │A│ │ │
+        // └─┴─┴─┴─┘
+        // A covers the cell -90..0, -90..0 and half of its neighbour to the east
+        let a = Boundary::new(
+            "A",
+            vec![ring(&[(-90.0, -90.0), (45.0, -90.0), (45.0, 0.0), (-90.0, 0.0)])],
+            vec![]
+        );
+        let boundaries = generate(&[a], 4, 2).unwrap();
+
+        assert_eq!(8, boundaries.raster.len());
+        let a = boundaries.id_index("A").unwrap();
+        assert_eq!(vec![a], boundaries.raster[5].containing_ids);
+        assert!(boundaries.raster[5].intersecting_areas.is_empty());
+        assert!(boundaries.raster[6].containing_ids.is_empty());
+        assert_eq!(1, boundaries.raster[6].intersecting_areas.len());
+        for i in [0, 1, 2, 3, 4, 7] {
+            assert!(boundaries.raster[i].get_all_ids().next().is_none());
+        }
+        assert_eq!(Some(12150.0), boundaries.regions.size(boundaries.id_index("A").unwrap()));
+
+        assert_eq!(vec!["A"], boundaries.ids(latlon(-45.0, -45.0)));
+        assert_eq!(vec!["A"], boundaries.ids(latlon(-45.0, 30.0)));
+        assert!(boundaries.ids(latlon(-45.0, 60.0)).is_empty());
+        assert!(boundaries.ids(latlon(45.0, -45.0)).is_empty());
+    }
+
+    #[test]
+    fn generate_hole() {
+        let a = Boundary::new(
+            "A",
+            vec![ring(&[(0.0, 0.0), (10.0, 0.0), (10.0, 10.0), (0.0, 10.0)])],
+            vec![ring(&[(4.0, 4.0), (6.0, 4.0), (6.0, 6.0), (4.0, 6.0)])]
+        );
+        let boundaries = generate(&[a], 360, 180).unwrap();
+
+        assert_eq!(Some(96.0), boundaries.regions.size(boundaries.id_index("A").unwrap()));
+        assert!(boundaries.is_in(latlon(3.5, 3.5), "A"));
+        assert!(boundaries.is_in(latlon(4.5, 3.5), "A"));
+        assert!(!boundaries.is_in(latlon(4.5, 4.5), "A"));
+        assert!(!boundaries.is_in(latlon(5.5, 5.5), "A"));
+        assert!(boundaries.is_in(latlon(6.5, 6.5), "A"));
+        assert!(!boundaries.is_in(latlon(10.5, 6.5), "A"));
+    }
+
+    #[test]
+    fn ring_across_180th_meridian_is_split() {
+        let ring = split_at_180th_meridian(vec![(178.0, 0.0), (-178.0, 0.0), (-178.0, 2.0), (178.0, 2.0)]);
+        assert_eq!(2, ring.len());
+        assert_eq!(8.0, signed_area(&ring[0]) + signed_area(&ring[1]));
+
+        // not crossing or going around the pole
+        let ring = vec![(10.0, 0.0), (20.0, 0.0), (20.0, 2.0)];
+        assert_eq!(vec![ring.clone()], split_at_180th_meridian(ring));
+        let ring = vec![(-180.0, -90.0), (-180.0, -80.0), (0.0, -70.0), (180.0, -80.0), (180.0, -90.0)];
+        assert_eq!(vec![ring.clone()], split_at_180th_meridian(ring));
+    }
+
+    #[test]
+    fn generate_across_180th_meridian() {
+        // an island around the 180th meridian, with a lagoon that crosses it, too
+        let a = Boundary::new(
+            "A",
+            vec![ring(&[(177.0, -18.0), (-177.0, -18.0), (-177.0, -15.0), (177.0, -15.0)])],
+            vec![ring(&[(179.5, -17.0), (-179.5, -17.0), (-179.5, -16.0), (179.5, -16.0)])]
+        );
+        let boundaries = generate(&[a], 360, 180).unwrap();
+
+        assert_eq!(Some(17.0), boundaries.regions.size(boundaries.id_index("A").unwrap()));
+        for longitude in [177.5, 178.5, 179.9, -179.9, -178.5, -177.5] {
+            assert_eq!(vec!["A"], boundaries.ids(latlon(-17.5, longitude)));
+        }
+        assert!(boundaries.ids(latlon(-16.5, 179.9)).is_empty());
+        assert!(boundaries.ids(latlon(-16.5, -179.9)).is_empty());
+        assert!(boundaries.ids(latlon(-17.5, 176.5)).is_empty());
+        assert!(boundaries.ids(latlon(-17.5, -176.5)).is_empty());
+        assert!(boundaries.ids(latlon(-17.5, 0.0)).is_empty());
+    }
+
+    #[test]
+    fn boundaries_with_same_id_are_merged() {
+        let boundaries = generate(&[
+            Boundary::new("A", vec![ring(&[(0.0, 0.0), (1.0, 0.0), (1.0, 1.0), (0.0, 1.0)])], vec![]),
+            Boundary::new("A", vec![ring(&[(2.0, 0.0), (3.0, 0.0), (3.0, 1.0), (2.0, 1.0)])], vec![]),
+        ], 360, 180).unwrap();
+
+        assert_eq!(Some(2.0), boundaries.regions.size(boundaries.id_index("A").unwrap()));
+        assert!(boundaries.is_in(latlon(0.5, 0.5), "A"));
+        assert!(!boundaries.is_in(latlon(0.5, 1.5), "A"));
+        assert!(boundaries.is_in(latlon(0.5, 2.5), "A"));
+    }
+
+    #[test]
+    fn generated_dataset_survives_round_trip() {
+        let a = Boundary::new(
+            "A",
+            vec![ring(&[(-10.3, -5.7), (12.1, -3.2), (8.8, 14.9), (-4.4, 9.1)])],
+            vec![]
+        );
+        let boundaries = generate(&[a], 60, 30).unwrap();
+
+        let mut buf = Vec::new();
+        boundaries.to_writer(&mut buf).unwrap();
+        let read = CountryBoundaries::from_reader(buf.as_slice()).unwrap();
+
+        assert_eq!(boundaries, read);
+        assert!(read.is_in(latlon(0.0, 0.0), "A"));
+        assert!(read.is_in(latlon(-5.0, -9.0), "A"));
+        assert!(!read.is_in(latlon(14.0, -4.0), "A"));
+        assert!(!read.is_in(latlon(-6.0, 0.0), "A"));
+    }
+}
@@ -0,0 +1,221 @@
+use std::io;
+use std::io::{ErrorKind, Read};
+use serde_json::Value;
+use crate::generator::Boundary;
+use crate::{Error, LatLon};
+
+/// Read the boundaries from a GeoJSON `FeatureCollection`.
+///
+/// The id of each boundary is taken from the feature property with the name `id_property`.
+/// Features without such a property and features that are not a `Polygon` or `MultiPolygon` are
+/// skipped.
+///
+/// # Example
+/// ```
+/// # use country_boundaries::generator::{generate, geojson};
+/// # use country_boundaries::LatLon;
+/// #
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let json = r#"{
+///   "type": "FeatureCollection",
+///   "features": [{
+///     "type": "Feature",
+///     "properties": { "iso": "A" },
+///     "geometry": {
+///       "type": "Polygon",
+///       "coordinates": [[[10, 10], [20, 10], [20, 20], [10, 20], [10, 10]]]
+///     }
+///   }]
+/// }"#;
+/// let boundaries = generate(&geojson::read(json.as_bytes(), "iso")?, 360, 180)?;
+///
+/// assert!(boundaries.is_in(LatLon::new(15.0, 15.0)?, "A"));
+/// # Ok(())
+/// # }
+/// ```
+pub fn read(reader: impl Read, id_property: &str) -> io::Result<Vec<Boundary>> {
+    let json: Value = serde_json::from_reader(reader)?;
+    let features = json.get("features")
+        .and_then(Value::as_array)
+        .ok_or_else(|| invalid_data("expected a FeatureCollection with 'features'"))?;
+
+    let mut boundaries = Vec::with_capacity(features.len());
+    for feature in features.iter() {
+        let id = feature.get("properties")
+            .and_then(|properties| properties.get(id_property))
+            .and_then(Value::as_str);
+        let Some(id) = id else { continue };
+        let Some(geometry) = feature.get("geometry") else { continue };
+
+        match geometry.get("type").and_then(Value::as_str) {
+            Some("Polygon") => {
+                let (outer, inner) = read_polygon(coordinates(geometry)?)?;
+                boundaries.push(Boundary::new(id, vec![outer], inner));
+            },
+            Some("MultiPolygon") => {
+                let polygons = as_array(coordinates(geometry)?)?;
+                let mut boundary = Boundary::new(id, Vec::with_capacity(polygons.len()), vec![]);
+                for polygon in polygons.iter() {
+                    let (outer, inner) = read_polygon(polygon)?;
+                    boundary.outer.push(outer);
+                    boundary.inner.extend(inner);
+                }
+                boundaries.push(boundary);
+            },
+            _ => {}
+        }
+    }
+    Ok(boundaries)
+}
+
+fn coordinates(geometry: &Value) -> io::Result<&Value> {
+    geometry.get("coordinates").ok_or_else(|| invalid_data("geometry without 'coordinates'"))
+}
+
+fn read_polygon(value: &Value) -> io::Result<(Vec<LatLon>, Vec<Vec<LatLon>>)> {
+    let mut rings = as_array(value)?.iter();
+    let outer = read_ring(rings.next().ok_or_else(|| invalid_data("polygon without rings"))?)?;
+    let inner = rings.map(read_ring).collect::<io::Result<Vec<_>>>()?;
+    Ok((outer, inner))
+}
+
+fn read_ring(value: &Value) -> io::Result<Vec<LatLon>> {
+    as_array(value)?.iter().map(read_position).collect()
+}
+
+fn read_position(value: &Value) -> io::Result<LatLon> {
+    let position = as_array(value)?;
+    let longitude = position.first().and_then(Value::as_f64);
+    let latitude = position.get(1).and_then(Value::as_f64);
+    let (Some(longitude), Some(latitude)) = (longitude, latitude) else {
+        return Err(invalid_data("position must consist of at least two numbers"))
+    };
+    LatLon::new(latitude, longitude).map_err(|e| io::Error::new(ErrorKind::InvalidData, e))
+}
+
+fn as_array(value: &Value) -> io::Result<&Vec<Value>> {
+    value.as_array().ok_or_else(|| invalid_data("expected an array of coordinates"))
+}
+
+fn invalid_data(message: &str) -> io::Error {
+    io::Error::new(ErrorKind::InvalidData, Error::new(format!("Invalid GeoJSON: {message}")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn latlon(latitude: f64, longitude: f64) -> LatLon {
+        LatLon::new(latitude, longitude).unwrap()
+    }
+
+    fn feature_collection(features: &str) -> String {
+        format!(r#"{{ "type": "FeatureCollection", "features": [{features}] }}"#)
+    }
+
+    #[test]
+    fn read_polygon_with_hole() {
+        let json = feature_collection(r#"{
+            "type": "Feature",
+            "properties": { "id": "A" },
+            "geometry": {
+                "type": "Polygon",
+                "coordinates": [
+                    [[0, 0], [10, 0], [10, 10], [0, 10], [0, 0]],
+                    [[4, 4], [6, 4], [6, 6], [4, 4]]
+                ]
+            }
+        }"#);
+        let boundaries = read(json.as_bytes(), "id").unwrap();
+
+        assert_eq!(1, boundaries.len());
+        assert_eq!("A", boundaries[0].id);
+        assert_eq!(1, boundaries[0].outer.len());
+        assert_eq!(5, boundaries[0].outer[0].len());
+        assert_eq!(1, boundaries[0].inner.len());
+        assert_eq!(10.0, boundaries[0].outer[0][1].longitude());
+        assert_eq!(0.0, boundaries[0].outer[0][1].latitude());
+    }
+
+    #[test]
+    fn read_multipolygon() {
+        let json = feature_collection(r#"{
+            "type": "Feature",
+            "properties": { "id": "A" },
+            "geometry": {
+                "type": "MultiPolygon",
+                "coordinates": [
+                    [[[0, 0], [1, 0], [1, 1], [0, 0]]],
+                    [[[2, 0], [3, 0], [3, 1], [2, 0]], [[2.1, 0.1], [2.2, 0.1], [2.2, 0.2]]]
+                ]
+            }
+        }"#);
+        let boundaries = read(json.as_bytes(), "id").unwrap();
+
+        assert_eq!(1, boundaries.len());
+        assert_eq!(2, boundaries[0].outer.len());
+        assert_eq!(1, boundaries[0].inner.len());
+    }
+
+    #[test]
+    fn skip_features_without_id_or_polygon() {
+        let json = feature_collection(r#"
+            {
+                "type": "Feature",
+                "properties": { "name": "A" },
+                "geometry": { "type": "Polygon", "coordinates": [[[0, 0], [1, 0], [1, 1]]] }
+            },
+            {
+                "type": "Feature",
+                "properties": { "id": "B" },
+                "geometry": { "type": "Point", "coordinates": [0, 0] }
+            },
+            {
+                "type": "Feature",
+                "properties": { "id": "C" },
+                "geometry": null
+            }
+        "#);
+        assert!(read(json.as_bytes(), "id").unwrap().is_empty());
+    }
+
+    #[test]
+    fn return_errors() {
+        assert!(read("".as_bytes(), "id").is_err());
+        assert!(read("{}".as_bytes(), "id").is_err());
+        assert!(read(feature_collection(r#"{
+            "type": "Feature",
+            "properties": { "id": "A" },
+            "geometry": { "type": "Polygon", "coordinates": [[[0, 91], [1, 0], [1, 1]]] }
+        }"#).as_bytes(), "id").is_err());
+        assert!(read(feature_collection(r#"{
+            "type": "Feature",
+            "properties": { "id": "A" },
+            "geometry": { "type": "Polygon", "coordinates": [[[0], [1, 0], [1, 1]]] }
+        }"#).as_bytes(), "id").is_err());
+        assert!(read(feature_collection(r#"{
+            "type": "Feature",
+            "properties": { "id": "A" },
+            "geometry": { "type": "Polygon", "coordinates": [] }
+        }"#).as_bytes(), "id").is_err());
+    }
+
+    #[test]
+    fn generate_from_geojson() {
+        let json = feature_collection(r#"{
+            "type": "Feature",
+            "properties": { "id": "A" },
+            "geometry": {
+                "type": "Polygon",
+                "coordinates": [[[5.5, 5.5], [7.5, 5.5], [7.5, 7.5], [5.5, 7.5], [5.5, 5.5]]]
+            }
+        }"#);
+        let boundaries = crate::generator::generate(&read(json.as_bytes(), "id").unwrap(), 360, 180)
+            .unwrap();
+
+        assert_eq!(vec!["A"], boundaries.ids(latlon(6.5, 6.5)));
+        assert_eq!(vec!["A"], boundaries.ids(latlon(5.6, 5.6)));
+        assert!(boundaries.ids(latlon(5.4, 5.6)).is_empty());
+        assert!(boundaries.ids(latlon(7.6, 7.4)).is_empty());
+    }
+}
@@ -0,0 +1,283 @@
+use std::collections::HashMap;
+use std::io;
+use std::io::{ErrorKind, Read};
+use roxmltree::{Document, Node};
+use crate::generator::Boundary;
+use crate::{Error, LatLon};
+
+/// Read the boundaries from an [OSM XML](https://wiki.openstreetmap.org/wiki/OSM_XML) file, such
+/// as the [`boundaries.osm`](https://josm.openstreetmap.de/export/HEAD/josm/trunk/resources/data/boundaries.osm)
+/// from the JOSM project the default data is generated from.
+///
+/// Closed ways and multipolygon relations are read as boundaries if they are tagged with
+/// `ISO3166-1:alpha2` or, otherwise, `ISO3166-2`, which is then used as the id. The member ways of
+/// a relation are joined into rings, members with the role `inner` become the inner rings.
+///
+/// # Example
+/// ```no_run
+/// # use std::fs::File;
+/// # use std::io::{BufReader, BufWriter};
+/// # use country_boundaries::generator::{generate, osm};
+/// #
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let boundaries = osm::read(BufReader::new(File::open("boundaries.osm")?))?;
+/// generate(&boundaries, 360, 180)?
+///     .to_writer(BufWriter::new(File::create("boundaries360x180.ser")?))?;
+/// # Ok(())
+/// # }
+/// ```
+pub fn read(mut reader: impl Read) -> io::Result<Vec<Boundary>> {
+    let mut xml = String::new();
+    reader.read_to_string(&mut xml)?;
+    let document = Document::parse(&xml).map_err(|e| io::Error::new(ErrorKind::InvalidData, e))?;
+
+    let elements: Vec<Node> = document.root_element().children()
+        .filter(|node| node.is_element() && node.attribute("action") != Some("delete"))
+        .collect();
+
+    let mut nodes: HashMap<i64, LatLon> = HashMap::new();
+    for node in elements.iter().filter(|node| node.has_tag_name("node")) {
+        let latitude = parse_attribute(node, "lat")?;
+        let longitude = parse_attribute(node, "lon")?;
+        let position = LatLon::new(latitude, longitude)
+            .map_err(|e| io::Error::new(ErrorKind::InvalidData, e))?;
+        nodes.insert(parse_attribute(node, "id")?, position);
+    }
+
+    let mut ways: HashMap<i64, Vec<i64>> = HashMap::new();
+    let mut boundaries = Vec::new();
+    for way in elements.iter().filter(|node| node.has_tag_name("way")) {
+        let node_ids = way.children()
+            .filter(|node| node.has_tag_name("nd"))
+            .map(|nd| parse_attribute(&nd, "ref"))
+            .collect::<io::Result<Vec<i64>>>()?;
+
+        if let Some(id) = boundary_id(way) {
+            if node_ids.len() < 4 || node_ids.first() != node_ids.last() {
+                return Err(invalid_data(format!(
+                    "way {} with id '{id}' is not closed", element_id(way)
+                )))
+            }
+            boundaries.push(Boundary::new(id, vec![positions(&node_ids, &nodes)?], vec![]));
+        }
+        ways.insert(parse_attribute(way, "id")?, node_ids);
+    }
+
+    for relation in elements.iter().filter(|node| node.has_tag_name("relation")) {
+        let Some(id) = boundary_id(relation) else { continue };
+        let mut outer: Vec<&[i64]> = Vec::new();
+        let mut inner: Vec<&[i64]> = Vec::new();
+        for member in relation.children().filter(|node| node.has_tag_name("member")) {
+            if member.attribute("type") != Some("way") { continue }
+            let way_id: i64 = parse_attribute(&member, "ref")?;
+            let way = ways.get(&way_id).ok_or_else(|| invalid_data(format!(
+                "relation {} references missing way {way_id}", element_id(relation)
+            )))?;
+            match member.attribute("role") {
+                Some("inner") => inner.push(way),
+                _ => outer.push(way),
+            }
+        }
+        let to_rings = |ways: Vec<&[i64]>| -> io::Result<Vec<Vec<LatLon>>> {
+            join_rings(ways)
+                .ok_or_else(|| invalid_data(format!(
+                    "relation {} with id '{id}' has rings that are not closed", element_id(relation)
+                )))?
+                .iter()
+                .map(|ring| positions(ring, &nodes))
+                .collect()
+        };
+        boundaries.push(Boundary::new(id, to_rings(outer)?, to_rings(inner)?));
+    }
+
+    Ok(boundaries)
+}
+
+fn boundary_id<'a>(element: &Node<'a, '_>) -> Option<&'a str> {
+    let tags: HashMap<&str, &str> = element.children()
+        .filter(|node| node.has_tag_name("tag"))
+        .filter_map(|tag| Some((tag.attribute("k")?, tag.attribute("v")?)))
+        .collect();
+    tags.get("ISO3166-1:alpha2").or_else(|| tags.get("ISO3166-2")).copied()
+}
+
+/// Join the given ways into closed rings by connecting them at their first and last nodes.
+/// Returns `None` if not all ways can be joined into closed rings.
+fn join_rings(ways: Vec<&[i64]>) -> Option<Vec<Vec<i64>>> {
+    let mut remaining = ways;
+    let mut rings = Vec::new();
+    while let Some(first) = remaining.pop() {
+        let mut ring = first.to_vec();
+        while ring.len() < 2 || ring.first() != ring.last() {
+            let end = *ring.last()?;
+            let index = remaining.iter()
+                .position(|way| way.first() == Some(&end) || way.last() == Some(&end))?;
+            let way = remaining.swap_remove(index);
+            if way.first() == Some(&end) {
+                ring.extend(way.iter().skip(1));
+            } else {
+                ring.extend(way.iter().rev().skip(1));
+            }
+        }
+        rings.push(ring);
+    }
+    Some(rings)
+}
+
+fn positions(node_ids: &[i64], nodes: &HashMap<i64, LatLon>) -> io::Result<Vec<LatLon>> {
+    node_ids.iter()
+        .map(|id| nodes.get(id).copied().ok_or_else(|| invalid_data(format!("missing node {id}"))))
+        .collect()
+}
+
+fn parse_attribute<T: std::str::FromStr>(node: &Node, name: &str) -> io::Result<T> {
+    node.attribute(name)
+        .and_then(|value| value.parse().ok())
+        .ok_or_else(|| invalid_data(format!(
+            "<{}> has no valid attribute '{name}'", node.tag_name().name()
+        )))
+}
+
+fn element_id<'a>(node: &Node<'a, '_>) -> &'a str {
+    node.attribute("id").unwrap_or("?")
+}
+
+fn invalid_data(message: String) -> io::Error {
+    io::Error::new(ErrorKind::InvalidData, Error::new(format!("Invalid OSM XML: {message}")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn latlon(latitude: f64, longitude: f64) -> LatLon {
+        LatLon::new(latitude, longitude).unwrap()
+    }
+
+    fn osm(elements: &str) -> String {
+        format!(r#"<?xml version='1.0' encoding='UTF-8'?>
+            <osm version='0.6' generator='JOSM'>
+              <node id='-1' lat='0.0' lon='0.0' />
+              <node id='-2' lat='0.0' lon='10.0' />
+              <node id='-3' lat='10.0' lon='10.0' />
+              <node id='-4' lat='10.0' lon='0.0' />
+              <node id='-5' lat='4.0' lon='4.0' />
+              <node id='-6' lat='4.0' lon='6.0' />
+              <node id='-7' lat='6.0' lon='6.0' />
+              <node id='-8' lat='6.0' lon='4.0' />
+              {elements}
+            </osm>"#)
+    }
+
+    #[test]
+    fn read_closed_way() {
+        let xml = osm(r#"
+            <way id='-10'>
+              <nd ref='-1' /><nd ref='-2' /><nd ref='-3' /><nd ref='-4' /><nd ref='-1' />
+              <tag k='ISO3166-1:alpha2' v='AA' />
+              <tag k='ISO3166-2' v='XX-AA' />
+            </way>
+            <way id='-11'>
+              <nd ref='-5' /><nd ref='-6' /><nd ref='-7' /><nd ref='-8' /><nd ref='-5' />
+              <tag k='ISO3166-2' v='AA-BB' />
+            </way>
+            <way id='-12'>
+              <nd ref='-5' /><nd ref='-6' /><nd ref='-7' /><nd ref='-8' /><nd ref='-5' />
+              <tag k='name' v='no id' />
+            </way>
+        "#);
+        let boundaries = read(xml.as_bytes()).unwrap();
+
+        assert_eq!(2, boundaries.len());
+        assert_eq!("AA", boundaries[0].id);
+        assert_eq!(5, boundaries[0].outer[0].len());
+        assert!(boundaries[0].inner.is_empty());
+        assert_eq!("AA-BB", boundaries[1].id);
+    }
+
+    #[test]
+    fn read_multipolygon_relation() {
+        // outer ring is split into two ways, one of which is in reverse direction
+        let xml = osm(r#"
+            <way id='-10'><nd ref='-1' /><nd ref='-2' /><nd ref='-3' /></way>
+            <way id='-11'><nd ref='-1' /><nd ref='-4' /><nd ref='-3' /></way>
+            <way id='-12'>
+              <nd ref='-5' /><nd ref='-6' /><nd ref='-7' /><nd ref='-8' /><nd ref='-5' />
+            </way>
+            <relation id='-20'>
+              <member type='way' ref='-10' role='outer' />
+              <member type='way' ref='-11' role='outer' />
+              <member type='way' ref='-12' role='inner' />
+              <tag k='type' v='multipolygon' />
+              <tag k='ISO3166-1:alpha2' v='AA' />
+            </relation>
+        "#);
+        let boundaries = read(xml.as_bytes()).unwrap();
+
+        assert_eq!(1, boundaries.len());
+        assert_eq!("AA", boundaries[0].id);
+        assert_eq!(1, boundaries[0].outer.len());
+        assert_eq!(5, boundaries[0].outer[0].len());
+        assert_eq!(1, boundaries[0].inner.len());
+
+        let boundaries = crate::generator::generate(&boundaries, 360, 180).unwrap();
+        assert!(boundaries.is_in(latlon(1.0, 1.0), "AA"));
+        assert!(boundaries.is_in(latlon(9.5, 0.5), "AA"));
+        assert!(!boundaries.is_in(latlon(5.0, 5.0), "AA"));
+        assert!(!boundaries.is_in(latlon(10.5, 5.0), "AA"));
+    }
+
+    #[test]
+    fn skip_deleted_elements() {
+        let xml = osm(r#"
+            <way id='-10' action='delete'>
+              <nd ref='-1' /><nd ref='-2' /><nd ref='-3' /><nd ref='-4' /><nd ref='-1' />
+              <tag k='ISO3166-1:alpha2' v='AA' />
+            </way>
+        "#);
+        assert!(read(xml.as_bytes()).unwrap().is_empty());
+    }
+
+    #[test]
+    fn join_ways_to_rings() {
+        assert_eq!(Some(vec![]), join_rings(vec![]));
+        assert_eq!(
+            Some(vec![vec![1, 2, 3, 4, 1]]),
+            join_rings(vec![&[3, 4, 1], &[1, 2, 3]])
+        );
+        assert_eq!(
+            Some(vec![vec![1, 2, 3, 4, 1]]),
+            join_rings(vec![&[1, 4, 3], &[1, 2, 3]])
+        );
+        assert_eq!(2, join_rings(vec![&[1, 2, 3, 1], &[4, 5, 6, 4]]).unwrap().len());
+        assert_eq!(None, join_rings(vec![&[1, 2, 3], &[3, 4]]));
+    }
+
+    #[test]
+    fn return_errors() {
+        assert!(read("".as_bytes()).is_err());
+        assert!(read("<osm><node id='1' lat='91' lon='0' /></osm>".as_bytes()).is_err());
+        assert!(read("<osm><node id='1' lon='0' /></osm>".as_bytes()).is_err());
+        // not closed
+        assert!(read(osm(r#"
+            <way id='-10'>
+              <nd ref='-1' /><nd ref='-2' /><nd ref='-3' />
+              <tag k='ISO3166-1:alpha2' v='AA' />
+            </way>
+        "#).as_bytes()).is_err());
+        // missing node
+        assert!(read(osm(r#"
+            <way id='-10'>
+              <nd ref='-1' /><nd ref='-2' /><nd ref='-99' /><nd ref='-1' />
+              <tag k='ISO3166-1:alpha2' v='AA' />
+            </way>
+        "#).as_bytes()).is_err());
+        // missing way
+        assert!(read(osm(r#"
+            <relation id='-20'>
+              <member type='way' ref='-10' role='outer' />
+              <tag k='ISO3166-1:alpha2' v='AA' />
+            </relation>
+        "#).as_bytes()).is_err());
+    }
+}
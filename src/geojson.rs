@@ -0,0 +1,302 @@
+use std::io;
+use std::io::Write;
+use crate::cell::multipolygon::is_point_in_ring;
+use crate::cell::point::Point;
+use crate::raster::Raster;
+use crate::{BoundingBox, CountryBoundaries};
+
+/// Options for [`CountryBoundaries::to_geojson`]. By default, all regions are exported.
+#[derive(Debug, Clone, Default)]
+pub struct GeoJsonOptions {
+    /// Only export the region with this id
+    pub id: Option<String>,
+    /// Only export the cells that intersect with these bounds. The cells are exported as a whole,
+    /// i.e. the geometry is not clipped to the bounds. The bounds may wrap around the 180th
+    /// meridian.
+    pub bounds: Option<BoundingBox>
+}
+
+/// A polygon as outer ring followed by its holes, each a closed ring of longitude and latitude
+type Polygon = Vec<Vec<(f64, f64)>>;
+
+pub(crate) fn to_geojson(
+    boundaries: &CountryBoundaries,
+    mut writer: impl Write,
+    options: &GeoJsonOptions
+) -> io::Result<()> {
+    let regions = &boundaries.regions;
+    let raster = Raster::new(boundaries.raster_width, boundaries.raster.len());
+
+    let only = options.id.as_deref().map(|id| regions.index(id));
+    let is_exported = |index: usize| match only {
+        Some(only) => only.is_some_and(|only| only.index() == index),
+        None => true
+    };
+    let mut is_selected = vec![options.bounds.is_none(); boundaries.raster.len()];
+    if let Some(bounds) = &options.bounds {
+        for index in raster.cells(bounds) {
+            is_selected[index] = true;
+        }
+    }
+
+    let mut polygons: Vec<Vec<Polygon>> = vec![Vec::new(); regions.len()];
+    // neighbouring cells in a row that are fully covered by a region are merged into one
+    // rectangle. The areas that partly cover a cell are not merged with anything
+    let mut runs: Vec<Option<(usize, usize)>> = vec![None; regions.len()];
+    for (index, cell) in boundaries.raster.iter().enumerate() {
+        if !is_selected[index] { continue }
+        for id in cell.containing_ids.iter().map(|id| id.index()) {
+            if !is_exported(id) { continue }
+            match runs[id] {
+                Some((start, end)) if end + 1 == index && index % raster.width != 0 => {
+                    runs[id] = Some((start, index));
+                },
+                run => {
+                    if let Some((start, end)) = run {
+                        polygons[id].push(rectangle(&raster, start, end));
+                    }
+                    runs[id] = Some((index, index));
+                }
+            }
+        }
+        for (id, multipolygon) in cell.intersecting_areas.iter() {
+            if !is_exported(id.index()) { continue }
+            let outer = &multipolygon.outer;
+            let mut cell_polygons: Vec<Polygon> = outer.iter()
+                .map(|ring| vec![to_ring(&raster, index, ring, true)])
+                .collect();
+            for ring in multipolygon.inner.iter() {
+                // assign each hole to the outer ring it is in
+                let Some(point) = ring.first() else { continue };
+                let outer_index = outer.iter()
+                    .position(|outer_ring| is_point_in_ring(point, outer_ring.iter().copied()))
+                    .unwrap_or(0);
+                if let Some(polygon) = cell_polygons.get_mut(outer_index) {
+                    polygon.push(to_ring(&raster, index, ring, false));
+                }
+            }
+            polygons[id.index()].extend(cell_polygons);
+        }
+    }
+    for (id, run) in runs.into_iter().enumerate() {
+        if let Some((start, end)) = run {
+            polygons[id].push(rectangle(&raster, start, end));
+        }
+    }
+
+    let mut features: Vec<(&str, Option<f64>, &Vec<Polygon>)> = regions.iter()
+        .zip(polygons.iter())
+        .filter(|(_, polygons)| !polygons.is_empty())
+        .map(|((id, size), polygons)| (id, size, polygons))
+        .collect();
+    features.sort_by(|a, b| a.0.cmp(b.0));
+
+    write!(writer, "{{\"type\":\"FeatureCollection\",\"features\":[")?;
+    for (i, (id, size, polygons)) in features.into_iter().enumerate() {
+        writeln!(writer, "{}", if i > 0 { "," } else { "" })?;
+        write!(writer, "{{\"type\":\"Feature\",\"properties\":{{\"id\":")?;
+        write_string(&mut writer, id)?;
+        if let Some(size) = size.filter(|size| size.is_finite()) {
+            write!(writer, ",\"size\":{size}")?;
+        }
+        write!(writer, "}},\"geometry\":{{\"type\":\"MultiPolygon\",\"coordinates\":")?;
+        write_multipolygon(&mut writer, polygons)?;
+        write!(writer, "}}}}")?;
+    }
+    writeln!(writer)?;
+    writeln!(writer, "]}}")
+}
+
+/// Returns the rectangle that spans the cells from `start` to `end` of the same row
+fn rectangle(raster: &Raster, start: usize, end: usize) -> Polygon {
+    let [min_longitude, min_latitude, _, _] = raster.cell_bounds(start);
+    let [_, _, max_longitude, max_latitude] = raster.cell_bounds(end);
+    vec![vec![
+        (min_longitude, min_latitude),
+        (max_longitude, min_latitude),
+        (max_longitude, max_latitude),
+        (min_longitude, max_latitude),
+        (min_longitude, min_latitude)
+    ]]
+}
+
+/// Converts the given `ring` local to the cell with the given `index` to a closed ring of
+/// longitude and latitude, counterclockwise if it is an `outer` ring and clockwise otherwise, as
+/// GeoJSON recommends
+fn to_ring(raster: &Raster, index: usize, ring: &[Point], outer: bool) -> Vec<(f64, f64)> {
+    let mut result: Vec<(f64, f64)> = ring.iter()
+        .map(|point| raster.longitude_latitude_of(index, point))
+        .collect();
+    if (signed_area(&result) > 0.0) != outer {
+        result.reverse();
+    }
+    if let Some(&first) = result.first() {
+        result.push(first);
+    }
+    result
+}
+
+fn signed_area(ring: &[(f64, f64)]) -> f64 {
+    let Some(&last) = ring.last() else { return 0.0 };
+    let mut sum = 0.0;
+    let mut previous = last;
+    for &point in ring.iter() {
+        sum += previous.0 * point.1 - point.0 * previous.1;
+        previous = point;
+    }
+    sum / 2.0
+}
+
+fn write_multipolygon(writer: &mut impl Write, polygons: &[Polygon]) -> io::Result<()> {
+    write!(writer, "[")?;
+    for (i, polygon) in polygons.iter().enumerate() {
+        if i > 0 { write!(writer, ",")?; }
+        write!(writer, "[")?;
+        for (j, ring) in polygon.iter().enumerate() {
+            if j > 0 { write!(writer, ",")?; }
+            write!(writer, "[")?;
+            for (k, (longitude, latitude)) in ring.iter().enumerate() {
+                if k > 0 { write!(writer, ",")?; }
+                write!(writer, "[{longitude},{latitude}]")?;
+            }
+            write!(writer, "]")?;
+        }
+        write!(writer, "]")?;
+    }
+    write!(writer, "]")
+}
+
+fn write_string(writer: &mut impl Write, value: &str) -> io::Result<()> {
+    write!(writer, "\"")?;
+    for c in value.chars() {
+        match c {
+            '"' => write!(writer, "\\\"")?,
+            '\\' => write!(writer, "\\\\")?,
+            c if (c as u32) < 0x20 => write!(writer, "\\u{:04x}", c as u32)?,
+            c => write!(writer, "{c}")?
+        }
+    }
+    write!(writer, "\"")
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::cell::multipolygon::Multipolygon;
+    use super::*;
+
+    fn written(boundaries: &CountryBoundaries, options: &GeoJsonOptions) -> String {
+        let mut buf = Vec::new();
+        to_geojson(boundaries, &mut buf, options).unwrap();
+        String::from_utf8(buf).unwrap()
+    }
+
+    fn p(x: u16, y: u16) -> Point {
+        Point { x, y }
+    }
+
+    #[test]
+    fn write_containing_cells_merged_per_row() {
+        // ┌─┬─┬─┬─┐
+        // │A│A│ │A│
+        // ├─┼─┼─┼─┤
+        // │ │ │ │ │
+        // └─┴─┴─┴─┘
+        let boundaries = CountryBoundaries::from_cells(
+            vec![
+                (vec!["A"], vec![]), (vec!["A"], vec![]), (vec![], vec![]), (vec!["A"], vec![]),
+                (vec![], vec![]), (vec![], vec![]), (vec![], vec![]), (vec![], vec![]),
+            ],
+            4,
+            &[("A", 2.5)]
+        );
+        assert_eq!(
+            "{\"type\":\"FeatureCollection\",\"features\":[\n\
+             {\"type\":\"Feature\",\"properties\":{\"id\":\"A\",\"size\":2.5},\
+             \"geometry\":{\"type\":\"MultiPolygon\",\"coordinates\":[\
+             [[[-180,0],[0,0],[0,90],[-180,90],[-180,0]]],\
+             [[[90,0],[180,0],[180,90],[90,90],[90,0]]]\
+             ]}}\n\
+             ]}\n",
+            written(&boundaries, &GeoJsonOptions::default())
+        );
+    }
+
+    #[test]
+    fn write_intersecting_area_with_hole() {
+        // clockwise outer ring and counterclockwise inner ring, which are both reversed
+        let multipolygon = Multipolygon {
+            outer: vec![vec![p(0, 0), p(0, 0xffff), p(0xffff, 0xffff), p(0xffff, 0)]],
+            inner: vec![vec![p(0x4000, 0x4000), p(0xc000, 0x4000), p(0xc000, 0xc000)]]
+        };
+        let boundaries = CountryBoundaries::from_cells(
+            vec![(vec![], vec![("A", multipolygon)])],
+            1,
+            &[]
+        );
+        assert_eq!(
+            "{\"type\":\"FeatureCollection\",\"features\":[\n\
+             {\"type\":\"Feature\",\"properties\":{\"id\":\"A\"},\
+             \"geometry\":{\"type\":\"MultiPolygon\",\"coordinates\":[[\
+             [[180,-90],[180,90],[-180,90],[-180,-90],[180,-90]],\
+             [[90.00411993591212,45.00205996795606],[90.00411993591212,-44.999313344014645],\
+             [-89.99862668802929,-44.999313344014645],[90.00411993591212,45.00205996795606]]\
+             ]]}}\n\
+             ]}\n",
+            written(&boundaries, &GeoJsonOptions::default())
+        );
+    }
+
+    #[test]
+    fn write_only_given_id_and_bounds() {
+        let boundaries = CountryBoundaries::from_cells(
+            vec![(vec!["A", "B"], vec![]), (vec!["A"], vec![])],
+            2,
+            &[("A", 1.0), ("B", 1.0)]
+        );
+        let only_b = written(&boundaries, &GeoJsonOptions { id: Some(String::from("B")), bounds: None });
+        assert!(only_b.contains("\"id\":\"B\""));
+        assert!(!only_b.contains("\"id\":\"A\""));
+
+        let unknown = written(&boundaries, &GeoJsonOptions { id: Some(String::from("C")), bounds: None });
+        assert_eq!("{\"type\":\"FeatureCollection\",\"features\":[\n]}\n", unknown);
+
+        let east = BoundingBox::new(10.0, 10.0, 20.0, 20.0).unwrap();
+        let in_east = written(&boundaries, &GeoJsonOptions { id: None, bounds: Some(east) });
+        assert!(in_east.contains("[[[[0,-90],[180,-90],[180,90],[0,90],[0,-90]]]]"));
+        assert!(!in_east.contains("\"id\":\"B\""));
+    }
+
+    #[test]
+    fn escape_strings() {
+        let mut buf = Vec::new();
+        write_string(&mut buf, "a\"b\\c\n").unwrap();
+        assert_eq!("\"a\\\"b\\\\c\\u000a\"", String::from_utf8(buf).unwrap());
+    }
+
+    #[test]
+    #[cfg(feature = "generator")]
+    fn exported_geometry_generates_same_dataset() {
+        use crate::generator::{generate, geojson, Boundary};
+        use crate::LatLon;
+
+        let ring = |points: &[(f64, f64)]| -> Vec<LatLon> {
+            points.iter().map(|&(lon, lat)| LatLon::new(lat, lon).unwrap()).collect()
+        };
+        let a = Boundary::new(
+            "A",
+            vec![ring(&[(-10.3, -5.7), (12.1, -3.2), (8.8, 14.9), (-4.4, 9.1)])],
+            vec![ring(&[(-1.2, -1.1), (2.3, -0.8), (1.1, 2.7)])]
+        );
+        let boundaries = generate(&[a], 60, 30).unwrap();
+
+        let json = written(&boundaries, &GeoJsonOptions::default());
+        let regenerated = generate(&geojson::read(json.as_bytes(), "id").unwrap(), 60, 30).unwrap();
+
+        for latitude in -70..150 {
+            for longitude in -120..130 {
+                let position = LatLon::new(latitude as f64 / 10.0 + 0.03, longitude as f64 / 10.0 + 0.07).unwrap();
+                assert_eq!(boundaries.ids(position), regenerated.ids(position), "at {position:?}");
+            }
+        }
+    }
+}
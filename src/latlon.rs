@@ -1,5 +1,8 @@
 use crate::error::Error;
 
+/// Mean radius of the earth in meters
+pub(crate) const EARTH_RADIUS: f64 = 6_371_008.8;
+
 #[derive(Debug, Copy, Clone)]
 pub struct LatLon {
     latitude: f64,
@@ -25,6 +28,19 @@ impl LatLon {
         }
         Ok(LatLon { latitude, longitude })
     }
+
+    /// Returns the great-circle distance to the `other` position in meters, assuming a spherical
+    /// earth
+    pub(crate) fn distance_to(&self, other: &LatLon) -> f64 {
+        let latitude1 = self.latitude.to_radians();
+        let latitude2 = other.latitude.to_radians();
+        let delta_latitude = latitude2 - latitude1;
+        let delta_longitude = (other.longitude - self.longitude).to_radians();
+        // haversine formula
+        let a = (delta_latitude / 2.0).sin().powi(2)
+            + latitude1.cos() * latitude2.cos() * (delta_longitude / 2.0).sin().powi(2);
+        2.0 * EARTH_RADIUS * a.sqrt().min(1.0).asin()
+    }
 }
 
 impl std::fmt::Display for LatLon {
@@ -60,4 +76,16 @@ mod tests {
         assert!(LatLon::new(0.0, -180.1).is_ok());
         assert!(LatLon::new(0.0, -99999.0).is_ok());
     }
+
+    #[test]
+    fn distance() {
+        let berlin = LatLon::new(52.5200, 13.4050).unwrap();
+        let paris = LatLon::new(48.8566, 2.3522).unwrap();
+        assert!((berlin.distance_to(&paris) - 877_500.0).abs() < 1000.0);
+        assert_eq!(0.0, paris.distance_to(&paris));
+        // one degree on the equator, across the 180th meridian
+        let east = LatLon::new(0.0, 179.5).unwrap();
+        let west = LatLon::new(0.0, -179.5).unwrap();
+        assert!((east.distance_to(&west) - 111_195.0).abs() < 1.0);
+    }
 }
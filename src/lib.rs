@@ -29,29 +29,41 @@
 //! // more efficient than calling `is_in` for every id in a row.
 //! assert!(
 //!     boundaries.is_in_any(
-//!         LatLon::new(21.0, 96.0)?,
+//!         LatLon::new(23.8, 90.4)?,
 //!         &HashSet::from(["BD", "DJ", "IR", "PS"])
 //!     )
 //! );
 //!
 //! // get which country ids can be found within a bounding box around the Vaalserberg³
+//! let vaalserberg = BoundingBox::new(50.7358, 5.9865, 50.7679, 6.0599)?;
 //! assert_eq!(
-//!     HashSet::from(["DE", "BE", "NL"]),
-//!     boundaries.intersecting_ids(BoundingBox::new(50.7679, 5.9865, 50.7358, 6.0599)?)
+//!     HashSet::from(["DE", "BE", "BE-WAL", "NL"]),
+//!     boundaries.intersecting_ids_exact(vaalserberg)
+//! );
+//! // faster, but also returns the ids of regions that are merely close to the bounding box
+//! assert!(
+//!     boundaries.intersecting_ids(vaalserberg).is_superset(&HashSet::from(["DE", "BE", "NL"]))
 //! );
 //!
 //! // get which country ids completely cover a bounding box around the Vaalserberg³
 //! assert_eq!(
 //!     HashSet::new(),
-//!     boundaries.containing_ids(BoundingBox::new(50.7679, 5.9865, 50.7358, 6.0599)?)
+//!     boundaries.containing_ids_exact(vaalserberg)
 //! );
+//!
+//! // get which country ids completely cover a bounding box around Aachen⁴. As it is close to the
+//! // border, only the exact variant finds that it is in Germany
+//! let aachen = BoundingBox::new(50.74, 6.05, 50.80, 6.15)?;
+//! assert_eq!(HashSet::from(["DE"]), boundaries.containing_ids_exact(aachen));
+//! assert_eq!(HashSet::new(), boundaries.containing_ids(aachen));
 //! #
 //! # Ok(())
 //! # }
 //! ```
 //! ¹ [Dallas](https://www.openstreetmap.org?mlat=32.7816&mlon=-96.7954) —
 //! ² [German exclave in Switzerland](https://www.openstreetmap.org?mlat=47.6973&mlon=8.6803) —
-//! ³ [Vaalserberg](https://www.openstreetmap.org/?mlat=50.754722&mlon=6.020833)
+//! ³ [Vaalserberg](https://www.openstreetmap.org/?mlat=50.754722&mlon=6.020833) —
+//! ⁴ [Aachen](https://www.openstreetmap.org/?mlat=50.7753&mlon=6.0839)
 //!
 //! How the ids are named and what areas are available depends on the data used. The data used in
 //! the examples is the default data (see below).
@@ -62,6 +74,8 @@
 //! You can generate an own (country) boundaries file from a GeoJson or an
 //! [OSM XML](https://wiki.openstreetmap.org/wiki/OSM_XML), using the Java shell application in the
 //! `/generator/` folder of the [Java project](https://github.com/westnordost/countryboundaries).
+//! Alternatively, enable the `generator` feature of this crate to generate a dataset from GeoJSON
+//! in Rust, or the `osm` feature to also generate it from OSM XML, see the `generator` module.
 //!
 //! ## Default data
 //!
@@ -71,6 +85,12 @@
 //! [Open Data Commons Open Database License](https://opendatacommons.org/licenses/odbl/) (ODbL),
 //! © OpenStreetMap contributors.
 //!
+//! Instead of reading the data from a file, it can also be embedded into the binary with the
+//! features `embed-360x180`, `embed-180x90` or `embed-60x30`, which make the default data
+//! available with e.g. `CountryBoundaries::default_360x180()`. To not even have to parse it at
+//! startup, the [`codegen`] module generates Rust code for a [`StaticCountryBoundaries`] at build
+//! time instead.
+//!
 //! The dataset can only be as small as it is because the actual country- and state boundaries have
 //! been simplified somewhat from their actual boundaries. Generally, it is made to meet the
 //! requirements for OpenStreetMap editing:
@@ -88,20 +108,69 @@
 
 // TODO versioning: start with 1.0.0?
 
-use std::{cmp::min, collections::HashMap, collections::HashSet, io, vec::Vec};
+use std::{collections::HashSet, io, vec::Vec};
 use cell::Cell;
 use crate::cell::point::Point;
 use crate::deserializer::from_reader;
+use crate::serializer::to_writer;
+use crate::raster::Raster;
+use crate::regions::Regions;
+use crate::region_bounds::region_bounds;
+use crate::polygon::Polygon;
 
 pub use self::latlon::LatLon;
 pub use self::bbox::BoundingBox;
-pub use self::error::Error;
+pub use self::error::{Error, LoadError};
+pub use self::options::{Limit, LoadOptions};
+pub use self::geojson::GeoJsonOptions;
+pub use self::regions::RegionIndex;
+pub use self::route::RouteSegment;
+pub use self::border::{BorderPoint, IdsWithConfidence};
+pub use self::tracker::{BorderEvent, Tracker};
+pub use self::boundaries_ref::CountryBoundariesRef;
+pub use self::static_boundaries::StaticCountryBoundaries;
+#[cfg(feature = "mmap")]
+pub use self::mapped::MappedCountryBoundaries;
 
 mod latlon;
 mod bbox;
 mod cell;
+mod raster;
+mod regions;
+mod region_bounds;
+mod polygon;
+mod route;
+mod tracker;
+mod border;
+mod boundaries_ref;
+mod static_boundaries;
+#[cfg(feature = "mmap")]
+mod mapped;
+#[cfg(any(feature = "embed-360x180", feature = "embed-180x90", feature = "embed-60x30"))]
+mod embedded;
 mod deserializer;
+mod serializer;
+mod geojson;
 mod error;
+mod options;
+mod validation;
+pub mod codegen;
+#[cfg(feature = "generator")]
+pub mod generator;
+
+/// How much farther away than the closest region other regions may be in meters to count as
+/// just as close, see [`CountryBoundaries::nearest_ids`]. Regions that share a border may differ
+/// a bit because the points of the polygons are rounded to the raster.
+const NEAREST_MARGIN: f64 = 10.0;
+
+/// How many positions are looked up together in [`CountryBoundaries::ids_batch`] and
+/// [`CountryBoundaries::is_in_any_batch`], i.e. grouped by cell and, with the `rayon` feature,
+/// handed to one thread
+const BATCH_CHUNK_SIZE: usize = 1 << 16;
+
+/// How many ids of a cell [`CountryBoundaries::ids_iter`] sorts on the stack. Only cells with
+/// more ids than that need memory to be allocated.
+const INLINE_IDS: usize = 16;
 
 #[derive(Debug, Clone, PartialEq)]
 pub struct CountryBoundaries {
@@ -109,15 +178,150 @@ pub struct CountryBoundaries {
     raster: Vec<Cell>,
     /// width of the raster
     raster_width: usize,
-    /// the sizes of the different countries contained
-    geometry_sizes: HashMap<String, f64>
+    /// the ids and sizes of the different countries contained
+    regions: Regions,
+    /// the bounding box of each region, in the same order as `regions`. It is derived from the
+    /// raster when the boundaries are created
+    bounds: Vec<Option<BoundingBox>>,
+    /// the rank of each region when ordered by size, in the same order as `regions`. It is
+    /// derived from the sizes when the boundaries are created, so that sorting by size is cheap
+    ranks: Vec<u32>
 }
 
 impl CountryBoundaries {
 
+    pub(crate) fn new(raster: Vec<Cell>, raster_width: usize, regions: Regions) -> CountryBoundaries {
+        let bounds = region_bounds(&raster, raster_width, regions.len());
+        let ranks = regions.size_ranks();
+        CountryBoundaries { raster, raster_width, regions, bounds, ranks }
+    }
+
     /// Create a CountryBoundaries from a stream of bytes.
-    pub fn from_reader(reader: impl io::Read) -> io::Result<CountryBoundaries> {
-        from_reader(reader)
+    ///
+    /// Returns a [`LoadError`] that tells what is wrong and where if the data is not a valid
+    /// boundaries file.
+    pub fn from_reader(reader: impl io::Read) -> Result<CountryBoundaries, LoadError> {
+        from_reader(reader, &LoadOptions::default())
+    }
+
+    /// Create a CountryBoundaries from a stream of bytes, with the given `options`.
+    ///
+    /// See [`from_reader`](CountryBoundaries::from_reader) and [`LoadOptions`]
+    pub fn from_reader_with_options(
+        reader: impl io::Read,
+        options: &LoadOptions
+    ) -> Result<CountryBoundaries, LoadError> {
+        from_reader(reader, options)
+    }
+
+    /// Open the boundaries file at the given `path` as a memory-mapped file and query it in place.
+    ///
+    /// Contrary to [`from_reader`](CountryBoundaries::from_reader), the data is not copied to the
+    /// heap, so the operating system can share it between all processes that open the same file.
+    ///
+    /// # Safety
+    ///
+    /// The file must not be modified or truncated, neither by this nor by any other process, for
+    /// as long as the returned [`MappedCountryBoundaries`] exists. Otherwise, the behavior is
+    /// undefined. See [`memmap2::Mmap::map`].
+    ///
+    /// # Example
+    /// ```
+    /// # use country_boundaries::{CountryBoundaries, LatLon};
+    /// #
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// // SAFETY: the bundled data files are never modified
+    /// let boundaries = unsafe { CountryBoundaries::open("./data/boundaries360x180.ser")? };
+    ///
+    /// assert_eq!(
+    ///     vec!["US-TX", "US"],
+    ///     boundaries.ids(LatLon::new(33.0, -97.0)?)
+    /// );
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[cfg(feature = "mmap")]
+    pub unsafe fn open(
+        path: impl AsRef<std::path::Path>
+    ) -> Result<MappedCountryBoundaries, LoadError> {
+        // SAFETY: the caller upholds the contract of this function, which is the same
+        unsafe { MappedCountryBoundaries::open(path.as_ref(), &LoadOptions::default()) }
+    }
+
+    /// Open the boundaries file at the given `path` as a memory-mapped file, with the given
+    /// `options`.
+    ///
+    /// See [`open`](CountryBoundaries::open) and [`LoadOptions`]
+    ///
+    /// # Safety
+    ///
+    /// Same as for [`open`](CountryBoundaries::open): The file must not be modified or truncated
+    /// for as long as the returned [`MappedCountryBoundaries`] exists.
+    #[cfg(feature = "mmap")]
+    pub unsafe fn open_with_options(
+        path: impl AsRef<std::path::Path>,
+        options: &LoadOptions
+    ) -> Result<MappedCountryBoundaries, LoadError> {
+        // SAFETY: the caller upholds the contract of this function, which is the same
+        unsafe { MappedCountryBoundaries::open(path.as_ref(), options) }
+    }
+
+    /// Write this CountryBoundaries as a stream of bytes, in the same format as read by
+    /// [`from_reader`](CountryBoundaries::from_reader).
+    ///
+    /// # Example
+    /// ```
+    /// # use country_boundaries::CountryBoundaries;
+    /// #
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// # let buf = std::fs::read("./data/boundaries360x180.ser")?;
+    /// # let boundaries = CountryBoundaries::from_reader(buf.as_slice())?;
+    /// let mut written = Vec::new();
+    /// boundaries.to_writer(&mut written)?;
+    /// assert_eq!(boundaries, CountryBoundaries::from_reader(written.as_slice())?);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn to_writer(&self, writer: impl io::Write) -> io::Result<()> {
+        to_writer(self, writer)
+    }
+
+    /// Write the regions of this CountryBoundaries as a GeoJSON `FeatureCollection`, e.g. to view
+    /// what the dataset holds in a GIS application.
+    ///
+    /// The geometry of each region is reconstructed from the raster: Cells that are fully covered
+    /// by a region become rectangles, merged with their neighbours in the same row, and the areas
+    /// that only partly cover a cell are converted back from cell-local coordinates to longitude
+    /// and latitude. So, the result is a `MultiPolygon` per region that is cut along the cell
+    /// borders. Each feature has the properties `id` and, if known, `size`.
+    ///
+    /// Only fully covered cells of the same row are merged. Each area that partly covers a cell
+    /// stays a separate polygon and is not merged with the polygons of neighbouring cells, so a
+    /// region appears as many pieces along its border. To get one outline per region, dissolve
+    /// the polygons of each feature in the GIS application.
+    ///
+    /// With the `options`, only a single region or only the cells within given bounds can be
+    /// exported.
+    ///
+    /// This function will not buffer the output, see [`io::BufWriter`].
+    ///
+    /// # Example
+    /// ```
+    /// # use country_boundaries::{CountryBoundaries, GeoJsonOptions};
+    /// #
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// # let buf = std::fs::read("./data/boundaries360x180.ser")?;
+    /// # let boundaries = CountryBoundaries::from_reader(buf.as_slice())?;
+    /// let mut written = Vec::new();
+    /// boundaries.to_geojson(
+    ///     &mut written,
+    ///     &GeoJsonOptions { id: Some(String::from("DE")), ..GeoJsonOptions::default() }
+    /// )?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn to_geojson(&self, writer: impl io::Write, options: &GeoJsonOptions) -> io::Result<()> {
+        geojson::to_geojson(self, writer, options)
     }
 
     /// Returns whether the given `position` is in the region with the given `id`
@@ -136,8 +340,116 @@ impl CountryBoundaries {
     /// # }
     /// ```
     pub fn is_in(&self, position: LatLon, id: &str) -> bool {
+        self.id_index(id).is_some_and(|index| self.is_in_index(position, index))
+    }
+
+    /// Returns the index of the region with the given `id`, or `None` if there is no such region.
+    ///
+    /// Use it together with [`is_in_index`](CountryBoundaries::is_in_index) to check a lot of
+    /// positions against the same region.
+    ///
+    /// # Example
+    /// ```
+    /// # use country_boundaries::{CountryBoundaries, LatLon};
+    /// #
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// # let buf = std::fs::read("./data/boundaries360x180.ser")?;
+    /// # let boundaries = CountryBoundaries::from_reader(buf.as_slice())?;
+    /// let germany = boundaries.id_index("DE").unwrap();
+    /// assert!(
+    ///     boundaries.is_in_index(LatLon::new(47.6973, 8.6910)?, germany)
+    /// );
+    /// assert_eq!(None, boundaries.id_index("XX"));
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn id_index(&self, id: &str) -> Option<RegionIndex> {
+        self.regions.index(id)
+    }
+
+    /// Returns the ids of all regions in this dataset, in no particular order.
+    ///
+    /// # Example
+    /// ```
+    /// # use country_boundaries::CountryBoundaries;
+    /// #
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// # let buf = std::fs::read("./data/boundaries360x180.ser")?;
+    /// # let boundaries = CountryBoundaries::from_reader(buf.as_slice())?;
+    /// let mut ids: Vec<&str> = boundaries.region_ids().collect();
+    /// ids.sort();
+    /// assert!(ids.binary_search(&"DE").is_ok());
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn region_ids(&self) -> impl Iterator<Item = &str> {
+        self.regions.iter().map(|(id, _)| id)
+    }
+
+    /// Returns whether this dataset contains a region with the given `id`.
+    ///
+    /// Useful to check user-supplied ids before calling [`is_in`](CountryBoundaries::is_in),
+    /// which just returns `false` for an id it does not know.
+    pub fn contains_region(&self, id: &str) -> bool {
+        self.regions.index(id).is_some()
+    }
+
+    /// Returns the size of the region with the given `id`, or `None` if there is no such region
+    /// or no size is known for it.
+    ///
+    /// The size is the area of the region's geometry in square degrees, i.e. as if longitude and
+    /// latitude were planar coordinates. So it is not an area on the earth's surface, regions far
+    /// from the equator are much larger than they are in reality. It is only meant to compare
+    /// regions with each other: [`ids`](CountryBoundaries::ids) orders its result by this size.
+    ///
+    /// # Example
+    /// ```
+    /// # use country_boundaries::CountryBoundaries;
+    /// #
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// # let buf = std::fs::read("./data/boundaries360x180.ser")?;
+    /// # let boundaries = CountryBoundaries::from_reader(buf.as_slice())?;
+    /// let germany = boundaries.region_size("DE").unwrap();
+    /// assert!(50.0 < germany && germany < 52.0);
+    /// assert_eq!(None, boundaries.region_size("XX"));
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn region_size(&self, id: &str) -> Option<f64> {
+        self.regions.size(self.regions.index(id)?)
+    }
+
+    /// Returns the bounding box of the region with the given `id`, or `None` if there is no such
+    /// region.
+    ///
+    /// The bounding box is derived from the raster, so it is only as precise as the data. If the
+    /// region crosses the 180th meridian, the bounding box wraps around it, i.e. its
+    /// `min_longitude` is greater than its `max_longitude`. It can be passed as it is to
+    /// [`intersecting_ids`](CountryBoundaries::intersecting_ids) and
+    /// [`containing_ids`](CountryBoundaries::containing_ids).
+    ///
+    /// # Example
+    /// ```
+    /// # use country_boundaries::CountryBoundaries;
+    /// #
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// # let buf = std::fs::read("./data/boundaries360x180.ser")?;
+    /// # let boundaries = CountryBoundaries::from_reader(buf.as_slice())?;
+    /// let russia = boundaries.bbox_of("RU").unwrap();
+    /// // Chukotka stretches beyond the 180th meridian
+    /// assert!(russia.min_longitude() > russia.max_longitude());
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn bbox_of(&self, id: &str) -> Option<BoundingBox> {
+        self.bounds[self.regions.index(id)?.index()]
+    }
+
+    /// Returns whether the given `position` is in the region with the given `index`, see
+    /// [`id_index`](CountryBoundaries::id_index)
+    pub fn is_in_index(&self, position: LatLon, index: RegionIndex) -> bool {
         let (cell, point)  = self.cell_and_local_point(position);
-        cell.is_in(point, id)
+        cell.is_in(point, index)
     }
 
     /// Returns whether the given `position` is in any of the regions with the given `ids`.
@@ -154,7 +466,7 @@ impl CountryBoundaries {
     /// // more efficient than calling `is_in` for every id in a row.
     /// assert!(
     ///     boundaries.is_in_any(
-    ///         LatLon::new(21.0, 96.0)?,
+    ///         LatLon::new(23.8, 90.4)?,
     ///         &HashSet::from(["BD", "DJ", "IR", "PS"])
     ///     )
     /// );
@@ -163,7 +475,7 @@ impl CountryBoundaries {
     /// ```
     pub fn is_in_any(&self, position: LatLon, ids: &HashSet<&str>) -> bool {
         let (cell, point)  = self.cell_and_local_point(position);
-        cell.is_in_any(point, ids)
+        cell.is_in_any(point, |index| ids.contains(self.regions.id(index)))
     }
 
     /// Returns the ids of the regions the given `position` is contained in, ordered by size of
@@ -187,13 +499,140 @@ impl CountryBoundaries {
     pub fn ids(&self, position: LatLon) -> Vec<&str> {
         let (cell, point)  = self.cell_and_local_point(position);
         let mut result = cell.get_ids(point);
-        let zero = 0.0;
-        result.sort_by(|&a, &b| {
-            let a = if let Some(size) = self.geometry_sizes.get(a) { size } else { &zero };
-            let b = if let Some(size) = self.geometry_sizes.get(b) { size } else { &zero };
-            a.total_cmp(b)
-        });
-        result
+        self.sort_by_size(&mut result);
+        result.into_iter().map(|index| self.regions.id(index)).collect()
+    }
+
+    /// Appends the ids of the regions the given `position` is contained in to `result`, ordered
+    /// like in [`ids`](CountryBoundaries::ids).
+    ///
+    /// Use it instead of `ids` to reuse the same `Vec` for many positions, so that no memory needs
+    /// to be allocated for each one.
+    ///
+    /// # Example
+    /// ```
+    /// # use country_boundaries::{CountryBoundaries, LatLon};
+    /// #
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// # let buf = std::fs::read("./data/boundaries360x180.ser")?;
+    /// # let boundaries = CountryBoundaries::from_reader(buf.as_slice())?;
+    /// let mut ids = Vec::new();
+    /// for position in [LatLon::new(33.0, -97.0)?, LatLon::new(52.5, 13.4)?] {
+    ///     ids.clear();
+    ///     boundaries.ids_into(position, &mut ids);
+    ///     assert!(!ids.is_empty());
+    /// }
+    /// assert_eq!(vec!["DE"], ids);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn ids_into<'a>(&'a self, position: LatLon, result: &mut Vec<&'a str>) {
+        result.extend(self.ids_iter(position));
+    }
+
+    /// Returns an iterator over the ids of the regions the given `position` is contained in,
+    /// ordered like in [`ids`](CountryBoundaries::ids). It does not allocate any memory, unless
+    /// the position is in a cell that has an unusually large number of regions.
+    ///
+    /// # Example
+    /// ```
+    /// # use country_boundaries::{CountryBoundaries, LatLon};
+    /// #
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// # let buf = std::fs::read("./data/boundaries360x180.ser")?;
+    /// # let boundaries = CountryBoundaries::from_reader(buf.as_slice())?;
+    /// let mut ids = boundaries.ids_iter(LatLon::new(33.0, -97.0)?);
+    /// assert_eq!(Some("US-TX"), ids.next());
+    /// assert_eq!(Some("US"), ids.next());
+    /// assert_eq!(None, ids.next());
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn ids_iter(&self, position: LatLon) -> impl Iterator<Item = &str> + '_ {
+        let (cell, point) = self.cell_and_local_point(position);
+        let mut ids = RankedIds::new(cell.containing_ids.len() + cell.intersecting_areas.len());
+        let covering = cell.containing_ids.iter()
+            .chain(cell.intersecting_areas.iter()
+                .filter(|(_, multipolygon)| multipolygon.covers(&point))
+                .map(|(index, _)| index)
+            );
+        // a cell has at most 2 * 255 ids. The order in the cell is part of the key, so that
+        // regions with the same rank stay in that order, just like after a stable sort
+        for (order, &index) in covering.enumerate() {
+            ids.push((self.ranks[index.index()], order as u16, index));
+        }
+        ids.as_mut_slice().sort_unstable();
+
+        (0..ids.len()).map(move |i| self.regions.id(ids.as_slice()[i].2))
+    }
+
+    /// Returns the ids of the regions each of the given `positions` is contained in, in the same
+    /// order as the `positions`. Each result is the same as what [`ids`](CountryBoundaries::ids)
+    /// returns for that position.
+    ///
+    /// It is faster than calling `ids` for every position in a row, as the positions are grouped
+    /// by the cell of the raster they are in. With the `rayon` feature, the positions are looked
+    /// up in parallel.
+    ///
+    /// # Example
+    /// ```
+    /// # use country_boundaries::{CountryBoundaries, LatLon};
+    /// #
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// # let buf = std::fs::read("./data/boundaries360x180.ser")?;
+    /// # let boundaries = CountryBoundaries::from_reader(buf.as_slice())?;
+    /// assert_eq!(
+    ///     vec![vec!["US-TX", "US"], vec!["DE"]],
+    ///     boundaries.ids_batch(&[LatLon::new(33.0, -97.0)?, LatLon::new(52.5, 13.4)?])
+    /// );
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn ids_batch(&self, positions: &[LatLon]) -> Vec<Vec<&str>> {
+        self.batch(positions, |cell, point| {
+            let mut result = cell.get_ids(point);
+            self.sort_by_size(&mut result);
+            result.into_iter().map(|index| self.regions.id(index)).collect()
+        })
+    }
+
+    /// Returns for each of the given `positions` whether it is in any of the regions with the
+    /// given `ids`, in the same order as the `positions`. Each result is the same as what
+    /// [`is_in_any`](CountryBoundaries::is_in_any) returns for that position.
+    ///
+    /// See [`ids_batch`](CountryBoundaries::ids_batch) for why it is faster than calling
+    /// `is_in_any` for every position in a row.
+    ///
+    /// # Example
+    /// ```
+    /// # use country_boundaries::{CountryBoundaries, LatLon};
+    /// # use std::collections::HashSet;
+    /// #
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// # let buf = std::fs::read("./data/boundaries360x180.ser")?;
+    /// # let boundaries = CountryBoundaries::from_reader(buf.as_slice())?;
+    /// let positions = [
+    ///     // Freilassing, Germany
+    ///     LatLon::new(47.838, 12.977)?,
+    ///     // Salzburg, Austria
+    ///     LatLon::new(47.800, 13.045)?,
+    ///     // Dallas, USA
+    ///     LatLon::new(33.0, -97.0)?,
+    /// ];
+    /// assert_eq!(
+    ///     vec![true, true, false],
+    ///     boundaries.is_in_any_batch(&positions, &HashSet::from(["DE", "AT"]))
+    /// );
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn is_in_any_batch(&self, positions: &[LatLon], ids: &HashSet<&str>) -> Vec<bool> {
+        // look up the ids only once instead of for every position
+        let mut wanted = vec![false; self.regions.len()];
+        for index in ids.iter().filter_map(|id| self.regions.index(id)) {
+            wanted[index.index()] = true;
+        }
+        self.batch(positions, |cell, point| cell.is_in_any(point, |index| wanted[index.index()]))
     }
 
     /// Returns the ids of the regions that fully contain the given bounding box `bounds`.
@@ -201,6 +640,11 @@ impl CountryBoundaries {
     /// The given bounding box is allowed to wrap around the 180th longitude,
     /// i.e `bounds.min_longitude` = 170 and `bounds.max_longitude` = -170 is fine.
     ///
+    /// This only returns regions that fully cover all the cells of the raster the bounding box
+    /// touches, so it may miss regions that contain a bounding box close to their border. See
+    /// [`containing_ids_exact`](CountryBoundaries::containing_ids_exact) for the exact, but
+    /// slower variant.
+    ///
     /// # Example
     /// ```
     /// # use country_boundaries::{CountryBoundaries, BoundingBox};
@@ -217,18 +661,61 @@ impl CountryBoundaries {
     /// # }
     /// ```
     pub fn containing_ids(&self, bounds: BoundingBox) -> HashSet<&str> {
-        let mut ids: HashSet<&str> = HashSet::new();
+        let mut ids: HashSet<RegionIndex> = HashSet::new();
         let mut first_cell = true;
         for cell in self.cells(&bounds) {
             if first_cell {
-                ids.extend(cell.containing_ids.iter().map(|id| id.as_str()));
+                ids.extend(cell.containing_ids.iter());
                 first_cell = false;
             } else {
-                ids.retain(|&id| cell.containing_ids.iter().any(|containing_id| containing_id == id));
-                if ids.is_empty() { return ids; }
+                ids.retain(|id| cell.containing_ids.contains(id));
+                if ids.is_empty() { break; }
+            }
+        }
+        ids.into_iter().map(|index| self.regions.id(index)).collect()
+    }
+
+    /// Returns the ids of the regions that fully contain the given bounding box `bounds`, checked
+    /// against the actual geometry of the regions.
+    ///
+    /// Contrary to [`containing_ids`](CountryBoundaries::containing_ids), this also returns
+    /// regions that only partly cover some of the cells of the raster the bounding box touches,
+    /// as long as they cover the part of the bounding box within these cells. It is slower,
+    /// because the bounding box needs to be compared to the polygons of these regions.
+    ///
+    /// # Example
+    /// ```
+    /// # use country_boundaries::{CountryBoundaries, BoundingBox};
+    /// # use std::collections::HashSet;
+    /// #
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// # let buf = std::fs::read("./data/boundaries360x180.ser")?;
+    /// # let boundaries = CountryBoundaries::from_reader(buf.as_slice())?;
+    /// // a bounding box around Aachen, which is in a cell that is not fully covered by Germany
+    /// let aachen = BoundingBox::new(50.74, 6.05, 50.80, 6.15)?;
+    /// assert_eq!(HashSet::from(["DE"]), boundaries.containing_ids_exact(aachen));
+    /// assert_eq!(HashSet::new(), boundaries.containing_ids(aachen));
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn containing_ids_exact(&self, bounds: BoundingBox) -> HashSet<&str> {
+        let raster = self.raster();
+        let mut ids: HashSet<RegionIndex> = HashSet::new();
+        let mut first_rect = true;
+        for index in raster.cells(&bounds) {
+            let cell = &self.raster[index];
+            for rect in raster.local_rects(index, &bounds) {
+                if first_rect {
+                    ids.extend(cell.get_covering_ids(rect));
+                    first_rect = false;
+                } else {
+                    let covering_ids: Vec<RegionIndex> = cell.get_covering_ids(rect).collect();
+                    ids.retain(|id| covering_ids.contains(id));
+                }
             }
+            if !first_rect && ids.is_empty() { break; }
         }
-        ids
+        ids.into_iter().map(|index| self.regions.id(index)).collect()
     }
 
     /// Returns the ids of the regions that contain or at lest intersect with the given bounding box
@@ -237,6 +724,11 @@ impl CountryBoundaries {
     /// The given bounding box is allowed to wrap around the 180th longitude, 
     /// i.e `bounds.min_longitude` = 170 and `bounds.max_longitude` = -170 is fine.
     ///
+    /// This only checks which regions are in the cells of the raster the bounding box touches,
+    /// so it may also return regions close to but outside of the bounding box. See
+    /// [`intersecting_ids_exact`](CountryBoundaries::intersecting_ids_exact) for the exact, but
+    /// slower variant.
+    ///
     /// # Example
     /// ```
     /// # use country_boundaries::{CountryBoundaries, BoundingBox};
@@ -253,135 +745,513 @@ impl CountryBoundaries {
     /// # }
     /// ```
     pub fn intersecting_ids(&self, bounds: BoundingBox) -> HashSet<&str> {
-        let mut ids: HashSet<&str> = HashSet::new();
+        let mut ids: HashSet<RegionIndex> = HashSet::new();
         for cell in self.cells(&bounds) {
             ids.extend(cell.get_all_ids());
         }
-        ids
+        ids.into_iter().map(|index| self.regions.id(index)).collect()
     }
 
-    fn cell_and_local_point(&self, position: LatLon) -> (&Cell, Point) {
-        let normalized_longitude = normalize(position.longitude(), -180.0, 360.0);
-        let cell_x = self.longitude_to_cell_x(normalized_longitude);
-        let cell_y = self.latitude_to_cell_y(position.latitude());
-
-        (
-            self.cell(cell_x, cell_y),
-            Point {
-                x: self.longitude_to_local_x(cell_x, normalized_longitude),
-                y: self.latitude_to_local_y(cell_y, position.latitude())
+    /// Returns the ids of the regions that contain or at least intersect with the given bounding
+    /// box `bounds`, checked against the actual geometry of the regions.
+    ///
+    /// Contrary to [`intersecting_ids`](CountryBoundaries::intersecting_ids), this does not
+    /// return regions that are only in the same cell of the raster as the bounding box but do not
+    /// intersect with it. It is slower, because the bounding box needs to be compared to the
+    /// polygons of all the regions that only partly cover the touched cells.
+    ///
+    /// # Example
+    /// ```
+    /// # use country_boundaries::{CountryBoundaries, BoundingBox};
+    /// # use std::collections::HashSet;
+    /// #
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// # let buf = std::fs::read("./data/boundaries360x180.ser")?;
+    /// # let boundaries = CountryBoundaries::from_reader(buf.as_slice())?;
+    /// // a bounding box around Aachen, which is in the same cell as Belgium, the Netherlands and
+    /// // Luxembourg
+    /// let aachen = BoundingBox::new(50.74, 6.05, 50.80, 6.15)?;
+    /// assert_eq!(HashSet::from(["DE"]), boundaries.intersecting_ids_exact(aachen));
+    /// assert_eq!(
+    ///     HashSet::from(["DE", "BE", "BE-WAL", "NL", "LU"]),
+    ///     boundaries.intersecting_ids(aachen)
+    /// );
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn intersecting_ids_exact(&self, bounds: BoundingBox) -> HashSet<&str> {
+        let raster = self.raster();
+        let mut ids: HashSet<RegionIndex> = HashSet::new();
+        for index in raster.cells(&bounds) {
+            let cell = &self.raster[index];
+            for rect in raster.local_rects(index, &bounds) {
+                ids.extend(cell.get_intersecting_ids(rect));
             }
-        )
+        }
+        ids.into_iter().map(|index| self.regions.id(index)).collect()
     }
 
-    fn cell(&self, x: usize, y: usize) -> &Cell {
-        &self.raster[y * self.raster_width + x]
+    /// Returns the ids of the regions that contain or at least intersect with the polygon given by
+    /// the ring `outer` and the optional rings `holes`, checked against the actual geometry of the
+    /// regions.
+    ///
+    /// The rings do not need to be closed, i.e. the last position does not need to be the same as
+    /// the first. Each edge of the polygon is the shorter way between its two positions, so the
+    /// polygon is allowed to cross the 180th longitude. Polygons that enclose one of the poles are
+    /// not supported.
+    ///
+    /// Only the cells of the raster the polygon touches are checked, in cells that are only partly
+    /// covered by a region, the polygon is compared to the polygons of that region.
+    ///
+    /// # Example
+    /// ```
+    /// # use country_boundaries::{CountryBoundaries, LatLon};
+    /// # use std::collections::HashSet;
+    /// #
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// # let buf = std::fs::read("./data/boundaries360x180.ser")?;
+    /// # let boundaries = CountryBoundaries::from_reader(buf.as_slice())?;
+    /// // a triangle from Aachen across the border to Vaals in the Netherlands
+    /// let triangle = [
+    ///     LatLon::new(50.776, 6.083)?,
+    ///     LatLon::new(50.770, 6.020)?,
+    ///     LatLon::new(50.760, 6.080)?,
+    /// ];
+    /// assert_eq!(HashSet::from(["DE", "NL"]), boundaries.intersecting_ids_polygon(&triangle, &[]));
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn intersecting_ids_polygon(&self, outer: &[LatLon], holes: &[Vec<LatLon>]) -> HashSet<&str> {
+        let polygon = Polygon::new(outer, holes);
+        polygon::intersecting_ids(&self.raster, self.raster_width, &polygon)
+            .into_iter()
+            .map(|index| self.regions.id(index))
+            .collect()
     }
 
-    fn longitude_to_cell_x(&self, longitude: f64) -> usize {
-        min(
-            self.raster_width.saturating_sub(1),
-            ((self.raster_width as f64) * (180.0 + longitude) / 360.0).floor() as usize
-        )
+    /// Returns the ids of the regions that completely contain the polygon given by the ring
+    /// `outer` and the optional rings `holes`, checked against the actual geometry of the regions.
+    ///
+    /// See [`intersecting_ids_polygon`](CountryBoundaries::intersecting_ids_polygon) for how the
+    /// polygon is interpreted. A region with a hole still contains the polygon if that hole is
+    /// within a hole of the polygon.
+    ///
+    /// # Example
+    /// ```
+    /// # use country_boundaries::{CountryBoundaries, LatLon};
+    /// # use std::collections::HashSet;
+    /// #
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// # let buf = std::fs::read("./data/boundaries360x180.ser")?;
+    /// # let boundaries = CountryBoundaries::from_reader(buf.as_slice())?;
+    /// // a triangle in the middle of Aachen
+    /// let triangle = [
+    ///     LatLon::new(50.780, 6.080)?,
+    ///     LatLon::new(50.770, 6.100)?,
+    ///     LatLon::new(50.760, 6.080)?,
+    /// ];
+    /// assert_eq!(HashSet::from(["DE"]), boundaries.containing_ids_polygon(&triangle, &[]));
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn containing_ids_polygon(&self, outer: &[LatLon], holes: &[Vec<LatLon>]) -> HashSet<&str> {
+        let polygon = Polygon::new(outer, holes);
+        polygon::containing_ids(&self.raster, self.raster_width, &polygon)
+            .into_iter()
+            .map(|index| self.regions.id(index))
+            .collect()
     }
 
-    fn latitude_to_cell_y(&self, latitude: f64) -> usize {
-        let raster_height = self.raster.len() as f64 / self.raster_width as f64;
-        ((raster_height * (90.0 - latitude) / 180.0).ceil() as usize).saturating_sub(1)
+    /// Returns the given `route` split into the parts that each lie in the same regions, in the
+    /// order of the route.
+    ///
+    /// Each part starts and ends either at the start or end of the route or at where the route
+    /// crosses a border of one of the regions, and has its length in meters. Consecutive positions
+    /// of the route are connected by straight lines in longitude and latitude, taking the shorter
+    /// way, which may be across the 180th longitude. The lengths are the great-circle distances
+    /// between the start and end points and the border crossings in between, on a spherical earth.
+    ///
+    /// Parts of the route that are in no region at all are included with no ids. A route with less
+    /// than two positions has no parts.
+    ///
+    /// # Example
+    /// ```
+    /// # use country_boundaries::{CountryBoundaries, LatLon};
+    /// #
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// # let buf = std::fs::read("./data/boundaries360x180.ser")?;
+    /// # let boundaries = CountryBoundaries::from_reader(buf.as_slice())?;
+    /// // from Aachen to Maastricht
+    /// let route = [LatLon::new(50.7753, 6.0839)?, LatLon::new(50.8514, 5.6910)?];
+    /// let segments = boundaries.route_segments(&route);
+    /// assert_eq!(2, segments.len());
+    /// assert_eq!(vec!["DE"], segments[0].ids);
+    /// assert_eq!(vec!["NL"], segments[1].ids);
+    /// // crossing the border west of Vaals after about 7 km
+    /// assert!((segments[0].end.longitude() - 5.99).abs() < 0.01);
+    /// assert!((segments[0].length - 7_150.0).abs() < 100.0);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn route_segments(&self, route: &[LatLon]) -> Vec<RouteSegment<'_>> {
+        route::route_segments(self, route)
     }
 
-    fn longitude_to_local_x(&self, cell_x: usize, longitude: f64) -> u16 {
-        let raster_width = self.raster_width as f64;
-        let cell_x = cell_x as f64;
-        let cell_longitude = -180.0 + 360.0 * cell_x / raster_width;
-        ((longitude - cell_longitude) * 0xffff as f64 * 360.0 / raster_width).floor() as u16
+    /// Returns the point on the border of the region with the given `id` that is closest to the
+    /// given `position`, or `None` if there is no such region.
+    ///
+    /// This works both for positions inside and outside of the region. As the boundaries in the
+    /// dataset are simplified, it is useful to know how close a position is to a border to judge
+    /// how reliable the answer of e.g. [`is_in`](CountryBoundaries::is_in) is.
+    ///
+    /// The cells of the raster are searched outward from the position. Only the edges of the
+    /// polygons of the region count as its border, and the sides of cells where the region ends
+    /// exactly at the side of the cell. Cells completely covered by the region are its interior.
+    /// The distance is the great-circle distance on a spherical earth.
+    ///
+    /// # Example
+    /// ```
+    /// # use country_boundaries::{CountryBoundaries, LatLon};
+    /// #
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// # let buf = std::fs::read("./data/boundaries360x180.ser")?;
+    /// # let boundaries = CountryBoundaries::from_reader(buf.as_slice())?;
+    /// // Denver is about 140 km south of the northern border of Colorado at 41° latitude
+    /// let border = boundaries.distance_to_border(LatLon::new(39.74, -104.99)?, "US-CO").unwrap();
+    /// assert!((border.distance - 140_000.0).abs() < 1000.0);
+    /// assert!((border.position.latitude() - 41.0).abs() < 0.01);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn distance_to_border(&self, position: LatLon, id: &str) -> Option<BorderPoint<'_>> {
+        let index = self.regions.index(id)?;
+        border::nearest_border(self, position, Some(index)).map(|border| self.border_point(border))
     }
 
-    fn latitude_to_local_y(&self, cell_y: usize, latitude: f64) -> u16 {
-        let raster_width = self.raster_width as f64;
-        let raster_height = self.raster.len() as f64 / raster_width;
-        let cell_y = cell_y as f64;
-        let cell_latitude = 90.0 - 180.0 * (cell_y + 1.0) / raster_height;
-        ((latitude - cell_latitude) * 0xffff as f64 * 180.0 / raster_height).floor() as u16
+    /// Returns the point on the border of any region that is closest to the given `position`, or
+    /// `None` if there are no regions at all.
+    ///
+    /// See [`distance_to_border`](CountryBoundaries::distance_to_border) for what counts as a
+    /// border.
+    ///
+    /// # Example
+    /// ```
+    /// # use country_boundaries::{CountryBoundaries, LatLon};
+    /// #
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// # let buf = std::fs::read("./data/boundaries360x180.ser")?;
+    /// # let boundaries = CountryBoundaries::from_reader(buf.as_slice())?;
+    /// // Kehl, Germany is right across the Rhine from Strasbourg, France
+    /// let border = boundaries.nearest_border(LatLon::new(48.573, 7.815)?).unwrap();
+    /// assert!(border.id == "DE" || border.id == "FR");
+    /// assert!(border.distance < 2000.0);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn nearest_border(&self, position: LatLon) -> Option<BorderPoint<'_>> {
+        border::nearest_border(self, position, None).map(|border| self.border_point(border))
     }
 
-    fn cells(&self, bounds: &BoundingBox) -> impl Iterator<Item = &Cell> {
-        let normalized_min_longitude = normalize(bounds.min_longitude(), -180.0, 360.0);
-        let normalized_max_longitude = normalize(bounds.max_longitude(), -180.0, 360.0);
-
-        let min_x = self.longitude_to_cell_x(normalized_min_longitude);
-        let max_y = self.latitude_to_cell_y(bounds.min_latitude());
-        let max_x = self.longitude_to_cell_x(normalized_max_longitude);
-        let min_y = self.latitude_to_cell_y(bounds.max_latitude());
-
-        let steps_y = max_y - min_y;
-        // might wrap around
-        let steps_x = if min_x > max_x { self.raster_width - min_x + max_x } else { max_x - min_x };
-
-        let mut x_step = 0;
-        let mut y_step = 0;
-
-        std::iter::from_fn(move || {
-            let result = if x_step <= steps_x && y_step <= steps_y {
-                let x = (min_x + x_step) % self.raster_width;
-                let y = min_y + y_step;
-                Some(self.cell(x, y))
-            } else { None };
-            
-            if y_step < steps_y {
-                y_step += 1;
-            } else {
-                y_step = 0;
-                x_step += 1;
+    /// Returns the ids of the regions the given `position` is in or, if it is in none, of the
+    /// region closest to it, if that is at most `max_distance` meters away. Each id comes with the
+    /// distance in meters to the region, which is 0 if the position is in it.
+    ///
+    /// For positions that are in any region, this returns the same ids as
+    /// [`ids`](CountryBoundaries::ids). Otherwise, it returns the closest region and all other
+    /// regions that are just as close, such as the country of the closest subdivision, ordered by
+    /// size like [`ids`](CountryBoundaries::ids). This is useful because the dataset is oblivious
+    /// of sea borders, so e.g. a position just off a simplified coastline is in no region.
+    ///
+    /// The cells of the raster are searched outward from the position, see
+    /// [`distance_to_border`](CountryBoundaries::distance_to_border) for how the distance is
+    /// measured.
+    ///
+    /// # Example
+    /// ```
+    /// # use country_boundaries::{CountryBoundaries, LatLon};
+    /// #
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// # let buf = std::fs::read("./data/boundaries360x180.ser")?;
+    /// # let boundaries = CountryBoundaries::from_reader(buf.as_slice())?;
+    /// // in the Gulf of Mexico, about 110 km off the coast of Louisiana
+    /// let position = LatLon::new(28.0, -92.0)?;
+    /// assert!(boundaries.ids(position).is_empty());
+    /// let nearest = boundaries.nearest_ids(position, 150_000.0);
+    /// assert_eq!(vec!["US-LA", "US"], nearest.iter().map(|(id, _)| *id).collect::<Vec<_>>());
+    /// assert!((nearest[0].1 - 108_000.0).abs() < 1000.0);
+    /// assert!(boundaries.nearest_ids(position, 100_000.0).is_empty());
+    ///
+    /// // inside a region, the result is the same as for `ids`
+    /// assert_eq!(
+    ///     vec![("US-TX", 0.0), ("US", 0.0)],
+    ///     boundaries.nearest_ids(LatLon::new(33.0, -97.0)?, 150_000.0)
+    /// );
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn nearest_ids(&self, position: LatLon, max_distance: f64) -> Vec<(&str, f64)> {
+        let ids = self.ids(position);
+        if !ids.is_empty() {
+            return ids.into_iter().map(|id| (id, 0.0)).collect();
+        }
+        let borders = border::nearest_borders(self, position, max_distance, NEAREST_MARGIN);
+        let mut ids: Vec<RegionIndex> = borders.iter().map(|border| border.id).collect();
+        self.sort_by_size(&mut ids);
+        ids.into_iter()
+            .filter_map(|id| borders.iter().find(|border| border.id == id))
+            .map(|border| (self.regions.id(border.id), border.distance))
+            .collect()
+    }
+
+    /// Returns the ids of the regions the given `position` is in, split into those it is
+    /// certainly in and those it might or might not be in, because their border is at most
+    /// `tolerance` meters away.
+    ///
+    /// As the boundaries in the dataset are simplified, a position close to a border might be on
+    /// the wrong side of it. The uncertain regions include those the position is in according to
+    /// [`ids`](CountryBoundaries::ids) and those it is not in but close to. See
+    /// [`distance_to_border`](CountryBoundaries::distance_to_border) for how the distance is
+    /// measured.
+    ///
+    /// # Example
+    /// ```
+    /// # use country_boundaries::{CountryBoundaries, LatLon};
+    /// #
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// # let buf = std::fs::read("./data/boundaries360x180.ser")?;
+    /// # let boundaries = CountryBoundaries::from_reader(buf.as_slice())?;
+    /// // Vaals in the Netherlands is right at the German border
+    /// let result = boundaries.ids_with_confidence(LatLon::new(50.7706, 6.0166)?, 1000.0);
+    /// assert!(result.certain.is_empty());
+    /// assert!(result.uncertain.contains(&"NL"));
+    /// assert!(result.uncertain.contains(&"DE"));
+    ///
+    /// // Dallas is far away from any border
+    /// let result = boundaries.ids_with_confidence(LatLon::new(33.0, -97.0)?, 1000.0);
+    /// assert_eq!(vec!["US-TX", "US"], result.certain);
+    /// assert!(result.uncertain.is_empty());
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn ids_with_confidence(&self, position: LatLon, tolerance: f64) -> IdsWithConfidence<'_> {
+        let (cell, point) = self.cell_and_local_point(position);
+        let borders = border::borders_within(self, position, tolerance);
+        let (mut uncertain, mut certain): (Vec<RegionIndex>, Vec<RegionIndex>) = cell.get_ids(point)
+            .into_iter()
+            .partition(|&id| borders.iter().any(|border| border.id == id));
+        for border in borders {
+            if !uncertain.contains(&border.id) {
+                uncertain.push(border.id);
             }
+        }
+        self.sort_by_size(&mut certain);
+        self.sort_by_size(&mut uncertain);
+        IdsWithConfidence {
+            certain: certain.into_iter().map(|index| self.regions.id(index)).collect(),
+            uncertain: uncertain.into_iter().map(|index| self.regions.id(index)).collect()
+        }
+    }
 
-            result
-        })
-        /* 
-        // this would be more elegant and shorter, but it is still experimental
-
-        return std::iter::from_generator(|| {
-            for x_step in 0..=steps_x {
-                let x = (min_x + x_step) % self.raster_width;
-                for y_step in 0..=steps_y {
-                    let y = y_step + min_y;
-                    yield &self.raster[y * self.raster_width + x];
+    /// Returns the ids of the regions that contain or at least intersect with the circle with the
+    /// given `radius` in meters around the given `position`.
+    ///
+    /// Only the cells of the raster the circle overlaps are visited. In cells that are only partly
+    /// covered by a region, the distance from the position to the edges of the region's polygons
+    /// is checked, so contrary to a [`BoundingBox`] around the circle passed to
+    /// [`intersecting_ids`](CountryBoundaries::intersecting_ids), this does not return regions that
+    /// are merely close to the circle. The circle may span the 180th longitude or a pole. See
+    /// [`distance_to_border`](CountryBoundaries::distance_to_border) for how the distance is
+    /// measured.
+    ///
+    /// # Example
+    /// ```
+    /// # use country_boundaries::{CountryBoundaries, LatLon};
+    /// # use std::collections::HashSet;
+    /// #
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// # let buf = std::fs::read("./data/boundaries360x180.ser")?;
+    /// # let boundaries = CountryBoundaries::from_reader(buf.as_slice())?;
+    /// // Geneva Airport is right at the French border
+    /// let geneva_airport = LatLon::new(46.238, 6.109)?;
+    /// assert_eq!(
+    ///     HashSet::from(["CH", "FR"]),
+    ///     boundaries.intersecting_ids_within(geneva_airport, 10_000.0)
+    /// );
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn intersecting_ids_within(&self, position: LatLon, radius: f64) -> HashSet<&str> {
+        let (cell, point) = self.cell_and_local_point(position);
+        let mut ids: HashSet<RegionIndex> = cell.get_ids(point).into_iter().collect();
+        ids.extend(border::borders_within(self, position, radius).into_iter().map(|border| border.id));
+        ids.into_iter().map(|index| self.regions.id(index)).collect()
+    }
+
+    fn cell_and_local_point(&self, position: LatLon) -> (&Cell, Point) {
+        let (index, point) = self.raster().cell_and_local_point(position);
+        (&self.raster[index], point)
+    }
+
+    /// Applies `f` to the cell and local point of each of the given `positions`, in chunks that are
+    /// each grouped by cell. With the `rayon` feature, the chunks are processed in parallel.
+    fn batch<T>(&self, positions: &[LatLon], f: impl Fn(&Cell, Point) -> T + Sync) -> Vec<T>
+    where T: Clone + Send {
+        #[cfg(feature = "rayon")]
+        {
+            use rayon::prelude::*;
+            positions.par_chunks(BATCH_CHUNK_SIZE)
+                .flat_map_iter(|chunk| self.batch_chunk(chunk, &f))
+                .collect()
+        }
+        #[cfg(not(feature = "rayon"))]
+        {
+            positions.chunks(BATCH_CHUNK_SIZE)
+                .flat_map(|chunk| self.batch_chunk(chunk, &f))
+                .collect()
+        }
+    }
+
+    fn batch_chunk<T: Clone>(&self, positions: &[LatLon], f: &impl Fn(&Cell, Point) -> T) -> Vec<T> {
+        let raster = self.raster();
+        let mut located: Vec<(usize, Point, usize)> = positions.iter()
+            .enumerate()
+            .map(|(i, &position)| {
+                let (index, point) = raster.cell_and_local_point(position);
+                (index, point, i)
+            })
+            .collect();
+        located.sort_unstable_by_key(|&(index, _, _)| index);
+
+        let mut results: Vec<Option<T>> = vec![None; positions.len()];
+        for group in located.chunk_by(|a, b| a.0 == b.0) {
+            let cell = &self.raster[group[0].0];
+            if cell.intersecting_areas.is_empty() {
+                // all positions in a cell that is completely covered by its regions are in the
+                // same regions
+                let result = f(cell, group[0].1);
+                for &(_, _, i) in group {
+                    results[i] = Some(result.clone());
+                }
+            } else {
+                for &(_, point, i) in group {
+                    results[i] = Some(f(cell, point));
                 }
             }
-        })
-        */
+        }
+        results.into_iter().map(|result| result.expect("every position is in a cell")).collect()
+    }
+
+    fn cells(&self, bounds: &BoundingBox) -> impl Iterator<Item = &Cell> {
+        self.raster().cells(bounds).map(|index| &self.raster[index])
+    }
+
+    fn border_point(&self, border: border::Border) -> BorderPoint<'_> {
+        let (longitude, latitude) = border.position;
+        BorderPoint {
+            id: self.regions.id(border.id),
+            position: LatLon::new(latitude.clamp(-90.0, 90.0), longitude)
+                .expect("position on the border is valid"),
+            distance: border.distance
+        }
+    }
+
+    /// Sorts the given regions by their size, smallest first
+    fn sort_by_size(&self, ids: &mut [RegionIndex]) {
+        ids.sort_by_key(|index| self.ranks[index.index()]);
+    }
+
+    fn raster(&self) -> Raster {
+        Raster::new(self.raster_width, self.raster.len())
+    }
+}
+
+/// The rank, order and index of the ids found in a cell, see [`CountryBoundaries::ids_iter`].
+/// Kept on the stack unless the cell has more than [`INLINE_IDS`] ids.
+#[allow(clippy::large_enum_variant)] // not boxing the large variant is the point
+enum RankedIds {
+    Inline([(u32, u16, RegionIndex); INLINE_IDS], usize),
+    Heap(Vec<(u32, u16, RegionIndex)>)
+}
+
+impl RankedIds {
+    fn new(capacity: usize) -> RankedIds {
+        if capacity <= INLINE_IDS {
+            RankedIds::Inline([(0, 0, RegionIndex(0)); INLINE_IDS], 0)
+        } else {
+            RankedIds::Heap(Vec::with_capacity(capacity))
+        }
+    }
+
+    /// Add the given `id`. For the inline variant, the capacity must not be exceeded
+    fn push(&mut self, id: (u32, u16, RegionIndex)) {
+        match self {
+            RankedIds::Inline(ids, len) => {
+                ids[*len] = id;
+                *len += 1;
+            },
+            RankedIds::Heap(ids) => ids.push(id)
+        }
+    }
+
+    fn len(&self) -> usize {
+        self.as_slice().len()
+    }
+
+    fn as_slice(&self) -> &[(u32, u16, RegionIndex)] {
+        match self {
+            RankedIds::Inline(ids, len) => &ids[..*len],
+            RankedIds::Heap(ids) => ids
+        }
+    }
+
+    fn as_mut_slice(&mut self) -> &mut [(u32, u16, RegionIndex)] {
+        match self {
+            RankedIds::Inline(ids, len) => &mut ids[..*len],
+            RankedIds::Heap(ids) => ids
+        }
     }
 }
 
-fn normalize(value: f64, start_at: f64, base: f64) -> f64 {
-    let mut value = value % base;
-    if value < start_at {
-        value += base;
-    } else if value >= start_at + base {
-        value -= base;
-    } 
-    value
+#[cfg(test)]
+impl CountryBoundaries {
+    /// Create a CountryBoundaries from cells in which the regions are given by their ids. The id
+    /// table consists of the ids of the given `geometry_sizes` plus all other ids in the cells.
+    #[allow(clippy::type_complexity)]
+    pub(crate) fn from_cells(
+        raster: Vec<(Vec<&str>, Vec<(&str, cell::multipolygon::Multipolygon)>)>,
+        raster_width: usize,
+        geometry_sizes: &[(&str, f64)]
+    ) -> CountryBoundaries {
+        let mut regions = Regions::default();
+        for &(id, size) in geometry_sizes {
+            regions.insert_with_size(id, size);
+        }
+        let raster = raster.into_iter()
+            .map(|(containing_ids, intersecting_areas)| Cell {
+                containing_ids: containing_ids.into_iter().map(|id| regions.insert(id)).collect(),
+                intersecting_areas: intersecting_areas.into_iter()
+                    .map(|(id, multipolygon)| (regions.insert(id), multipolygon))
+                    .collect()
+            })
+            .collect();
+        CountryBoundaries::new(raster, raster_width, regions)
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use crate::LatLon;
+    use crate::cell::multipolygon::Multipolygon;
 
     use super::*;
 
     // just a convenience macro that constructs a cell
     macro_rules! cell {
         ($containing_ids: expr) => {
-            Cell {
-                containing_ids: $containing_ids.iter().map(|&s| String::from(s)).collect(),
-                intersecting_areas: vec![]
-            }
+            ($containing_ids.to_vec(), vec![])
         };
         ($containing_ids: expr, $intersecting_areas: expr) => {
-            Cell {
-                containing_ids: $containing_ids.iter().map(|&s| String::from(s)).collect(),
-                intersecting_areas: $intersecting_areas
-            }
+            ($containing_ids.to_vec(), $intersecting_areas)
         }
     }
 
@@ -393,6 +1263,31 @@ mod tests {
         BoundingBox::new(min_latitude, min_longitude, max_latitude, max_longitude).unwrap()
     }
 
+    /// The world, with A covering the cell 0..180 and a sliver of A in the upper right corner of
+    /// the cell -180..0, so that the side between the two cells is only a border of A below the
+    /// equator:
+    /// ┌──┬──┬──┐
+    /// │  │ ▝│AA│
+    /// └──┴──┴──┘
+    fn boundaries_with_sliver() -> CountryBoundaries {
+        CountryBoundaries::from_cells(
+            vec![
+                cell!(&[] as &[&str; 0], vec![("A", Multipolygon {
+                    outer: vec![vec![
+                        Point { x: 0xf000, y: 0x8000 },
+                        Point { x: 0xffff, y: 0x8000 },
+                        Point { x: 0xffff, y: 0xffff },
+                        Point { x: 0xf000, y: 0xffff },
+                    ]],
+                    inner: vec![]
+                })]),
+                cell!(&["A"])
+            ],
+            2,
+            &[("A", 10.0)]
+        )
+    }
+
     #[test]
     fn delegates_to_correct_cell_at_edges() {
         // the world:
@@ -401,11 +1296,11 @@ mod tests {
         // ├─┼─┤
         // │C│D│
         // └─┴─┘
-        let boundaries = CountryBoundaries {
-            raster: vec![cell!(&["A"]), cell!(&["B"]), cell!(&["C"]), cell!(&["D"])],
-            raster_width: 2,
-            geometry_sizes: HashMap::new()
-        };
+        let boundaries = CountryBoundaries::from_cells(
+            vec![cell!(&["A"]), cell!(&["B"]), cell!(&["C"]), cell!(&["D"])],
+            2,
+            &[]
+        );
 
         assert_eq!(vec!["C"], boundaries.ids(latlon(-90.0, -180.0)));
         assert_eq!(vec!["C"], boundaries.ids(latlon(-90.0, -90.0)));
@@ -431,13 +1326,43 @@ mod tests {
     }
 
 
+    #[test]
+    fn local_point_is_relative_to_cell_size() {
+        // the world, with A covering the left half of the cell -180..0 and the whole other cell:
+        // ┌──┬──┬──┐
+        // │A │  │AA│
+        // └──┴──┴──┘
+        let boundaries = CountryBoundaries::from_cells(
+            vec![
+                cell!(&[] as &[&str; 0], vec![("A", Multipolygon {
+                    outer: vec![vec![
+                        Point { x: 0, y: 0 },
+                        Point { x: 0x7fff, y: 0 },
+                        Point { x: 0x7fff, y: 0xffff },
+                        Point { x: 0, y: 0xffff },
+                    ]],
+                    inner: vec![]
+                })]),
+                cell!(&["A"])
+            ],
+            2,
+            &[]
+        );
+
+        assert!(boundaries.is_in(latlon(0.0, -135.0), "A"));
+        assert!(boundaries.is_in(latlon(-60.0, -91.0), "A"));
+        assert!(!boundaries.is_in(latlon(0.0, -89.0), "A"));
+        assert!(!boundaries.is_in(latlon(60.0, -45.0), "A"));
+        assert!(boundaries.is_in(latlon(60.0, 45.0), "A"));
+    }
+
     #[test]
     fn no_array_index_out_of_bounds_at_world_edges() {
-        let boundaries = CountryBoundaries {
-            raster: vec![cell!(&["A"])],
-            raster_width: 1,
-            geometry_sizes: HashMap::new()
-        };
+        let boundaries = CountryBoundaries::from_cells(
+            vec![cell!(&["A"])],
+            1,
+            &[]
+        );
 
         boundaries.ids(latlon(-90.0, -180.0));
         boundaries.ids(latlon(90.0, 180.0));
@@ -447,26 +1372,26 @@ mod tests {
 
     #[test]
     fn get_containing_ids_sorted_by_size_ascending() {
-        let boundaries = CountryBoundaries {
-            raster: vec![cell!(&["D","B","C","A"])],
-            raster_width: 1,
-            geometry_sizes: HashMap::from([
-                (String::from("A"), 10.0),
-                (String::from("B"), 15.0),
-                (String::from("C"), 100.0),
-                (String::from("D"), 800.0),
-            ])
-        };
+        let boundaries = CountryBoundaries::from_cells(
+            vec![cell!(&["D","B","C","A"])],
+            1,
+            &[
+                ("A", 10.0),
+                ("B", 15.0),
+                ("C", 100.0),
+                ("D", 800.0),
+            ]
+        );
         assert_eq!(vec!["A", "B", "C", "D"], boundaries.ids(latlon(1.0, 1.0)));
     }
 
     #[test]
     fn get_intersecting_ids_in_bbox_is_merged_correctly() {
-        let boundaries = CountryBoundaries {
-            raster: vec![cell!(&["A"]), cell!(&["B"]), cell!(&["C"]), cell!(&["D","E"])],
-            raster_width: 2,
-            geometry_sizes: HashMap::new()
-        };
+        let boundaries = CountryBoundaries::from_cells(
+            vec![cell!(&["A"]), cell!(&["B"]), cell!(&["C"]), cell!(&["D","E"])],
+            2,
+            &[]
+        );
         assert_eq!(
             HashSet::from(["A","B","C","D","E"]),
             boundaries.intersecting_ids(bbox(-10.0,-10.0, 10.0,10.0))
@@ -475,24 +1400,89 @@ mod tests {
 
     #[test]
     fn get_intersecting_ids_in_bbox_wraps_longitude_correctly() {
-        let boundaries = CountryBoundaries {
-            raster: vec![cell!(&["A"]), cell!(&["B"]), cell!(&["C"])],
-            raster_width: 3,
-            geometry_sizes: HashMap::new()
-        };
+        let boundaries = CountryBoundaries::from_cells(
+            vec![cell!(&["A"]), cell!(&["B"]), cell!(&["C"])],
+            3,
+            &[]
+        );
         assert_eq!(
             HashSet::from(["A", "C"]),
             boundaries.intersecting_ids(bbox(0.0, 170.0, 1.0, -170.0))
         )
     }
 
+    #[test]
+    fn get_exact_intersecting_ids_in_bbox() {
+        // the world, with A covering the left half of the cell -180..0 and the whole other cell:
+        // ┌──┬──┬──┐
+        // │A │  │AA│
+        // └──┴──┴──┘
+        let boundaries = CountryBoundaries::from_cells(
+            vec![
+                cell!(&[] as &[&str; 0], vec![("A", Multipolygon {
+                    outer: vec![vec![
+                        Point { x: 0, y: 0 },
+                        Point { x: 0x7fff, y: 0 },
+                        Point { x: 0x7fff, y: 0xffff },
+                        Point { x: 0, y: 0xffff },
+                    ]],
+                    inner: vec![]
+                })]),
+                cell!(&["A"])
+            ],
+            2,
+            &[]
+        );
+        assert!(boundaries.intersecting_ids_exact(bbox(-10.0, -80.0, 10.0, -70.0)).is_empty());
+        assert_eq!(
+            HashSet::from(["A"]),
+            boundaries.intersecting_ids(bbox(-10.0, -80.0, 10.0, -70.0))
+        );
+        assert_eq!(
+            HashSet::from(["A"]),
+            boundaries.intersecting_ids_exact(bbox(-10.0, -100.0, 10.0, -80.0))
+        );
+        assert_eq!(
+            HashSet::from(["A"]),
+            boundaries.intersecting_ids_exact(bbox(-10.0, 10.0, 10.0, 20.0))
+        );
+    }
+
+    #[test]
+    fn get_exact_intersecting_ids_in_bbox_wraps_longitude_correctly() {
+        // the world, with A covering the right half of the cell -180..0:
+        // ┌──┬──┬──┐
+        // │  │ A│BB│
+        // └──┴──┴──┘
+        let boundaries = CountryBoundaries::from_cells(
+            vec![
+                cell!(&[] as &[&str; 0], vec![("A", Multipolygon {
+                    outer: vec![vec![
+                        Point { x: 0x8000, y: 0 },
+                        Point { x: 0xffff, y: 0 },
+                        Point { x: 0xffff, y: 0xffff },
+                        Point { x: 0x8000, y: 0xffff },
+                    ]],
+                    inner: vec![]
+                })]),
+                cell!(&["B"])
+            ],
+            2,
+            &[]
+        );
+        assert_eq!(
+            HashSet::from(["B"]),
+            boundaries.intersecting_ids_exact(bbox(0.0, 170.0, 1.0, -170.0))
+        );
+    }
+
     #[test]
     fn get_containing_ids_in_bbox_wraps_longitude_correctly() {
-        let boundaries = CountryBoundaries {
-            raster: vec![cell!(&["A", "B", "C"]),cell!(&["X"]),cell!(&["A", "B"])],
-            raster_width: 3,
-            geometry_sizes: HashMap::new()
-        };
+        let boundaries = CountryBoundaries::from_cells(
+            vec![cell!(&["A", "B", "C"]),cell!(&["X"]),cell!(&["A", "B"])],
+            3,
+            &[]
+        );
         assert_eq!(
             HashSet::from(["A", "B"]),
             boundaries.containing_ids(bbox(0.0, 170.0, 1.0, -170.0))
@@ -501,27 +1491,497 @@ mod tests {
 
 
     #[test]
-    fn get_containing_ids_in_bbox_returns_correct_result_when_one_cell_is_empty() {
-        let boundaries = CountryBoundaries {
-            raster: vec![cell!(&[] as &[&str; 0]), cell!(&["A"]), cell!(&["A"]), cell!(&["A"])],
-            raster_width: 2,
-            geometry_sizes: HashMap::new()
+    fn get_exact_containing_ids_in_bbox() {
+        // the world, with A covering the left half of the cell -180..0 and the whole other cell:
+        // ┌──┬──┬──┐
+        // │A │  │AA│
+        // └──┴──┴──┘
+        let boundaries = CountryBoundaries::from_cells(
+            vec![
+                cell!(&[] as &[&str; 0], vec![("A", Multipolygon {
+                    outer: vec![vec![
+                        Point { x: 0, y: 0 },
+                        Point { x: 0x7fff, y: 0 },
+                        Point { x: 0x7fff, y: 0xffff },
+                        Point { x: 0, y: 0xffff },
+                    ]],
+                    inner: vec![]
+                })]),
+                cell!(&["A"])
+            ],
+            2,
+            &[]
+        );
+        assert_eq!(
+            HashSet::from(["A"]),
+            boundaries.containing_ids_exact(bbox(-10.0, -170.0, 10.0, -100.0))
+        );
+        assert!(boundaries.containing_ids(bbox(-10.0, -170.0, 10.0, -100.0)).is_empty());
+        // wrapping around the 180th meridian
+        assert_eq!(
+            HashSet::from(["A"]),
+            boundaries.containing_ids_exact(bbox(-10.0, 170.0, 10.0, -170.0))
+        );
+        assert!(boundaries.containing_ids_exact(bbox(-10.0, -100.0, 10.0, -80.0)).is_empty());
+        assert!(boundaries.containing_ids_exact(bbox(-10.0, -80.0, 10.0, 10.0)).is_empty());
+    }
+
+    #[test]
+    fn get_intersecting_and_containing_ids_of_polygon() {
+        // the world, with A covering the left half of the cell -180..0 and the whole other cell:
+        // ┌──┬──┬──┐
+        // │A │  │AA│
+        // └──┴──┴──┘
+        let boundaries = CountryBoundaries::from_cells(
+            vec![
+                cell!(&[] as &[&str; 0], vec![("A", Multipolygon {
+                    outer: vec![vec![
+                        Point { x: 0, y: 0 },
+                        Point { x: 0x7fff, y: 0 },
+                        Point { x: 0x7fff, y: 0xffff },
+                        Point { x: 0, y: 0xffff },
+                    ]],
+                    inner: vec![]
+                })]),
+                cell!(&["A"])
+            ],
+            2,
+            &[]
+        );
+        let triangle = |west: f64, east: f64| vec![
+            latlon(-10.0, west), latlon(-10.0, east), latlon(10.0, (west + east) / 2.0)
+        ];
+        assert!(boundaries.intersecting_ids_polygon(&triangle(-80.0, -70.0), &[]).is_empty());
+        assert!(boundaries.containing_ids_polygon(&triangle(-80.0, -70.0), &[]).is_empty());
+
+        assert_eq!(HashSet::from(["A"]), boundaries.intersecting_ids_polygon(&triangle(-100.0, -80.0), &[]));
+        assert!(boundaries.containing_ids_polygon(&triangle(-100.0, -80.0), &[]).is_empty());
+
+        assert_eq!(HashSet::from(["A"]), boundaries.intersecting_ids_polygon(&triangle(-170.0, -100.0), &[]));
+        assert_eq!(HashSet::from(["A"]), boundaries.containing_ids_polygon(&triangle(-170.0, -100.0), &[]));
+
+        // the polygon may be anywhere in the cell, as long as it is in the part covered by A
+        assert!(boundaries.containing_ids_polygon(&triangle(-100.0, 10.0), &[]).is_empty());
+        assert_eq!(HashSet::from(["A"]), boundaries.containing_ids_polygon(&triangle(10.0, 20.0), &[]));
+    }
+
+    #[test]
+    fn get_ids_of_polygon_wraps_longitude_correctly() {
+        // the world, with A covering the right half of the cell -180..0:
+        // ┌──┬──┬──┐
+        // │  │ A│BB│
+        // └──┴──┴──┘
+        let boundaries = CountryBoundaries::from_cells(
+            vec![
+                cell!(&[] as &[&str; 0], vec![("A", Multipolygon {
+                    outer: vec![vec![
+                        Point { x: 0x8000, y: 0 },
+                        Point { x: 0xffff, y: 0 },
+                        Point { x: 0xffff, y: 0xffff },
+                        Point { x: 0x8000, y: 0xffff },
+                    ]],
+                    inner: vec![]
+                })]),
+                cell!(&["B"])
+            ],
+            2,
+            &[]
+        );
+        let across_180th_meridian = vec![
+            latlon(-1.0, 170.0), latlon(-1.0, -170.0), latlon(1.0, -170.0), latlon(1.0, 170.0)
+        ];
+        assert_eq!(HashSet::from(["B"]), boundaries.intersecting_ids_polygon(&across_180th_meridian, &[]));
+        assert!(boundaries.containing_ids_polygon(&across_180th_meridian, &[]).is_empty());
+    }
+
+    #[test]
+    fn get_route_segments() {
+        // the world, with A covering the right half of the cell -180..0:
+        // ┌──┬──┬──┐
+        // │  │ A│BB│
+        // └──┴──┴──┘
+        let boundaries = CountryBoundaries::from_cells(
+            vec![
+                cell!(&[] as &[&str; 0], vec![("A", Multipolygon {
+                    outer: vec![vec![
+                        Point { x: 0x8000, y: 0 },
+                        Point { x: 0xffff, y: 0 },
+                        Point { x: 0xffff, y: 0xffff },
+                        Point { x: 0x8000, y: 0xffff },
+                    ]],
+                    inner: vec![]
+                })]),
+                cell!(&["B"])
+            ],
+            2,
+            &[]
+        );
+        let one_degree = 111_195.08;
+
+        let segments = boundaries.route_segments(&[latlon(0.0, -135.0), latlon(0.0, 30.0)]);
+        assert_eq!(3, segments.len());
+        assert!(segments[0].ids.is_empty());
+        assert_eq!(vec!["A"], segments[1].ids);
+        assert_eq!(vec!["B"], segments[2].ids);
+        assert_eq!(-135.0, segments[0].start.longitude());
+        assert!((segments[0].end.longitude() - -90.0).abs() < 0.01);
+        assert_eq!(segments[0].end.longitude(), segments[1].start.longitude());
+        assert_eq!(0.0, segments[1].end.longitude());
+        assert_eq!(30.0, segments[2].end.longitude());
+        assert!((segments[0].length - 45.0 * one_degree).abs() < 2000.0);
+        assert!((segments[1].length - 90.0 * one_degree).abs() < 2000.0);
+        assert!((segments[2].length - 30.0 * one_degree).abs() < 2000.0);
+
+        // across the 180th meridian
+        let segments = boundaries.route_segments(&[latlon(0.0, 135.0), latlon(0.0, -135.0)]);
+        assert_eq!(2, segments.len());
+        assert_eq!(vec!["B"], segments[0].ids);
+        assert!(segments[1].ids.is_empty());
+        assert_eq!(-180.0, segments[0].end.longitude());
+        assert!((segments[0].length - 45.0 * one_degree).abs() < 1.0);
+        assert!((segments[1].length - 45.0 * one_degree).abs() < 1.0);
+
+        // consecutive parts in the same regions are merged
+        let segments = boundaries.route_segments(&[latlon(0.0, 10.0), latlon(10.0, 20.0), latlon(0.0, 30.0)]);
+        assert_eq!(1, segments.len());
+        assert_eq!(30.0, segments[0].end.longitude());
+
+        assert!(boundaries.route_segments(&[latlon(0.0, 10.0)]).is_empty());
+    }
+
+    #[test]
+    fn get_distance_to_border() {
+        // the world, with A covering the right half of the cell -180..0:
+        // ┌──┬──┬──┐
+        // │  │ A│BB│
+        // └──┴──┴──┘
+        let boundaries = CountryBoundaries::from_cells(
+            vec![
+                cell!(&[] as &[&str; 0], vec![("A", Multipolygon {
+                    outer: vec![vec![
+                        Point { x: 0x8000, y: 0 },
+                        Point { x: 0xffff, y: 0 },
+                        Point { x: 0xffff, y: 0xffff },
+                        Point { x: 0x8000, y: 0xffff },
+                    ]],
+                    inner: vec![]
+                })]),
+                cell!(&["B"])
+            ],
+            2,
+            &[]
+        );
+        let one_degree = 111_195.08;
+
+        // the side of the cell is the border of A, because B is on the other side
+        let border = boundaries.distance_to_border(latlon(0.0, -30.0), "A").unwrap();
+        assert_eq!("A", border.id);
+        assert!((border.distance - 30.0 * one_degree).abs() < 1.0);
+        assert!(border.position.longitude().abs() < 0.01);
+        let border = boundaries.distance_to_border(latlon(10.0, -80.0), "A").unwrap();
+        assert!((border.position.longitude() - -90.0).abs() < 0.01);
+        // on a sphere, the closest point on a meridian is a bit closer to the pole
+        assert!((10.0..10.2).contains(&border.position.latitude()));
+
+        // across the 180th meridian
+        let border = boundaries.distance_to_border(latlon(0.0, 170.0), "B").unwrap();
+        assert!((border.distance - 10.0 * one_degree).abs() < 1.0);
+        let border = boundaries.distance_to_border(latlon(0.0, -170.0), "B").unwrap();
+        assert!((border.distance - 10.0 * one_degree).abs() < 1.0);
+
+        assert!(boundaries.distance_to_border(latlon(0.0, 0.0), "C").is_none());
+
+        let border = boundaries.nearest_border(latlon(0.0, -80.0)).unwrap();
+        assert_eq!("A", border.id);
+        // 0x8000 is not exactly in the middle of the cell
+        assert!((border.distance - 10.0 * one_degree).abs() < 500.0);
+        let border = boundaries.nearest_border(latlon(0.0, 175.0)).unwrap();
+        assert_eq!("B", border.id);
+        assert!((border.distance - 5.0 * one_degree).abs() < 1.0);
+    }
+
+    #[test]
+    fn get_distance_to_border_along_side_of_cell() {
+        let boundaries = boundaries_with_sliver();
+        let one_degree = 111_195.08;
+
+        // below the equator, the side of the cell is the border of A, even though the cell on the
+        // other side contains a bit of A
+        let border = boundaries.distance_to_border(latlon(-45.0, 10.0), "A").unwrap();
+        assert!(border.position.longitude().abs() < 0.01);
+        assert!((-46.0..-45.0).contains(&border.position.latitude()));
+        assert!((border.distance - 7.053 * one_degree).abs() < 0.01 * one_degree);
+        // above the equator, it is not
+        let border = boundaries.distance_to_border(latlon(45.0, 10.0), "A").unwrap();
+        assert!((border.position.longitude() - -11.25).abs() < 0.01);
+    }
+
+    #[test]
+    fn get_nearest_ids() {
+        // the world, with A covering the right half of the cell -180..0 and B and its
+        // subdivision B-1 covering the cell 0..180:
+        // ┌──┬──┬──┐
+        // │  │ A│BB│
+        // └──┴──┴──┘
+        let boundaries = CountryBoundaries::from_cells(
+            vec![
+                cell!(&[] as &[&str; 0], vec![("A", Multipolygon {
+                    outer: vec![vec![
+                        Point { x: 0x8000, y: 0 },
+                        Point { x: 0xffff, y: 0 },
+                        Point { x: 0xffff, y: 0xffff },
+                        Point { x: 0x8000, y: 0xffff },
+                    ]],
+                    inner: vec![]
+                })]),
+                cell!(&["B", "B-1"])
+            ],
+            2,
+            &[("A", 10.0), ("B", 20.0), ("B-1", 5.0)]
+        );
+        let one_degree = 111_195.08;
+
+        assert_eq!(vec![("B-1", 0.0), ("B", 0.0)], boundaries.nearest_ids(latlon(0.0, 10.0), 0.0));
+        assert_eq!(vec![("A", 0.0)], boundaries.nearest_ids(latlon(0.0, -10.0), 0.0));
+
+        let nearest = boundaries.nearest_ids(latlon(0.0, -170.0), 20.0 * one_degree);
+        assert_eq!(vec!["B-1", "B"], nearest.iter().map(|(id, _)| *id).collect::<Vec<_>>());
+        assert!((nearest[0].1 - 10.0 * one_degree).abs() < 1.0);
+        assert_eq!(nearest[0].1, nearest[1].1);
+
+        let nearest = boundaries.nearest_ids(latlon(0.0, -100.0), 20.0 * one_degree);
+        assert_eq!(vec!["A"], nearest.iter().map(|(id, _)| *id).collect::<Vec<_>>());
+
+        assert!(boundaries.nearest_ids(latlon(0.0, -135.0), 40.0 * one_degree).is_empty());
+    }
+
+    #[test]
+    fn get_nearest_ids_with_border_along_side_of_cell() {
+        let boundaries = boundaries_with_sliver();
+        let one_degree = 111_195.08;
+
+        let nearest = boundaries.nearest_ids(latlon(-45.0, -10.0), 8.0 * one_degree);
+        assert_eq!(vec!["A"], nearest.iter().map(|(id, _)| *id).collect::<Vec<_>>());
+        assert!((nearest[0].1 - 7.053 * one_degree).abs() < 0.01 * one_degree);
+    }
+
+    #[test]
+    fn get_ids_with_confidence() {
+        // the world, with A covering the right half of the cell -180..0 and B and its
+        // subdivision B-1 covering the cell 0..180:
+        // ┌──┬──┬──┐
+        // │  │ A│BB│
+        // └──┴──┴──┘
+        let boundaries = CountryBoundaries::from_cells(
+            vec![
+                cell!(&[] as &[&str; 0], vec![("A", Multipolygon {
+                    outer: vec![vec![
+                        Point { x: 0x8000, y: 0 },
+                        Point { x: 0xffff, y: 0 },
+                        Point { x: 0xffff, y: 0xffff },
+                        Point { x: 0x8000, y: 0xffff },
+                    ]],
+                    inner: vec![]
+                })]),
+                cell!(&["B", "B-1"])
+            ],
+            2,
+            &[("A", 10.0), ("B", 20.0), ("B-1", 5.0)]
+        );
+        let one_degree = 111_195.08;
+
+        let result = boundaries.ids_with_confidence(latlon(0.0, 90.0), one_degree);
+        assert_eq!(vec!["B-1", "B"], result.certain);
+        assert!(result.uncertain.is_empty());
+
+        // close to the border between A and B
+        let result = boundaries.ids_with_confidence(latlon(0.0, 0.5), one_degree);
+        assert!(result.certain.is_empty());
+        assert_eq!(vec!["B-1", "A", "B"], result.uncertain);
+        let result = boundaries.ids_with_confidence(latlon(0.0, -0.5), one_degree);
+        assert!(result.certain.is_empty());
+        assert_eq!(vec!["B-1", "A", "B"], result.uncertain);
+
+        // close to the border of A, but not of B
+        let result = boundaries.ids_with_confidence(latlon(0.0, -89.5), one_degree);
+        assert!(result.certain.is_empty());
+        assert_eq!(vec!["A"], result.uncertain);
+        let result = boundaries.ids_with_confidence(latlon(0.0, -91.0), 0.5 * one_degree);
+        assert_eq!(IdsWithConfidence::default(), result);
+    }
+
+    #[test]
+    fn get_intersecting_ids_within_radius() {
+        // the world, with A covering the right half of the cell -180..0 and B and its
+        // subdivision B-1 covering the cell 0..180:
+        // ┌──┬──┬──┐
+        // │  │ A│BB│
+        // └──┴──┴──┘
+        let boundaries = CountryBoundaries::from_cells(
+            vec![
+                cell!(&[] as &[&str; 0], vec![("A", Multipolygon {
+                    outer: vec![vec![
+                        Point { x: 0x8000, y: 0 },
+                        Point { x: 0xffff, y: 0 },
+                        Point { x: 0xffff, y: 0xffff },
+                        Point { x: 0x8000, y: 0xffff },
+                    ]],
+                    inner: vec![]
+                })]),
+                cell!(&["B", "B-1"])
+            ],
+            2,
+            &[("A", 10.0), ("B", 20.0), ("B-1", 5.0)]
+        );
+        let one_degree = 111_195.08;
+
+        assert_eq!(HashSet::from(["B", "B-1"]), boundaries.intersecting_ids_within(latlon(0.0, 90.0), one_degree));
+        assert_eq!(HashSet::from(["A", "B", "B-1"]), boundaries.intersecting_ids_within(latlon(0.0, 1.0), 2.0 * one_degree));
+        assert_eq!(HashSet::from(["A"]), boundaries.intersecting_ids_within(latlon(0.0, -91.0), 2.0 * one_degree));
+        assert!(boundaries.intersecting_ids_within(latlon(0.0, -91.0), 0.5 * one_degree).is_empty());
+        // across the 180th meridian
+        assert!(boundaries.intersecting_ids_within(latlon(0.0, -179.0), 0.5 * one_degree).is_empty());
+        assert_eq!(HashSet::from(["B", "B-1"]), boundaries.intersecting_ids_within(latlon(0.0, -179.0), 2.0 * one_degree));
+        // across the pole
+        assert_eq!(HashSet::from(["A", "B", "B-1"]), boundaries.intersecting_ids_within(latlon(89.0, -135.0), 3.0 * one_degree));
+        assert!(boundaries.intersecting_ids_within(latlon(89.0, -135.0), 0.5 * one_degree).is_empty());
+    }
+
+    #[test]
+    fn get_ids_into_and_iter_are_sorted_by_size() {
+        // one cell covering the whole world, completely covered by A, B and E and with C covering
+        // the right half and D covering the left half of it
+        let half = |min_x: u16, max_x: u16| Multipolygon {
+            outer: vec![vec![
+                Point { x: min_x, y: 0 },
+                Point { x: max_x, y: 0 },
+                Point { x: max_x, y: 0xffff },
+                Point { x: min_x, y: 0xffff },
+            ]],
+            inner: vec![]
         };
+        let boundaries = CountryBoundaries::from_cells(
+            vec![cell!(&["A", "B", "E"], vec![("C", half(0x8000, 0xffff)), ("D", half(0, 0x8000))])],
+            1,
+            &[("A", 3.0), ("B", 1.0), ("C", 3.0), ("D", 0.5)]
+        );
+        // E has no size, so it counts as the smallest. A and C have the same size, so they stay
+        // in the order of the cell
+        for (position, expected) in [
+            (latlon(0.0, 90.0), vec!["E", "B", "A", "C"]),
+            (latlon(0.0, -90.0), vec!["E", "D", "B", "A"]),
+        ] {
+            assert_eq!(expected, boundaries.ids(position));
+            assert_eq!(expected, boundaries.ids_iter(position).collect::<Vec<_>>());
+            let mut ids = vec!["X"];
+            boundaries.ids_into(position, &mut ids);
+            assert_eq!([vec!["X"], expected].concat(), ids);
+        }
+    }
+
+    #[test]
+    fn get_ids_iter_of_cell_with_many_ids() {
+        let half = |min_x: u16, max_x: u16| Multipolygon {
+            outer: vec![vec![
+                Point { x: min_x, y: 0 },
+                Point { x: max_x, y: 0 },
+                Point { x: max_x, y: 0xffff },
+                Point { x: min_x, y: 0xffff },
+            ]],
+            inner: vec![]
+        };
+        for count in [INLINE_IDS / 2, INLINE_IDS, 2 * INLINE_IDS, 255] {
+            let ids: Vec<String> = (0..count).map(|i| format!("R{i}")).collect();
+            // some sizes are the same and some regions have no size
+            let sizes: Vec<(&str, f64)> = ids.iter()
+                .enumerate()
+                .filter(|(i, _)| i % 7 != 0)
+                .map(|(i, id)| (id.as_str(), ((i * 37) % 11) as f64))
+                .collect();
+            let (containing, intersecting) = ids.split_at(count / 2);
+            let boundaries = CountryBoundaries::from_cells(
+                vec![cell!(
+                    &containing.iter().map(String::as_str).collect::<Vec<_>>(),
+                    intersecting.iter()
+                        .enumerate()
+                        .map(|(i, id)| {
+                            let area = if i % 2 == 0 { half(0, 0x8000) } else { half(0x8000, 0xffff) };
+                            (id.as_str(), area)
+                        })
+                        .collect()
+                )],
+                1,
+                &sizes
+            );
+            for position in [latlon(0.0, 90.0), latlon(0.0, -90.0)] {
+                assert_eq!(boundaries.ids(position), boundaries.ids_iter(position).collect::<Vec<_>>());
+            }
+        }
+    }
+
+    #[test]
+    fn get_ids_and_is_in_any_of_batch() {
+        // the world, with A covering the right half of the cell -180..0 and B and its
+        // subdivision B-1 covering the cell 0..180:
+        // ┌──┬──┬──┐
+        // │  │ A│BB│
+        // └──┴──┴──┘
+        let boundaries = CountryBoundaries::from_cells(
+            vec![
+                cell!(&[] as &[&str; 0], vec![("A", Multipolygon {
+                    outer: vec![vec![
+                        Point { x: 0x8000, y: 0 },
+                        Point { x: 0xffff, y: 0 },
+                        Point { x: 0xffff, y: 0xffff },
+                        Point { x: 0x8000, y: 0xffff },
+                    ]],
+                    inner: vec![]
+                })]),
+                cell!(&["B", "B-1"])
+            ],
+            2,
+            &[("A", 10.0), ("B", 20.0), ("B-1", 5.0)]
+        );
+        let positions = [
+            latlon(0.0, 10.0),
+            latlon(0.0, -100.0),
+            latlon(0.0, -80.0),
+            latlon(50.0, 170.0),
+            latlon(-50.0, -10.0),
+        ];
+        assert_eq!(
+            vec![vec!["B-1", "B"], vec![], vec!["A"], vec!["B-1", "B"], vec!["A"]],
+            boundaries.ids_batch(&positions)
+        );
+        assert_eq!(
+            vec![false, false, true, false, true],
+            boundaries.is_in_any_batch(&positions, &HashSet::from(["A", "C"]))
+        );
+        assert!(boundaries.ids_batch(&[]).is_empty());
+    }
+
+    #[test]
+    fn get_containing_ids_in_bbox_returns_correct_result_when_one_cell_is_empty() {
+        let boundaries = CountryBoundaries::from_cells(
+            vec![cell!(&[] as &[&str; 0]), cell!(&["A"]), cell!(&["A"]), cell!(&["A"])],
+            2,
+            &[]
+        );
         assert!(boundaries.containing_ids(bbox(-10.0, -10.0, 10.0, 10.0)).is_empty())
     }
 
     #[test]
     fn get_containing_ids_in_bbox_is_merged_correctly() {
-        let boundaries = CountryBoundaries {
-            raster: vec![
+        let boundaries = CountryBoundaries::from_cells(
+            vec![
                 cell!(&["A","B"]),
                 cell!(&["B","A"]),
                 cell!(&["C","B","A"]),
                 cell!(&["D","A"]),
             ],
-            raster_width: 2,
-            geometry_sizes: HashMap::new()
-        };
+            2,
+            &[]
+        );
         assert_eq!(
             HashSet::from(["A"]),
             boundaries.containing_ids(bbox(-10.0, -10.0, 10.0, 10.0))
@@ -530,11 +1990,11 @@ mod tests {
 
     #[test]
     fn get_containing_ids_in_bbox_is_merged_correctly_an_nothing_is_left() {
-        let boundaries = CountryBoundaries {
-            raster: vec![cell!(&["A"]), cell!(&["B"]), cell!(&["C"]), cell!(&["D"])],
-            raster_width: 2,
-            geometry_sizes: HashMap::new()
-        };
+        let boundaries = CountryBoundaries::from_cells(
+            vec![cell!(&["A"]), cell!(&["B"]), cell!(&["C"]), cell!(&["D"])],
+            2,
+            &[]
+        );
 
         assert!(
             boundaries.containing_ids(bbox(-10.0, -10.0, 10.0, 10.0)).is_empty()
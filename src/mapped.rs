@@ -0,0 +1,103 @@
+use std::collections::HashSet;
+use std::fs::File;
+use std::path::Path;
+use memmap2::Mmap;
+use crate::boundaries_ref::{Index, View};
+use crate::{BoundingBox, LatLon, LoadError, LoadOptions};
+
+/// Country boundaries that are queried in place from a memory-mapped file.
+///
+/// Multiple processes that open the same file share the same memory, as the file is only loaded
+/// into the page cache of the operating system. Create it with the unsafe
+/// [`CountryBoundaries::open`](crate::CountryBoundaries::open), as the file must not be modified
+/// while it is mapped.
+///
+/// Apart from the above, it is the same as [`CountryBoundariesRef`](crate::CountryBoundariesRef).
+#[derive(Debug)]
+pub struct MappedCountryBoundaries {
+    mmap: Mmap,
+    index: Index
+}
+
+impl MappedCountryBoundaries {
+
+    /// # Safety
+    ///
+    /// The file must not be modified or truncated while the returned value exists, see
+    /// [`CountryBoundaries::open`](crate::CountryBoundaries::open)
+    pub(crate) unsafe fn open(
+        path: &Path,
+        options: &LoadOptions
+    ) -> Result<MappedCountryBoundaries, LoadError> {
+        let file = File::open(path)?;
+        // SAFETY: the caller guarantees that the file is not modified while it is mapped
+        let mmap = unsafe { Mmap::map(&file)? };
+        let index = Index::new(&mmap, options)?;
+        Ok(MappedCountryBoundaries { mmap, index })
+    }
+
+    /// Returns whether the given `position` is in the region with the given `id`
+    ///
+    /// See [`CountryBoundaries::is_in`](crate::CountryBoundaries::is_in)
+    pub fn is_in(&self, position: LatLon, id: &str) -> bool {
+        self.view().is_in(position, id)
+    }
+
+    /// Returns whether the given `position` is in any of the regions with the given `ids`.
+    ///
+    /// See [`CountryBoundaries::is_in_any`](crate::CountryBoundaries::is_in_any)
+    pub fn is_in_any(&self, position: LatLon, ids: &HashSet<&str>) -> bool {
+        self.view().is_in_any(position, ids)
+    }
+
+    /// Returns the ids of the regions the given `position` is contained in, ordered by size of
+    /// the region ascending
+    ///
+    /// See [`CountryBoundaries::ids`](crate::CountryBoundaries::ids)
+    pub fn ids(&self, position: LatLon) -> Vec<&str> {
+        self.view().ids(position)
+    }
+
+    /// Returns the ids of the regions that fully contain the given bounding box `bounds`.
+    ///
+    /// See [`CountryBoundaries::containing_ids`](crate::CountryBoundaries::containing_ids)
+    pub fn containing_ids(&self, bounds: BoundingBox) -> HashSet<&str> {
+        self.view().containing_ids(bounds)
+    }
+
+    /// Returns the ids of the regions that contain or at lest intersect with the given bounding box
+    /// `bounds`.
+    ///
+    /// See [`CountryBoundaries::intersecting_ids`](crate::CountryBoundaries::intersecting_ids)
+    pub fn intersecting_ids(&self, bounds: BoundingBox) -> HashSet<&str> {
+        self.view().intersecting_ids(bounds)
+    }
+
+    fn view(&self) -> View<'_, '_> {
+        View { data: &self.mmap, index: &self.index }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::CountryBoundaries;
+    use super::*;
+
+    #[test]
+    fn open_and_query() {
+        let boundaries = unsafe { CountryBoundaries::open("./data/boundaries60x30.ser") }.unwrap();
+        let position = LatLon::new(33.0, -97.0).unwrap();
+        assert_eq!(vec!["US-TX", "US"], boundaries.ids(position));
+        assert!(boundaries.is_in(position, "US"));
+    }
+
+    #[test]
+    fn open_missing_file_is_error() {
+        assert!(unsafe { CountryBoundaries::open("./data/missing.ser") }.is_err());
+    }
+
+    #[test]
+    fn open_invalid_file_is_error() {
+        assert!(unsafe { CountryBoundaries::open("./Cargo.toml") }.is_err());
+    }
+}
@@ -0,0 +1,88 @@
+use crate::LoadError;
+
+/// Options for loading a boundaries file, e.g. with
+/// [`CountryBoundaries::from_reader_with_options`](crate::CountryBoundaries::from_reader_with_options).
+///
+/// # Example
+///
+/// Boundaries files from untrusted sources should be loaded with limits, so that a malicious file
+/// cannot make the loading take up a lot of memory:
+/// ```
+/// # use country_boundaries::{CountryBoundaries, LoadOptions};
+/// #
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let buf = std::fs::read("./data/boundaries360x180.ser")?;
+/// let options = LoadOptions {
+///     max_total_bytes: 10_000_000,
+///     max_cells: 360 * 180,
+///     max_ring_length: 1000,
+///     max_id_length: 16,
+///     ..LoadOptions::default()
+/// };
+/// let boundaries = CountryBoundaries::from_reader_with_options(buf.as_slice(), &options)?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LoadOptions {
+    /// Whether to check that the data makes sense while loading it. This is on by default.
+    ///
+    /// The checks are that
+    /// - every ring of a polygon has at least 3 points,
+    /// - every id in the raster also has a geometry size and
+    /// - no id occurs more than once in the same cell.
+    ///
+    /// The raster size is always checked to be a multiple of the raster width, regardless of this
+    /// option.
+    ///
+    /// Only turn it off for data that is known to be valid, e.g. because it has been validated
+    /// before. Queries on invalid data do not panic, but may return nonsense.
+    pub validate: bool,
+    /// Maximum size of the whole data in bytes. Unlimited by default.
+    pub max_total_bytes: usize,
+    /// Maximum number of cells of the raster. Unlimited by default.
+    pub max_cells: usize,
+    /// Maximum number of points of a ring of a polygon. Unlimited by default.
+    pub max_ring_length: usize,
+    /// Maximum length of an id in bytes. Unlimited by default, but the file format does not
+    /// support ids longer than 65535 bytes anyway.
+    pub max_id_length: usize
+}
+
+impl Default for LoadOptions {
+    fn default() -> Self {
+        LoadOptions {
+            validate: true,
+            max_total_bytes: usize::MAX,
+            max_cells: usize::MAX,
+            max_ring_length: usize::MAX,
+            max_id_length: usize::MAX
+        }
+    }
+}
+
+/// A limit of the [`LoadOptions`]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Limit {
+    /// [`LoadOptions::max_total_bytes`]
+    TotalBytes,
+    /// [`LoadOptions::max_cells`]
+    Cells,
+    /// [`LoadOptions::max_ring_length`]
+    RingLength,
+    /// [`LoadOptions::max_id_length`]
+    IdLength
+}
+
+impl LoadOptions {
+    /// Check that the given `value` read at the byte `offset` does not exceed the given `limit`
+    pub(crate) fn check_limit(&self, offset: usize, limit: Limit, value: usize) -> Result<(), LoadError> {
+        let max = match limit {
+            Limit::TotalBytes => self.max_total_bytes,
+            Limit::Cells => self.max_cells,
+            Limit::RingLength => self.max_ring_length,
+            Limit::IdLength => self.max_id_length
+        };
+        if value <= max { Ok(()) } else { Err(LoadError::LimitExceeded { offset, limit, value, max }) }
+    }
+}
@@ -0,0 +1,438 @@
+use crate::cell::Cell;
+use crate::cell::multipolygon::Multipolygon;
+use crate::cell::point::Point;
+use crate::raster::{normalize, Raster};
+use crate::{LatLon, RegionIndex};
+
+/// A position in degrees as x = longitude, y = latitude, or local to a cell
+type Position = (f64, f64);
+
+/// Polygon to query the regions it intersects with or is contained in, in degrees.
+///
+/// The longitudes are continuous, i.e. where an edge crosses the 180th meridian, the following
+/// longitudes continue beyond ±180 instead of jumping to the other side of the world. Edges always
+/// take the shorter way around the world.
+pub(crate) struct Polygon {
+    /// the outer ring, followed by the holes
+    rings: Vec<Vec<Position>>
+}
+
+impl Polygon {
+    pub fn new(outer: &[LatLon], holes: &[Vec<LatLon>]) -> Polygon {
+        let outer = continuous_ring(outer);
+        let reference = outer.first().map_or(0.0, |p| p.0);
+        let mut rings = Vec::with_capacity(1 + holes.len());
+        rings.push(outer);
+        for hole in holes.iter() {
+            let mut hole = continuous_ring(hole);
+            // a hole is within the outer ring, so shift it to be in the same part of the world
+            let shift = hole.first().map_or(0.0, |p| {
+                360.0 * ((reference - p.0) / 360.0).round()
+            });
+            for point in hole.iter_mut() {
+                point.0 += shift;
+            }
+            rings.push(hole);
+        }
+        Polygon { rings }
+    }
+
+    /// Returns the indices of the cells of the given `raster` that this polygon intersects with,
+    /// each with the longitude by which the cell needs to be shifted to get to the part of the
+    /// world the longitudes of this polygon are in
+    fn cells<'a>(&'a self, raster: &'a Raster) -> impl Iterator<Item = (usize, f64)> + 'a {
+        let mut min = (f64::INFINITY, f64::INFINITY);
+        let mut max = (f64::NEG_INFINITY, f64::NEG_INFINITY);
+        for &(x, y) in self.rings.iter().flatten() {
+            min = (min.0.min(x), min.1.min(y));
+            max = (max.0.max(x), max.1.max(y));
+        }
+        let width = raster.width as i64;
+        let to_column = |longitude: f64| (width as f64 * (180.0 + longitude) / 360.0).floor() as i64;
+        let (min_column, max_column, min_row, max_row) = if min.0 <= max.0 && raster.width > 0 {
+            let min_column = to_column(min.0);
+            let max_column = to_column(max.0).min(min_column + width - 1);
+            let min_row = raster.latitude_to_cell_y(max.1);
+            let max_row = raster.latitude_to_cell_y(min.1).min(raster.height.saturating_sub(1));
+            (min_column, max_column, min_row, max_row)
+        } else {
+            // empty polygon or raster
+            (0, -1, 1, 0)
+        };
+
+        (min_column..=max_column)
+            .flat_map(move |column| (min_row..=max_row).map(move |row| (column, row)))
+            .map(move |(column, row)| (
+                raster.cell_index(column.rem_euclid(width) as usize, row),
+                360.0 * column.div_euclid(width) as f64
+            ))
+    }
+
+    /// Returns this polygon in coordinates local to the cell with the given bounds
+    fn to_local(&self, [min_longitude, min_latitude, max_longitude, max_latitude]: [f64; 4]) -> Polygon {
+        let x_scale = 0xffff as f64 / (max_longitude - min_longitude);
+        let y_scale = 0xffff as f64 / (max_latitude - min_latitude);
+        Polygon {
+            rings: self.rings.iter()
+                .map(|ring| ring.iter()
+                    .map(|&(x, y)| ((x - min_longitude) * x_scale, (y - min_latitude) * y_scale))
+                    .collect()
+                )
+                .collect()
+        }
+    }
+
+    fn contains(&self, point: Position) -> bool {
+        let mut rings = self.rings.iter();
+        rings.next().is_some_and(|outer| is_in_ring(point, outer)) &&
+        !rings.any(|hole| is_in_ring(point, hole))
+    }
+
+    fn edges(&self) -> impl Iterator<Item = (Position, Position)> + '_ {
+        self.rings.iter().flat_map(|ring| ring_edges(ring.iter().copied()))
+    }
+}
+
+/// Returns the ids of the regions in the given `raster` that intersect with the given `polygon`
+pub(crate) fn intersecting_ids(raster: &[Cell], raster_width: usize, polygon: &Polygon) -> Vec<RegionIndex> {
+    let mut ids = Vec::new();
+    for_each_touched_cell(raster, raster_width, polygon, |cell, local| {
+        ids.extend(cell.containing_ids.iter().copied());
+        for (id, multipolygon) in cell.intersecting_areas.iter() {
+            if local.intersects(multipolygon) {
+                ids.push(*id);
+            }
+        }
+    });
+    ids
+}
+
+/// Returns the ids of the regions in the given `raster` that completely contain the given
+/// `polygon`
+pub(crate) fn containing_ids(raster: &[Cell], raster_width: usize, polygon: &Polygon) -> Vec<RegionIndex> {
+    let mut ids: Option<Vec<RegionIndex>> = None;
+    for_each_touched_cell(raster, raster_width, polygon, |cell, local| {
+        let covering_ids: Vec<RegionIndex> = cell.containing_ids.iter().copied()
+            .chain(
+                cell.intersecting_areas.iter()
+                    .filter(|(_, multipolygon)| local.is_covered_by(multipolygon))
+                    .map(|(id, _)| *id)
+            )
+            .collect();
+        match &mut ids {
+            None => ids = Some(covering_ids),
+            Some(ids) => ids.retain(|id| covering_ids.contains(id))
+        }
+    });
+    ids.unwrap_or_default()
+}
+
+fn for_each_touched_cell(
+    raster: &[Cell],
+    raster_width: usize,
+    polygon: &Polygon,
+    mut f: impl FnMut(&Cell, &LocalPolygon)
+) {
+    let grid = Raster::new(raster_width, raster.len());
+    for (index, shift) in polygon.cells(&grid) {
+        let [min_longitude, min_latitude, max_longitude, max_latitude] = grid.cell_bounds(index);
+        let bounds = [min_longitude + shift, min_latitude, max_longitude + shift, max_latitude];
+        if let Some(local) = LocalPolygon::new(polygon.to_local(bounds)) {
+            f(&raster[index], &local);
+        }
+    }
+}
+
+/// The part of a polygon within a cell, in coordinates local to that cell
+struct LocalPolygon {
+    polygon: Polygon,
+    /// edges of the polygon that intersect with the cell
+    edges: Vec<(Position, Position)>,
+    /// at least one point of every part of the polygon within the cell
+    samples: Vec<Position>
+}
+
+const CELL: (Position, Position) = ((0.0, 0.0), (0xffff as f64, 0xffff as f64));
+
+impl LocalPolygon {
+    /// Returns the part of the given `polygon` in local coordinates within the cell, or `None` if
+    /// it does not intersect with the cell
+    fn new(polygon: Polygon) -> Option<LocalPolygon> {
+        let mut edges = Vec::new();
+        let mut samples = Vec::new();
+        for (a, b) in polygon.edges() {
+            if let Some((c, d)) = clip_segment(a, b, CELL) {
+                edges.push((a, b));
+                samples.push(((c.0 + d.0) / 2.0, (c.1 + d.1) / 2.0));
+            }
+        }
+        if samples.is_empty() {
+            // no edge within the cell, so the cell is either completely inside or outside
+            let center = (0xffff as f64 / 2.0, 0xffff as f64 / 2.0);
+            if !polygon.contains(center) {
+                return None;
+            }
+            samples.push(center);
+        }
+        Some(LocalPolygon { polygon, edges, samples })
+    }
+
+    /// Returns whether this polygon intersects with the given multipolygon
+    fn intersects(&self, multipolygon: &Multipolygon) -> bool {
+        // either their borders intersect, or one is inside the other
+        multipolygon_edges(multipolygon)
+            .any(|(a, b)| self.edges.iter().any(|&(c, d)| segments_intersect(a, b, c, d))) ||
+        multipolygon.outer.iter()
+            .filter_map(|ring| ring.first())
+            .any(|point| self.polygon.contains(to_position(point))) ||
+        self.samples.iter().any(|&point| covers(multipolygon, point))
+    }
+
+    /// Returns whether this polygon is completely within the given multipolygon
+    fn is_covered_by(&self, multipolygon: &Multipolygon) -> bool {
+        // the border of the multipolygon on the border of the cell does not count, as it just
+        // means that it continues in the neighbouring cell
+        let mut inner_edges = multipolygon_edges(multipolygon).filter(|&(a, b)| !is_on_cell_border(a, b));
+        !inner_edges.any(|(a, b)|
+            self.edges.iter().any(|&(c, d)| segments_intersect(a, b, c, d)) ||
+            self.polygon.contains(((a.0 + b.0) / 2.0, (a.1 + b.1) / 2.0))
+        ) &&
+        self.samples.iter().all(|&point| covers(multipolygon, point))
+    }
+}
+
+/// Returns the given ring with continuous longitudes and without a closing point
+fn continuous_ring(ring: &[LatLon]) -> Vec<Position> {
+    let mut result: Vec<Position> = Vec::with_capacity(ring.len());
+    for position in ring.iter() {
+        let longitude = match result.last() {
+            Some(&(previous, _)) => previous + normalize(position.longitude() - previous, -180.0, 360.0),
+            None => normalize(position.longitude(), -180.0, 360.0)
+        };
+        result.push((longitude, position.latitude()));
+    }
+    if result.len() > 1 && result.first() == result.last() {
+        result.pop();
+    }
+    result
+}
+
+fn to_position(point: &Point) -> Position {
+    (point.x as f64, point.y as f64)
+}
+
+fn ring_edges<I>(ring: I) -> impl Iterator<Item = (Position, Position)>
+where
+    I: Iterator<Item = Position> + Clone
+{
+    ring.clone().zip(ring.cycle().skip(1))
+}
+
+fn multipolygon_edges(multipolygon: &Multipolygon) -> impl Iterator<Item = (Position, Position)> + '_ {
+    multipolygon.outer.iter().chain(multipolygon.inner.iter())
+        .flat_map(|ring| ring_edges(ring.iter().map(to_position)))
+}
+
+fn is_on_cell_border(a: Position, b: Position) -> bool {
+    let max = 0xffff as f64;
+    (a.0 == 0.0 && b.0 == 0.0) || (a.0 == max && b.0 == max) ||
+    (a.1 == 0.0 && b.1 == 0.0) || (a.1 == max && b.1 == max)
+}
+
+fn covers(multipolygon: &Multipolygon, point: Position) -> bool {
+    let outer = multipolygon.outer.iter()
+        .filter(|ring| is_in_ring(point, &ring.iter().map(to_position).collect::<Vec<_>>()))
+        .count();
+    let inner = multipolygon.inner.iter()
+        .filter(|ring| is_in_ring(point, &ring.iter().map(to_position).collect::<Vec<_>>()))
+        .count();
+    outer > inner
+}
+
+/// Winding number test, same as for the points local to a cell
+fn is_in_ring(p: Position, ring: &[Position]) -> bool {
+    let mut wn = 0;
+    for (vi, vj) in ring_edges(ring.iter().copied()) {
+        if vi.1 <= p.1 {
+            if vj.1 > p.1 && side(vi, vj, p) > 0.0 {
+                wn += 1;
+            }
+        } else if vj.1 <= p.1 && side(vi, vj, p) < 0.0 {
+            wn -= 1;
+        }
+    }
+    wn != 0
+}
+
+/// Returns a positive value if `p` is left of the line through `a` and `b`, a negative value if
+/// it is right of it and 0 if it is on it
+fn side(a: Position, b: Position, p: Position) -> f64 {
+    (b.0 - a.0) * (p.1 - a.1) - (p.0 - a.0) * (b.1 - a.1)
+}
+
+/// Returns whether the segments from `a` to `b` and from `c` to `d` intersect or touch
+fn segments_intersect(a: Position, b: Position, c: Position, d: Position) -> bool {
+    let d1 = side(c, d, a);
+    let d2 = side(c, d, b);
+    let d3 = side(a, b, c);
+    let d4 = side(a, b, d);
+    if ((d1 > 0.0 && d2 < 0.0) || (d1 < 0.0 && d2 > 0.0)) &&
+       ((d3 > 0.0 && d4 < 0.0) || (d3 < 0.0 && d4 > 0.0)) {
+        return true;
+    }
+    (d1 == 0.0 && is_within_box(a, c, d)) ||
+    (d2 == 0.0 && is_within_box(b, c, d)) ||
+    (d3 == 0.0 && is_within_box(c, a, b)) ||
+    (d4 == 0.0 && is_within_box(d, a, b))
+}
+
+/// Returns whether `p` is within the bounding box of the segment from `a` to `b`
+fn is_within_box(p: Position, a: Position, b: Position) -> bool {
+    a.0.min(b.0) <= p.0 && p.0 <= a.0.max(b.0) && a.1.min(b.1) <= p.1 && p.1 <= a.1.max(b.1)
+}
+
+/// Returns the part of the segment from `a` to `b` within the given rectangle (Liang–Barsky), or
+/// `None` if it is completely outside
+fn clip_segment(a: Position, b: Position, (min, max): (Position, Position)) -> Option<(Position, Position)> {
+    let delta = (b.0 - a.0, b.1 - a.1);
+    let mut t0: f64 = 0.0;
+    let mut t1: f64 = 1.0;
+    for (p, q) in [
+        (-delta.0, a.0 - min.0),
+        (delta.0, max.0 - a.0),
+        (-delta.1, a.1 - min.1),
+        (delta.1, max.1 - a.1)
+    ] {
+        if p == 0.0 {
+            if q < 0.0 { return None; }
+        } else {
+            let t = q / p;
+            if p < 0.0 {
+                t0 = t0.max(t);
+            } else {
+                t1 = t1.min(t);
+            }
+            if t0 > t1 { return None; }
+        }
+    }
+    Some((
+        (a.0 + t0 * delta.0, a.1 + t0 * delta.1),
+        (a.0 + t1 * delta.0, a.1 + t1 * delta.1)
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ring(points: &[(f64, f64)]) -> Vec<LatLon> {
+        points.iter().map(|&(lon, lat)| LatLon::new(lat, lon).unwrap()).collect()
+    }
+
+    fn p(x: u16, y: u16) -> Point {
+        Point { x, y }
+    }
+
+    fn local(rings: Vec<Vec<Position>>) -> LocalPolygon {
+        LocalPolygon::new(Polygon { rings }).unwrap()
+    }
+
+    fn square(min: f64, max: f64) -> Vec<Position> {
+        vec![(min, min), (max, min), (max, max), (min, max)]
+    }
+
+    #[test]
+    fn ring_is_continuous_across_180th_meridian() {
+        let polygon = Polygon::new(&ring(&[(170.0, 0.0), (-170.0, 0.0), (-170.0, 10.0), (170.0, 10.0), (170.0, 0.0)]), &[]);
+        assert_eq!(vec![vec![(170.0, 0.0), (190.0, 0.0), (190.0, 10.0), (170.0, 10.0)]], polygon.rings);
+    }
+
+    #[test]
+    fn hole_is_shifted_to_outer_ring() {
+        let polygon = Polygon::new(
+            &ring(&[(170.0, 0.0), (-170.0, 0.0), (-170.0, 10.0), (170.0, 10.0)]),
+            &[ring(&[(-175.0, 4.0), (-178.0, 6.0), (-175.0, 6.0)])]
+        );
+        assert_eq!(vec![(185.0, 4.0), (182.0, 6.0), (185.0, 6.0)], polygon.rings[1]);
+    }
+
+    #[test]
+    fn cells_across_180th_meridian() {
+        let raster = Raster::new(4, 8);
+        let polygon = Polygon::new(
+            &ring(&[(170.0, 10.0), (-100.0, 10.0), (-10.0, 10.0), (-10.0, 20.0), (170.0, 20.0)]),
+            &[]
+        );
+        assert_eq!(vec![(3, 0.0), (0, 360.0), (1, 360.0)], polygon.cells(&raster).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn local_polygon_outside_of_cell() {
+        assert!(LocalPolygon::new(Polygon { rings: vec![square(70000.0, 80000.0)] }).is_none());
+        // cell is in the hole
+        assert!(LocalPolygon::new(Polygon { rings: vec![square(-10.0, 70000.0), square(-5.0, 68000.0)] }).is_none());
+    }
+
+    #[test]
+    fn local_polygon_covering_whole_cell() {
+        let polygon = local(vec![square(-10.0, 70000.0)]);
+        assert!(polygon.edges.is_empty());
+        let multipolygon = Multipolygon { outer: vec![vec![p(0, 0), p(10, 0), p(0, 10)]], inner: vec![] };
+        assert!(polygon.intersects(&multipolygon));
+        assert!(!polygon.is_covered_by(&multipolygon));
+    }
+
+    #[test]
+    fn intersects_multipolygon() {
+        let multipolygon = Multipolygon {
+            outer: vec![vec![p(0, 0), p(0x8000, 0), p(0x8000, 0x8000), p(0, 0x8000)]],
+            inner: vec![vec![p(0x2000, 0x2000), p(0x6000, 0x2000), p(0x6000, 0x6000), p(0x2000, 0x6000)]]
+        };
+        // crossing the border
+        assert!(local(vec![square(0x7000 as f64, 0x9000 as f64)]).intersects(&multipolygon));
+        // inside
+        assert!(local(vec![square(0x1000 as f64, 0x1800 as f64)]).intersects(&multipolygon));
+        // around
+        assert!(local(vec![square(-10.0, 0x9000 as f64)]).intersects(&multipolygon));
+        // outside and in the hole
+        assert!(!local(vec![square(0x9000 as f64, 0xa000 as f64)]).intersects(&multipolygon));
+        assert!(!local(vec![square(0x3000 as f64, 0x4000 as f64)]).intersects(&multipolygon));
+    }
+
+    #[test]
+    fn is_covered_by_multipolygon() {
+        let multipolygon = Multipolygon {
+            outer: vec![vec![p(0, 0), p(0x8000, 0), p(0x8000, 0x8000), p(0, 0x8000)]],
+            inner: vec![vec![p(0x2000, 0x2000), p(0x6000, 0x2000), p(0x6000, 0x6000), p(0x2000, 0x6000)]]
+        };
+        assert!(local(vec![square(0x1000 as f64, 0x1800 as f64)]).is_covered_by(&multipolygon));
+        // reaching across the border of the cell into the neighbouring cell
+        assert!(local(vec![square(-100.0, 0x1000 as f64)]).is_covered_by(&multipolygon));
+        // crossing the border, around, outside and in the hole
+        assert!(!local(vec![square(0x7000 as f64, 0x9000 as f64)]).is_covered_by(&multipolygon));
+        assert!(!local(vec![square(-10.0, 0x9000 as f64)]).is_covered_by(&multipolygon));
+        assert!(!local(vec![square(0x9000 as f64, 0xa000 as f64)]).is_covered_by(&multipolygon));
+        assert!(!local(vec![square(0x3000 as f64, 0x4000 as f64)]).is_covered_by(&multipolygon));
+        // a polygon with a hole around the hole of the multipolygon
+        assert!(local(vec![square(0x1000 as f64, 0x7000 as f64), square(0x1800 as f64, 0x6800 as f64)])
+            .is_covered_by(&multipolygon));
+    }
+
+    #[test]
+    fn segments_intersect_or_touch() {
+        assert!(segments_intersect((0.0, 0.0), (10.0, 10.0), (0.0, 10.0), (10.0, 0.0)));
+        assert!(segments_intersect((0.0, 0.0), (10.0, 0.0), (5.0, 0.0), (5.0, 10.0)));
+        assert!(segments_intersect((0.0, 0.0), (10.0, 0.0), (5.0, 0.0), (15.0, 0.0)));
+        assert!(!segments_intersect((0.0, 0.0), (10.0, 0.0), (11.0, 0.0), (15.0, 0.0)));
+        assert!(!segments_intersect((0.0, 0.0), (10.0, 0.0), (5.0, 1.0), (5.0, 10.0)));
+    }
+
+    #[test]
+    fn clip_segment_to_rectangle() {
+        let rect = ((0.0, 0.0), (10.0, 10.0));
+        assert_eq!(Some(((0.0, 5.0), (10.0, 5.0))), clip_segment((-5.0, 5.0), (15.0, 5.0), rect));
+        assert_eq!(Some(((2.0, 2.0), (3.0, 3.0))), clip_segment((2.0, 2.0), (3.0, 3.0), rect));
+        assert_eq!(None, clip_segment((-5.0, 11.0), (15.0, 11.0), rect));
+        assert_eq!(None, clip_segment((-5.0, 4.0), (4.0, -5.0), rect));
+    }
+}
@@ -0,0 +1,199 @@
+use std::cmp::min;
+use crate::cell::point::Point;
+use crate::cell::rect::Rect;
+use crate::{BoundingBox, LatLon};
+
+/// The dimensions of the 2-dimensional array of cells that covers the whole world, and the
+/// conversion from positions to cells and to points local to a cell
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub(crate) struct Raster {
+    pub width: usize,
+    pub height: usize
+}
+
+impl Raster {
+
+    /// Raster with the given `width` for an array of `size` cells
+    pub fn new(width: usize, size: usize) -> Raster {
+        Raster { width, height: size.checked_div(width).unwrap_or(0) }
+    }
+
+    /// Returns the index of the cell the given `position` is in and its position local to that cell
+    pub fn cell_and_local_point(&self, position: LatLon) -> (usize, Point) {
+        let normalized_longitude = normalize(position.longitude(), -180.0, 360.0);
+        let cell_x = self.longitude_to_cell_x(normalized_longitude);
+        let cell_y = self.latitude_to_cell_y(position.latitude());
+
+        (
+            self.cell_index(cell_x, cell_y),
+            Point {
+                x: self.longitude_to_local_x(cell_x, normalized_longitude),
+                y: self.latitude_to_local_y(cell_y, position.latitude())
+            }
+        )
+    }
+
+    pub fn cell_index(&self, x: usize, y: usize) -> usize {
+        y * self.width + x
+    }
+
+    fn longitude_to_cell_x(&self, longitude: f64) -> usize {
+        min(
+            self.width.saturating_sub(1),
+            ((self.width as f64) * (180.0 + longitude) / 360.0).floor() as usize
+        )
+    }
+
+    pub fn latitude_to_cell_y(&self, latitude: f64) -> usize {
+        let raster_height = self.height as f64;
+        ((raster_height * (90.0 - latitude) / 180.0).ceil() as usize).saturating_sub(1)
+    }
+
+    fn longitude_to_local_x(&self, cell_x: usize, longitude: f64) -> u16 {
+        let raster_width = self.width as f64;
+        let cell_x = cell_x as f64;
+        let cell_longitude = -180.0 + 360.0 * cell_x / raster_width;
+        ((longitude - cell_longitude) * 0xffff as f64 * raster_width / 360.0).floor() as u16
+    }
+
+    fn latitude_to_local_y(&self, cell_y: usize, latitude: f64) -> u16 {
+        let raster_height = self.height as f64;
+        let cell_y = cell_y as f64;
+        let cell_latitude = 90.0 - 180.0 * (cell_y + 1.0) / raster_height;
+        ((latitude - cell_latitude) * 0xffff as f64 * raster_height / 180.0).floor() as u16
+    }
+
+    /// Returns the bounds of the cell with the given `index` as minimum longitude, minimum latitude,
+    /// maximum longitude and maximum latitude
+    pub fn cell_bounds(&self, index: usize) -> [f64; 4] {
+        let cell_x = (index % self.width) as f64;
+        let cell_y = (index / self.width) as f64;
+        let raster_width = self.width as f64;
+        let raster_height = self.height as f64;
+        [
+            -180.0 + 360.0 * cell_x / raster_width,
+            90.0 - 180.0 * (cell_y + 1.0) / raster_height,
+            -180.0 + 360.0 * (cell_x + 1.0) / raster_width,
+            90.0 - 180.0 * cell_y / raster_height
+        ]
+    }
+
+    /// Returns the longitude and latitude of the given `point` local to the cell with the given
+    /// `index`, i.e. the inverse of [`cell_and_local_point`](Raster::cell_and_local_point)
+    pub fn longitude_latitude_of(&self, index: usize, point: &Point) -> (f64, f64) {
+        let [min_longitude, min_latitude, max_longitude, max_latitude] = self.cell_bounds(index);
+        (
+            min_longitude + (max_longitude - min_longitude) * point.x as f64 / 0xffff as f64,
+            min_latitude + (max_latitude - min_latitude) * point.y as f64 / 0xffff as f64
+        )
+    }
+
+    /// Returns the parts of the given `bounds` that are within the cell with the given `index`, in
+    /// coordinates local to that cell. These are two if the bounds wrap around the 180th meridian
+    /// and both their ends are in the same cell.
+    pub fn local_rects(&self, index: usize, bounds: &BoundingBox) -> Vec<Rect> {
+        let [min_longitude, min_latitude, max_longitude, max_latitude] = self.cell_bounds(index);
+        if bounds.min_latitude() > max_latitude || bounds.max_latitude() < min_latitude {
+            return Vec::new();
+        }
+        let to_local = |value: f64, min: f64, max: f64| {
+            ((value - min) * 0xffff as f64 / (max - min)).clamp(0.0, 0xffff as f64)
+        };
+        let min_y = to_local(bounds.min_latitude(), min_latitude, max_latitude).floor() as u16;
+        let max_y = to_local(bounds.max_latitude(), min_latitude, max_latitude).ceil() as u16;
+
+        let west = normalize(bounds.min_longitude(), -180.0, 360.0);
+        let east = normalize(bounds.max_longitude(), -180.0, 360.0);
+        let longitudes = if west <= east {
+            vec![(west, east)]
+        } else {
+            vec![(west, 180.0), (-180.0, east)]
+        };
+        longitudes.into_iter()
+            .filter(|&(west, east)| west <= max_longitude && east >= min_longitude)
+            .map(|(west, east)| Rect {
+                min: Point {
+                    x: to_local(west, min_longitude, max_longitude).floor() as u16,
+                    y: min_y
+                },
+                max: Point {
+                    x: to_local(east, min_longitude, max_longitude).ceil() as u16,
+                    y: max_y
+                }
+            })
+            .collect()
+    }
+
+    /// Returns the indices of the cells that intersect with the given `bounds`
+    pub fn cells(&self, bounds: &BoundingBox) -> impl Iterator<Item = usize> {
+        let normalized_min_longitude = normalize(bounds.min_longitude(), -180.0, 360.0);
+        let normalized_max_longitude = normalize(bounds.max_longitude(), -180.0, 360.0);
+
+        let min_x = self.longitude_to_cell_x(normalized_min_longitude);
+        let max_y = self.latitude_to_cell_y(bounds.min_latitude());
+        let max_x = self.longitude_to_cell_x(normalized_max_longitude);
+        let min_y = self.latitude_to_cell_y(bounds.max_latitude());
+
+        let steps_y = max_y - min_y;
+        // might wrap around
+        let steps_x = if min_x > max_x { self.width - min_x + max_x } else { max_x - min_x };
+
+        let raster = *self;
+        let is_empty = raster.width == 0 || raster.height == 0;
+        let mut x_step = 0;
+        let mut y_step = 0;
+
+        std::iter::from_fn(move || {
+            let result = if !is_empty && x_step <= steps_x && y_step <= steps_y {
+                let x = (min_x + x_step) % raster.width;
+                let y = min_y + y_step;
+                Some(raster.cell_index(x, y))
+            } else { None };
+
+            if y_step < steps_y {
+                y_step += 1;
+            } else {
+                y_step = 0;
+                x_step += 1;
+            }
+
+            result
+        })
+        /*
+        // this would be more elegant and shorter, but it is still experimental
+
+        return std::iter::from_generator(|| {
+            for x_step in 0..=steps_x {
+                let x = (min_x + x_step) % self.width;
+                for y_step in 0..=steps_y {
+                    let y = y_step + min_y;
+                    yield self.cell_index(x, y);
+                }
+            }
+        })
+        */
+    }
+}
+
+pub(crate) fn normalize(value: f64, start_at: f64, base: f64) -> f64 {
+    let mut value = value % base;
+    if value < start_at {
+        value += base;
+    } else if value >= start_at + base {
+        value -= base;
+    }
+    value
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn no_cells_in_empty_raster() {
+        let bounds = BoundingBox::new(-10.0, -10.0, 10.0, 10.0).unwrap();
+        assert_eq!(0, Raster::new(0, 0).cells(&bounds).count());
+        assert_eq!(0, Raster::new(2, 0).cells(&bounds).count());
+        assert_eq!(4, Raster::new(2, 4).cells(&bounds).count());
+    }
+}
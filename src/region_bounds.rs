@@ -0,0 +1,165 @@
+use crate::cell::Cell;
+use crate::cell::point::Point;
+use crate::raster::Raster;
+use crate::BoundingBox;
+
+/// Returns the bounding box of each of the `region_count` regions in the given `raster`, in the
+/// order of the id table. It is `None` for regions that are not in any cell.
+///
+/// The bounding box of a region that crosses the 180th meridian wraps around it, i.e. its
+/// minimum longitude is greater than its maximum longitude.
+pub(crate) fn region_bounds(
+    raster: &[Cell],
+    raster_width: usize,
+    region_count: usize
+) -> Vec<Option<BoundingBox>> {
+    let mut extents = vec![Extent::new(); region_count];
+    let grid = Raster::new(raster_width, raster.len());
+
+    for (index, cell) in raster.iter().enumerate() {
+        let [min_longitude, min_latitude, max_longitude, max_latitude] = grid.cell_bounds(index);
+        for id in cell.containing_ids.iter() {
+            extents[id.index()].add(min_longitude, min_latitude, max_longitude, max_latitude);
+        }
+        for (id, multipolygon) in cell.intersecting_areas.iter() {
+            // inner rings are always within outer rings, so they can't extend the bounds
+            let mut points = multipolygon.outer.iter().flatten();
+            let Some(first) = points.next() else { continue };
+            let mut min = *first;
+            let mut max = *first;
+            for point in points {
+                min = Point { x: min.x.min(point.x), y: min.y.min(point.y) };
+                max = Point { x: max.x.max(point.x), y: max.y.max(point.y) };
+            }
+            let (min_longitude, min_latitude) = grid.longitude_latitude_of(index, &min);
+            let (max_longitude, max_latitude) = grid.longitude_latitude_of(index, &max);
+            extents[id.index()].add(min_longitude, min_latitude, max_longitude, max_latitude);
+        }
+    }
+
+    extents.into_iter().map(Extent::into_bounding_box).collect()
+}
+
+/// The latitudes and the longitude ranges covered by a region
+#[derive(Clone)]
+struct Extent {
+    min_latitude: f64,
+    max_latitude: f64,
+    longitudes: Vec<(f64, f64)>
+}
+
+impl Extent {
+    fn new() -> Extent {
+        Extent { min_latitude: f64::INFINITY, max_latitude: f64::NEG_INFINITY, longitudes: vec![] }
+    }
+
+    fn add(&mut self, min_longitude: f64, min_latitude: f64, max_longitude: f64, max_latitude: f64) {
+        self.min_latitude = self.min_latitude.min(min_latitude);
+        self.max_latitude = self.max_latitude.max(max_latitude);
+        self.longitudes.push((min_longitude, max_longitude));
+    }
+
+    fn into_bounding_box(mut self) -> Option<BoundingBox> {
+        self.longitudes.sort_by(|a, b| a.0.total_cmp(&b.0));
+        let mut ranges: Vec<(f64, f64)> = Vec::new();
+        for (min, max) in self.longitudes {
+            match ranges.last_mut() {
+                Some(last) if min <= last.1 => last.1 = last.1.max(max),
+                _ => ranges.push((min, max))
+            }
+        }
+        let first = ranges.first()?;
+        let last = ranges.last()?;
+
+        // the bounding box spans everything but the largest gap between the longitude ranges. If
+        // that gap is not the one across the 180th meridian, the bounding box wraps around it
+        let mut largest_gap = first.0 + 360.0 - last.1;
+        let mut min_longitude = first.0;
+        let mut max_longitude = last.1;
+        for pair in ranges.windows(2) {
+            let gap = pair[1].0 - pair[0].1;
+            if gap > largest_gap {
+                largest_gap = gap;
+                min_longitude = pair[1].0;
+                max_longitude = pair[0].1;
+            }
+        }
+        BoundingBox::new(self.min_latitude, min_longitude, self.max_latitude, max_longitude).ok()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::cell::multipolygon::Multipolygon;
+    use crate::CountryBoundaries;
+    use super::*;
+
+    fn bbox(min_latitude: f64, min_longitude: f64, max_latitude: f64, max_longitude: f64) -> BoundingBox {
+        BoundingBox::new(min_latitude, min_longitude, max_latitude, max_longitude).unwrap()
+    }
+
+    #[test]
+    fn bounds_of_containing_cells() {
+        // ┌─┬─┬─┬─┐
+        // │ │ │ │ │
+        // ├─┼─┼─┼─┤
+        // │ │A│A│ │
+        // └─┴─┴─┴─┘
+        let boundaries = CountryBoundaries::from_cells(
+            vec![
+                (vec![], vec![]), (vec![], vec![]), (vec![], vec![]), (vec![], vec![]),
+                (vec![], vec![]), (vec!["A"], vec![]), (vec!["A"], vec![]), (vec![], vec![]),
+            ],
+            4,
+            &[("A", 1.0), ("B", 1.0)]
+        );
+        assert_eq!(Some(bbox(-90.0, -90.0, 0.0, 90.0)), boundaries.bbox_of("A"));
+        assert_eq!(None, boundaries.bbox_of("B"));
+        assert_eq!(None, boundaries.bbox_of("C"));
+    }
+
+    #[test]
+    fn bounds_of_intersecting_areas() {
+        let triangle = Multipolygon {
+            outer: vec![vec![
+                Point { x: 0x4000, y: 0x4000 },
+                Point { x: 0xc000, y: 0x4000 },
+                Point { x: 0x4000, y: 0xffff }
+            ]],
+            inner: vec![]
+        };
+        let boundaries = CountryBoundaries::from_cells(
+            vec![(vec![], vec![]), (vec![], vec![("A", triangle)])],
+            2,
+            &[("A", 1.0)]
+        );
+        let bounds = boundaries.bbox_of("A").unwrap();
+        assert!((bounds.min_longitude() - 45.0).abs() < 0.01);
+        assert!((bounds.max_longitude() - 135.0).abs() < 0.01);
+        assert!((bounds.min_latitude() - -45.0).abs() < 0.01);
+        assert_eq!(90.0, bounds.max_latitude());
+    }
+
+    #[test]
+    fn bounds_wrap_around_180th_meridian() {
+        // ┌─┬─┬─┬─┐
+        // │A│ │ │A│
+        // └─┴─┴─┴─┘
+        let boundaries = CountryBoundaries::from_cells(
+            vec![(vec!["A"], vec![]), (vec![], vec![]), (vec![], vec![]), (vec!["A"], vec![])],
+            4,
+            &[("A", 1.0)]
+        );
+        assert_eq!(Some(bbox(-90.0, 90.0, 90.0, -90.0)), boundaries.bbox_of("A"));
+    }
+
+    #[test]
+    fn bounds_of_region_covering_all_longitudes() {
+        let boundaries = CountryBoundaries::from_cells(
+            vec![(vec!["A"], vec![]), (vec!["A"], vec![])],
+            2,
+            &[("A", 1.0)]
+        );
+        assert_eq!(Some(bbox(-90.0, -180.0, 90.0, 180.0)), boundaries.bbox_of("A"));
+    }
+}
@@ -0,0 +1,148 @@
+use std::collections::HashMap;
+
+/// Compact handle for a region of a [`CountryBoundaries`](crate::CountryBoundaries), to avoid
+/// comparing ids as strings in hot loops.
+///
+/// Get it with [`CountryBoundaries::id_index`](crate::CountryBoundaries::id_index). It is only
+/// valid for the `CountryBoundaries` it was obtained from.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct RegionIndex(pub(crate) u32);
+
+impl RegionIndex {
+    pub(crate) fn index(self) -> usize {
+        self.0 as usize
+    }
+}
+
+/// Table of the ids of all regions and their sizes. The cells refer to a region by its index in
+/// this table.
+///
+/// The ids are in the order in which they were added, so that the table is written in the same
+/// order as it was read.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Regions {
+    ids: Vec<String>,
+    /// the sizes of the regions, in the same order as `ids`. Not every region necessarily has a
+    /// size, e.g. if the data has not been validated
+    sizes: Vec<Option<f64>>,
+    indices: HashMap<String, RegionIndex>
+}
+
+impl Regions {
+
+    pub fn with_capacity(capacity: usize) -> Regions {
+        Regions {
+            ids: Vec::with_capacity(capacity),
+            sizes: Vec::with_capacity(capacity),
+            indices: HashMap::with_capacity(capacity)
+        }
+    }
+
+    /// Returns the index of the region with the given `id`, adding it first if it is not in the
+    /// table yet
+    pub fn insert(&mut self, id: &str) -> RegionIndex {
+        if let Some(&index) = self.indices.get(id) {
+            return index;
+        }
+        // the table can never be larger than u32::MAX because the file format doesn't allow it
+        let index = RegionIndex(self.ids.len() as u32);
+        self.ids.push(String::from(id));
+        self.sizes.push(None);
+        self.indices.insert(String::from(id), index);
+        index
+    }
+
+    /// Returns the index of the region with the given `id`, adding it first if it is not in the
+    /// table yet, and sets its `size`
+    pub fn insert_with_size(&mut self, id: &str, size: f64) -> RegionIndex {
+        let index = self.insert(id);
+        self.sizes[index.index()] = Some(size);
+        index
+    }
+
+    pub fn len(&self) -> usize {
+        self.ids.len()
+    }
+
+    pub fn index(&self, id: &str) -> Option<RegionIndex> {
+        self.indices.get(id).copied()
+    }
+
+    pub fn id(&self, index: RegionIndex) -> &str {
+        &self.ids[index.index()]
+    }
+
+    pub fn size(&self, index: RegionIndex) -> Option<f64> {
+        self.sizes[index.index()]
+    }
+
+    /// Returns the rank of each region when ordered by size ascending, in order of the table.
+    /// Regions of the same size have the same rank and regions without a size count as having
+    /// size 0, so comparing the ranks of two regions is the same as comparing their sizes.
+    pub fn size_ranks(&self) -> Vec<u32> {
+        let size = |index: usize| self.sizes[index].unwrap_or(0.0);
+        let mut by_size: Vec<usize> = (0..self.ids.len()).collect();
+        by_size.sort_by(|&a, &b| size(a).total_cmp(&size(b)));
+
+        let mut ranks = vec![0; self.ids.len()];
+        let mut rank = 0;
+        for (i, &index) in by_size.iter().enumerate() {
+            if i > 0 && size(by_size[i - 1]).total_cmp(&size(index)).is_ne() {
+                rank += 1;
+            }
+            ranks[index] = rank;
+        }
+        ranks
+    }
+
+    /// Iterate over all ids and their sizes, in order of the table
+    pub fn iter(&self) -> impl Iterator<Item = (&str, Option<f64>)> {
+        self.ids.iter().map(String::as_str).zip(self.sizes.iter().copied())
+    }
+}
+
+impl PartialEq for Regions {
+    fn eq(&self, other: &Self) -> bool {
+        // `indices` is derived from `ids`
+        self.ids == other.ids && self.sizes == other.sizes
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn insert() {
+        let mut regions = Regions::default();
+        assert_eq!(RegionIndex(0), regions.insert("A"));
+        assert_eq!(RegionIndex(1), regions.insert_with_size("B", 2.0));
+        assert_eq!(RegionIndex(0), regions.insert_with_size("A", 1.0));
+        assert_eq!(RegionIndex(1), regions.insert("B"));
+
+        assert_eq!(Some(RegionIndex(1)), regions.index("B"));
+        assert_eq!(None, regions.index("C"));
+        assert_eq!("B", regions.id(RegionIndex(1)));
+        assert_eq!(Some(1.0), regions.size(RegionIndex(0)));
+        assert_eq!(vec![("A", Some(1.0)), ("B", Some(2.0))], regions.iter().collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn size_ranks() {
+        let mut regions = Regions::default();
+        regions.insert_with_size("A", 3.0);
+        regions.insert_with_size("B", 1.0);
+        regions.insert("C");
+        regions.insert_with_size("D", 3.0);
+        regions.insert_with_size("E", 2.0);
+        assert_eq!(vec![3, 1, 0, 3, 2], regions.size_ranks());
+        assert!(Regions::default().size_ranks().is_empty());
+    }
+
+    #[test]
+    fn size_is_none_if_not_set() {
+        let mut regions = Regions::default();
+        let index = regions.insert("A");
+        assert_eq!(None, regions.size(index));
+    }
+}
@@ -0,0 +1,177 @@
+use crate::raster::{normalize, Raster};
+use crate::{CountryBoundaries, LatLon};
+
+/// A part of a route that lies in the same regions, see
+/// [`CountryBoundaries::route_segments`]
+#[derive(Debug, Clone)]
+pub struct RouteSegment<'a> {
+    /// The ids of the regions this part of the route lies in, ordered by size like in
+    /// [`CountryBoundaries::ids`]. Empty if it lies in no region.
+    pub ids: Vec<&'a str>,
+    /// Where this part begins, i.e. the start of the route or where it crosses into these regions
+    pub start: LatLon,
+    /// Where this part ends, i.e. the end of the route or where it crosses out of these regions
+    pub end: LatLon,
+    /// Length of this part in meters
+    pub length: f64
+}
+
+/// A position as x = longitude, y = latitude. Longitudes are continuous along an edge of the
+/// route, i.e. they may go beyond ±180
+type Position = (f64, f64);
+
+pub(crate) fn route_segments<'a>(boundaries: &'a CountryBoundaries, route: &[LatLon]) -> Vec<RouteSegment<'a>> {
+    let raster = Raster::new(boundaries.raster_width, boundaries.raster.len());
+    let mut segments: Vec<RouteSegment> = Vec::new();
+    for pair in route.windows(2) {
+        let a = (normalize(pair[0].longitude(), -180.0, 360.0), pair[0].latitude());
+        // always take the shorter way, which may be across the 180th meridian
+        let b = (a.0 + normalize(pair[1].longitude() - a.0, -180.0, 360.0), pair[1].latitude());
+        let position_at = |t: f64| {
+            if t == 0.0 { pair[0] } else if t == 1.0 { pair[1] } else { to_latlon(interpolate(a, b, t)) }
+        };
+
+        let breaks = crossings(boundaries, &raster, a, b);
+        for range in breaks.windows(2) {
+            let (t0, t1) = (range[0], range[1]);
+            if t1 <= t0 { continue; }
+            // between two crossings, the route is in the same regions all the way
+            let ids = boundaries.ids(to_latlon(interpolate(a, b, (t0 + t1) / 2.0)));
+            let start = position_at(t0);
+            let end = position_at(t1);
+            let length = start.distance_to(&end);
+            match segments.last_mut() {
+                Some(last) if last.ids == ids => {
+                    last.end = end;
+                    last.length += length;
+                }
+                _ => segments.push(RouteSegment { ids, start, end, length })
+            }
+        }
+    }
+    segments
+}
+
+/// Returns the sorted positions on the edge from `a` to `b` at which it crosses from one cell into
+/// another or crosses an edge of any of the areas in those cells, as fractions of its length.
+/// Includes 0 and 1.
+fn crossings(boundaries: &CountryBoundaries, raster: &Raster, a: Position, b: Position) -> Vec<f64> {
+    let mut breaks = vec![0.0, 1.0];
+    if raster.width == 0 || raster.height == 0 {
+        return breaks;
+    }
+    add_grid_crossings(&mut breaks, a.0, b.0, -180.0, 360.0 / raster.width as f64);
+    add_grid_crossings(&mut breaks, a.1, b.1, -90.0, 180.0 / raster.height as f64);
+    breaks.sort_by(f64::total_cmp);
+
+    let mut border_crossings = Vec::new();
+    for range in breaks.windows(2) {
+        let (t0, t1) = (range[0], range[1]);
+        if t1 <= t0 { continue; }
+        let middle = interpolate(a, b, (t0 + t1) / 2.0);
+        let (index, _) = raster.cell_and_local_point(to_latlon(middle));
+        let cell = &boundaries.raster[index];
+        if cell.intersecting_areas.is_empty() { continue; }
+
+        // the cell might need to be shifted by 360° to where the longitudes of the edge are
+        let shift = middle.0 - normalize(middle.0, -180.0, 360.0);
+        let [min_longitude, min_latitude, max_longitude, max_latitude] = raster.cell_bounds(index);
+        let to_local = |(x, y): Position| (
+            (x - shift - min_longitude) * 0xffff as f64 / (max_longitude - min_longitude),
+            (y - min_latitude) * 0xffff as f64 / (max_latitude - min_latitude)
+        );
+        let p0 = to_local(interpolate(a, b, t0));
+        let p1 = to_local(interpolate(a, b, t1));
+        for (_, multipolygon) in cell.intersecting_areas.iter() {
+            for ring in multipolygon.outer.iter().chain(multipolygon.inner.iter()) {
+                let points = ring.iter().map(|p| (p.x as f64, p.y as f64));
+                for (q0, q1) in points.clone().zip(points.cycle().skip(1)) {
+                    if let Some(s) = intersection(p0, p1, q0, q1) {
+                        border_crossings.push(t0 + s * (t1 - t0));
+                    }
+                }
+            }
+        }
+    }
+    breaks.extend(border_crossings);
+    breaks.sort_by(f64::total_cmp);
+    breaks.dedup();
+    breaks
+}
+
+/// Adds the positions on the line from `a` to `b` at which it crosses the grid lines at `start` +
+/// n × `step`, as fractions of its length
+fn add_grid_crossings(breaks: &mut Vec<f64>, a: f64, b: f64, start: f64, step: f64) {
+    if a == b { return; }
+    let min = ((a.min(b) - start) / step).floor() as i64 + 1;
+    let max = ((a.max(b) - start) / step).ceil() as i64 - 1;
+    for n in min..=max {
+        breaks.push((start + n as f64 * step - a) / (b - a));
+    }
+}
+
+/// Returns at which fraction of the segment from `p0` to `p1` it intersects with the segment from
+/// `q0` to `q1`, or `None` if they do not intersect or are parallel
+fn intersection(p0: Position, p1: Position, q0: Position, q1: Position) -> Option<f64> {
+    let r = (p1.0 - p0.0, p1.1 - p0.1);
+    let s = (q1.0 - q0.0, q1.1 - q0.1);
+    let denominator = cross(r, s);
+    if denominator == 0.0 { return None; }
+    let d = (q0.0 - p0.0, q0.1 - p0.1);
+    let t = cross(d, s) / denominator;
+    let u = cross(d, r) / denominator;
+    ((0.0..=1.0).contains(&t) && (0.0..=1.0).contains(&u)).then_some(t)
+}
+
+fn cross(a: Position, b: Position) -> f64 {
+    a.0 * b.1 - a.1 * b.0
+}
+
+fn interpolate(a: Position, b: Position, t: f64) -> Position {
+    (a.0 + (b.0 - a.0) * t, a.1 + (b.1 - a.1) * t)
+}
+
+fn to_latlon((longitude, latitude): Position) -> LatLon {
+    LatLon::new(latitude.clamp(-90.0, 90.0), normalize(longitude, -180.0, 360.0))
+        .expect("interpolated between valid positions")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn grid_crossings() {
+        let mut breaks = Vec::new();
+        add_grid_crossings(&mut breaks, -100.0, 100.0, -180.0, 90.0);
+        assert_eq!(vec![0.05, 0.5, 0.95], breaks);
+
+        // exactly on a grid line is not a crossing
+        let mut breaks = Vec::new();
+        add_grid_crossings(&mut breaks, 0.0, 90.0, -180.0, 90.0);
+        assert!(breaks.is_empty());
+
+        // backwards and beyond 180
+        let mut breaks = Vec::new();
+        add_grid_crossings(&mut breaks, 190.0, 170.0, -180.0, 90.0);
+        assert_eq!(vec![0.5], breaks);
+    }
+
+    #[test]
+    fn segments_intersection() {
+        assert_eq!(Some(0.5), intersection((0.0, 0.0), (10.0, 10.0), (0.0, 10.0), (10.0, 0.0)));
+        assert_eq!(Some(0.25), intersection((0.0, 0.0), (8.0, 0.0), (2.0, -1.0), (2.0, 5.0)));
+        // touching
+        assert_eq!(Some(1.0), intersection((0.0, 0.0), (2.0, 0.0), (2.0, 0.0), (2.0, 5.0)));
+        // not intersecting or parallel
+        assert_eq!(None, intersection((0.0, 0.0), (1.0, 0.0), (2.0, -1.0), (2.0, 5.0)));
+        assert_eq!(None, intersection((0.0, 0.0), (8.0, 0.0), (0.0, 1.0), (8.0, 1.0)));
+    }
+
+    #[test]
+    fn interpolated_latlon_is_normalized() {
+        let position = to_latlon(interpolate((170.0, 0.0), (200.0, 10.0), 0.5));
+        assert_eq!(-175.0, position.longitude());
+        assert_eq!(5.0, position.latitude());
+    }
+}
@@ -0,0 +1,252 @@
+use std::io;
+use std::io::{ErrorKind, Write};
+use crate::cell::Cell;
+use crate::cell::multipolygon::Multipolygon;
+use crate::cell::point::Point;
+use crate::regions::Regions;
+use crate::{CountryBoundaries, Error, RegionIndex};
+
+/// Serialize a `CountryBoundaries` into an IO stream, in the same format that is read by
+/// [`from_reader`](crate::deserializer::from_reader).
+///
+/// The geometry sizes are written in the same order as they were read, so that writing
+/// boundaries that have been read results in the same bytes again.
+///
+/// When writing to a sink against which short writes are not efficient, such as a [`File`],
+/// you will want to apply your own buffering because this function will not buffer the output.
+/// See [`io::BufWriter`].
+///
+/// [`File`]: std::fs::File
+pub fn to_writer(boundaries: &CountryBoundaries, mut writer: impl Write) -> io::Result<()> {
+    write_u16(&mut writer, 2)?;
+
+    let regions = &boundaries.regions;
+    let geometry_sizes: Vec<(&str, f64)> = regions.iter()
+        .filter_map(|(id, size)| Some((id, size?)))
+        .collect();
+    write_usize32(&mut writer, geometry_sizes.len())?;
+    for (id, size) in geometry_sizes {
+        write_string(&mut writer, id)?;
+        write_f64(&mut writer, size)?;
+    }
+    write_usize32(&mut writer, boundaries.raster_width)?;
+    write_usize32(&mut writer, boundaries.raster.len())?;
+    for cell in boundaries.raster.iter() {
+        write_cell(&mut writer, cell, regions)?;
+    }
+    Ok(())
+}
+
+fn write_cell(writer: &mut impl Write, cell: &Cell, regions: &Regions) -> io::Result<()> {
+    write_usize8(writer, cell.containing_ids.len())?;
+    for &id in cell.containing_ids.iter() {
+        write_string(writer, regions.id(id))?;
+    }
+    write_usize8(writer, cell.intersecting_areas.len())?;
+    for areas in cell.intersecting_areas.iter() {
+        write_areas(writer, areas, regions)?;
+    }
+    Ok(())
+}
+
+fn write_areas(
+    writer: &mut impl Write,
+    areas: &(RegionIndex, Multipolygon),
+    regions: &Regions
+) -> io::Result<()> {
+    write_string(writer, regions.id(areas.0))?;
+    write_polygons(writer, &areas.1.outer)?;
+    write_polygons(writer, &areas.1.inner)
+}
+
+fn write_polygons(writer: &mut impl Write, polygons: &[Vec<Point>]) -> io::Result<()> {
+    write_usize8(writer, polygons.len())?;
+    for ring in polygons.iter() {
+        write_ring(writer, ring)?;
+    }
+    Ok(())
+}
+
+fn write_ring(writer: &mut impl Write, ring: &[Point]) -> io::Result<()> {
+    write_usize32(writer, ring.len())?;
+    for point in ring.iter() {
+        write_point(writer, point)?;
+    }
+    Ok(())
+}
+
+fn write_point(writer: &mut impl Write, point: &Point) -> io::Result<()> {
+    write_u16(writer, point.x)?;
+    write_u16(writer, point.y)
+}
+
+fn write_u8(writer: &mut impl Write, value: u8) -> io::Result<()> {
+    writer.write_all(&value.to_be_bytes())
+}
+
+fn write_usize8(writer: &mut impl Write, value: usize) -> io::Result<()> {
+    write_u8(writer, u8::try_from(value).map_err(|_| too_large(value, u8::MAX as u64))?)
+}
+
+fn write_u16(writer: &mut impl Write, value: u16) -> io::Result<()> {
+    writer.write_all(&value.to_be_bytes())
+}
+
+fn write_u32(writer: &mut impl Write, value: u32) -> io::Result<()> {
+    writer.write_all(&value.to_be_bytes())
+}
+
+fn write_usize32(writer: &mut impl Write, value: usize) -> io::Result<()> {
+    write_u32(writer, u32::try_from(value).map_err(|_| too_large(value, u32::MAX as u64))?)
+}
+
+fn write_f64(writer: &mut impl Write, value: f64) -> io::Result<()> {
+    writer.write_all(&value.to_be_bytes())
+}
+
+fn write_string(writer: &mut impl Write, value: &str) -> io::Result<()> {
+    let length = u16::try_from(value.len())
+        .map_err(|_| too_large(value.len(), u16::MAX as u64))?;
+    write_u16(writer, length)?;
+    writer.write_all(value.as_bytes())
+}
+
+fn too_large(value: usize, max: u64) -> io::Error {
+    io::Error::new(ErrorKind::InvalidInput,
+        Error::new(format!(
+            "Cannot write '{value}' in the boundaries file format, it must not be greater than '{max}'"
+        ))
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn written(write: impl FnOnce(&mut Vec<u8>) -> io::Result<()>) -> Vec<u8> {
+        let mut buf = Vec::new();
+        write(&mut buf).unwrap();
+        buf
+    }
+
+    #[test]
+    fn test_write_string() {
+        assert_eq!(vec![0x00, 0x00], written(|w| write_string(w, "")));
+        assert_eq!(vec![0x00, 0x01, 0x41], written(|w| write_string(w, "A")));
+        assert_eq!(vec![0x00, 0x02, 0x41, 0x42], written(|w| write_string(w, "AB")));
+
+        assert!(write_string(&mut Vec::new(), &"A".repeat(0x10000)).is_err());
+    }
+
+    #[test]
+    fn write_float() {
+        assert_eq!(
+            vec![0x40, 0x29, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00],
+            written(|w| write_f64(w, 12.5))
+        );
+    }
+
+    #[test]
+    fn test_write_usize8() {
+        assert_eq!(vec![0x11], written(|w| write_usize8(w, 17)));
+        assert_eq!(vec![0xff], written(|w| write_usize8(w, 0xff)));
+        assert!(write_usize8(&mut Vec::new(), 0x100).is_err());
+    }
+
+    #[test]
+    fn test_write_u16() {
+        assert_eq!(vec![0x00, 0x11], written(|w| write_u16(w, 17)));
+        assert_eq!(vec![0xff, 0xff], written(|w| write_u16(w, u16::MAX)));
+    }
+
+    #[test]
+    fn test_write_usize32() {
+        assert_eq!(vec![0x00, 0x00, 0x00, 0x11], written(|w| write_usize32(w, 17)));
+        assert_eq!(vec![0x00, 0x00, 0xff, 0xff], written(|w| write_usize32(w, 0xffff)));
+    }
+
+    #[test]
+    #[cfg(target_pointer_width = "64")]
+    fn write_usize32_results_in_error_if_number_too_big() {
+        assert!(write_usize32(&mut Vec::new(), 0x1_0000_0000).is_err());
+    }
+
+    #[test]
+    fn test_write_ring() {
+        assert_eq!(vec![0x00, 0x00, 0x00, 0x00], written(|w| write_ring(w, &[])));
+        assert_eq!(
+            vec![
+                0x00, 0x00, 0x00, 0x02, // length
+                0x00, 0x01,             // p1.x
+                0x00, 0x02,             // p1.y
+                0x00, 0x03,             // p2.x
+                0x00, 0x04              // p2.y
+            ],
+            written(|w| write_ring(w, &[Point {x: 1, y: 2}, Point {x: 3, y: 4}]))
+        );
+    }
+
+    #[test]
+    fn test_write_cell() {
+        assert_eq!(
+            vec![0x00, 0x00],
+            written(|w| write_cell(w, &Cell { containing_ids: vec![], intersecting_areas: vec![] }, &Regions::default()))
+        );
+        assert_eq!(
+            vec![
+                0x01,             // containing ids length
+                0x00, 0x01, 0x41, // "A"
+                0x01,             // intersecting areas length
+                0x00, 0x01, 0x42, // "B"
+                0x00, 0x00        // empty multipolygon
+            ],
+            written(|w| {
+                let mut regions = Regions::default();
+                let cell = Cell {
+                    containing_ids: vec![regions.insert("A")],
+                    intersecting_areas: vec![
+                        (regions.insert("B"), Multipolygon { inner: vec![], outer: vec![] })
+                    ]
+                };
+                write_cell(w, &cell, &regions)
+            })
+        );
+    }
+
+    #[test]
+    fn test_write_basic() {
+        let boundaries = CountryBoundaries::from_cells(
+            vec![(vec!["A"], vec![])],
+            1,
+            &[("A", 12.5)]
+        );
+        assert_eq!(
+            vec![
+                0x00, 0x02,                                     // version number
+                0x00, 0x00, 0x00, 0x01,                         // geometry sizes map length
+                0x00, 0x01, 0x41,                               // "A"
+                0x40, 0x29, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, // 12.5
+                0x00, 0x00, 0x00, 0x01,                         // raster width
+                0x00, 0x00, 0x00, 0x01,                         // raster size
+                0x01,                                           // cell containing ids length
+                0x00, 0x01, 0x41,                               // "A"
+                0x00,                                           // intersecting areas length
+            ],
+            written(|w| to_writer(&boundaries, w))
+        );
+    }
+
+    #[test]
+    fn write_geometry_sizes_in_order_of_id_table() {
+        let boundaries = CountryBoundaries::from_cells(
+            vec![(vec![], vec![])],
+            1,
+            &[("B", 1.0), ("C", 1.0), ("A", 1.0)]
+        );
+        let buf = written(|w| to_writer(&boundaries, w));
+        assert_eq!(boundaries, CountryBoundaries::from_reader(buf.as_slice()).unwrap());
+        assert_eq!([0x42], buf[8..9]);
+        assert_eq!([0x43], buf[19..20]);
+        assert_eq!([0x41], buf[30..31]);
+    }
+}
@@ -0,0 +1,177 @@
+use std::collections::HashSet;
+use crate::cell::multipolygon::is_point_in_ring;
+use crate::cell::point::Point;
+use crate::raster::Raster;
+use crate::{BoundingBox, LatLon};
+
+/// Country boundaries that are compiled into the binary as static arrays, so that no parsing at
+/// all is necessary at runtime.
+///
+/// The Rust code to create it is generated at build time from a boundaries file with
+/// [`codegen::write_rust`](crate::codegen::write_rust), see the [`codegen`](crate::codegen)
+/// module. Apart from that, it has the same queries as
+/// [`CountryBoundaries`](crate::CountryBoundaries).
+#[derive(Debug, Copy, Clone)]
+pub struct StaticCountryBoundaries {
+    raster_width: usize,
+    /// the ids of all regions. Everything else refers to a region by its index in this table
+    ids: &'static [&'static str],
+    /// the sizes of the regions, in the same order as `ids`
+    sizes: &'static [f64],
+    /// for each cell the start of its containing ids and of its intersecting areas, plus one
+    /// more entry that marks the end of the last cell
+    cells: &'static [[u32; 2]],
+    /// ids of areas that completely cover a cell
+    containing_ids: &'static [u32],
+    /// id, start of the outer rings, start of the inner rings and end of the rings of areas that
+    /// only partly cover a cell
+    intersecting_areas: &'static [[u32; 4]],
+    /// start of each ring in `points`, plus one more entry that marks the end of the last ring
+    rings: &'static [u32],
+    /// x and y of all points of all rings
+    points: &'static [[u16; 2]]
+}
+
+impl StaticCountryBoundaries {
+
+    /// Create a StaticCountryBoundaries from its parts. This is what the code generated by
+    /// [`codegen::write_rust`](crate::codegen::write_rust) calls, the parts are not meant to be
+    /// put together by hand.
+    #[allow(clippy::too_many_arguments)]
+    pub const fn from_parts(
+        raster_width: usize,
+        ids: &'static [&'static str],
+        sizes: &'static [f64],
+        cells: &'static [[u32; 2]],
+        containing_ids: &'static [u32],
+        intersecting_areas: &'static [[u32; 4]],
+        rings: &'static [u32],
+        points: &'static [[u16; 2]]
+    ) -> StaticCountryBoundaries {
+        StaticCountryBoundaries {
+            raster_width, ids, sizes, cells, containing_ids, intersecting_areas, rings, points
+        }
+    }
+
+    /// Returns whether the given `position` is in the region with the given `id`
+    ///
+    /// See [`CountryBoundaries::is_in`](crate::CountryBoundaries::is_in)
+    pub fn is_in(&self, position: LatLon, id: &str) -> bool {
+        let (cell, point) = self.cell_and_local_point(position);
+        self.cell_containing_ids(cell).any(|containing_id| self.id(containing_id) == id) ||
+        self.cell_intersecting_areas(cell)
+            .any(|area| self.id(area[0]) == id && self.covers(area, &point))
+    }
+
+    /// Returns whether the given `position` is in any of the regions with the given `ids`.
+    ///
+    /// See [`CountryBoundaries::is_in_any`](crate::CountryBoundaries::is_in_any)
+    pub fn is_in_any(&self, position: LatLon, ids: &HashSet<&str>) -> bool {
+        let (cell, point) = self.cell_and_local_point(position);
+        self.cell_containing_ids(cell).any(|containing_id| ids.contains(self.id(containing_id))) ||
+        self.cell_intersecting_areas(cell)
+            .any(|area| ids.contains(self.id(area[0])) && self.covers(area, &point))
+    }
+
+    /// Returns the ids of the regions the given `position` is contained in, ordered by size of
+    /// the region ascending
+    ///
+    /// See [`CountryBoundaries::ids`](crate::CountryBoundaries::ids)
+    pub fn ids(&self, position: LatLon) -> Vec<&'static str> {
+        let (cell, point) = self.cell_and_local_point(position);
+        let mut result: Vec<u32> = self.cell_containing_ids(cell).collect();
+        result.extend(
+            self.cell_intersecting_areas(cell)
+                .filter(|area| self.covers(area, &point))
+                .map(|area| area[0])
+        );
+        result.sort_by(|&a, &b| self.size(a).total_cmp(&self.size(b)));
+        result.into_iter().map(|id| self.id(id)).collect()
+    }
+
+    /// Returns the ids of the regions that fully contain the given bounding box `bounds`.
+    ///
+    /// See [`CountryBoundaries::containing_ids`](crate::CountryBoundaries::containing_ids)
+    pub fn containing_ids(&self, bounds: BoundingBox) -> HashSet<&'static str> {
+        let mut ids: HashSet<u32> = HashSet::new();
+        let mut first_cell = true;
+        for cell in self.raster().cells(&bounds) {
+            if first_cell {
+                ids.extend(self.cell_containing_ids(cell));
+                first_cell = false;
+            } else {
+                ids.retain(|&id| self.cell_containing_ids(cell).any(|containing_id| containing_id == id));
+                if ids.is_empty() { break; }
+            }
+        }
+        ids.into_iter().map(|id| self.id(id)).collect()
+    }
+
+    /// Returns the ids of the regions that contain or at lest intersect with the given bounding box
+    /// `bounds`.
+    ///
+    /// See [`CountryBoundaries::intersecting_ids`](crate::CountryBoundaries::intersecting_ids)
+    pub fn intersecting_ids(&self, bounds: BoundingBox) -> HashSet<&'static str> {
+        let mut ids: HashSet<&'static str> = HashSet::new();
+        for cell in self.raster().cells(&bounds) {
+            ids.extend(self.cell_containing_ids(cell).map(|id| self.id(id)));
+            ids.extend(self.cell_intersecting_areas(cell).map(|area| self.id(area[0])));
+        }
+        ids
+    }
+
+    fn cell_and_local_point(&self, position: LatLon) -> (usize, Point) {
+        self.raster().cell_and_local_point(position)
+    }
+
+    fn raster(&self) -> Raster {
+        Raster::new(self.raster_width, self.cells.len().saturating_sub(1))
+    }
+
+    fn id(&self, index: u32) -> &'static str {
+        self.ids.get(index as usize).copied().unwrap_or_default()
+    }
+
+    fn size(&self, index: u32) -> f64 {
+        self.sizes.get(index as usize).copied().unwrap_or_default()
+    }
+
+    fn cell_containing_ids(&self, cell: usize) -> impl Iterator<Item = u32> {
+        slice(self.containing_ids, self.cell_range(cell, 0)).iter().copied()
+    }
+
+    fn cell_intersecting_areas(&self, cell: usize) -> std::slice::Iter<'static, [u32; 4]> {
+        slice(self.intersecting_areas, self.cell_range(cell, 1)).iter()
+    }
+
+    fn cell_range(&self, cell: usize, part: usize) -> (u32, u32) {
+        let start = self.cells.get(cell).map_or(0, |c| c[part]);
+        let end = self.cells.get(cell + 1).map_or(0, |c| c[part]);
+        (start, end)
+    }
+
+    fn covers(&self, area: &[u32; 4], point: &Point) -> bool {
+        let mut insides = 0;
+        for ring in area[1]..area[2] {
+            if is_point_in_ring(point, self.ring(ring)) {
+                insides += 1;
+            }
+        }
+        for ring in area[2]..area[3] {
+            if is_point_in_ring(point, self.ring(ring)) {
+                insides -= 1;
+            }
+        }
+        insides > 0
+    }
+
+    fn ring(&self, ring: u32) -> impl DoubleEndedIterator<Item = Point> + Clone {
+        let start = self.rings.get(ring as usize).copied().unwrap_or(0);
+        let end = self.rings.get(ring as usize + 1).copied().unwrap_or(0);
+        slice(self.points, (start, end)).iter().map(|&[x, y]| Point { x, y })
+    }
+}
+
+fn slice<T>(values: &[T], (start, end): (u32, u32)) -> &[T] {
+    values.get(start as usize..end as usize).unwrap_or_default()
+}
@@ -0,0 +1,247 @@
+use crate::route::route_segments;
+use crate::{CountryBoundaries, LatLon, RegionIndex};
+
+/// Follows a position that is updated one fix at a time, e.g. a GPS trace, and tells when it
+/// crosses into or out of regions.
+///
+/// # Example
+/// ```
+/// # use country_boundaries::{BorderEvent, CountryBoundaries, LatLon, Tracker};
+/// #
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// # let buf = std::fs::read("./data/boundaries360x180.ser")?;
+/// # let boundaries = CountryBoundaries::from_reader(buf.as_slice())?;
+/// let mut tracker = Tracker::new(&boundaries).interpolate_crossings(true);
+/// // in Freilassing, Germany
+/// tracker.update(LatLon::new(47.838, 12.977)?);
+/// // in Salzburg, Austria
+/// let events = tracker.update(LatLon::new(47.800, 13.045)?);
+/// assert!(matches!(events[0], BorderEvent::Exited { id: "DE", position: Some(_) }));
+/// assert!(matches!(events[1], BorderEvent::Entered { id: "AT", position: Some(_) }));
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct Tracker<'a> {
+    boundaries: &'a CountryBoundaries,
+    interpolate_crossings: bool,
+    last: Option<Fix>
+}
+
+/// That a tracked position crossed into or out of a region, see [`Tracker::update`]
+#[derive(Debug, Clone)]
+pub enum BorderEvent<'a> {
+    /// The position left the region with the given `id`. `position` is where it crossed the
+    /// border, if crossings are interpolated.
+    Exited { id: &'a str, position: Option<LatLon> },
+    /// The position entered the region with the given `id`. `position` is where it crossed the
+    /// border, if crossings are interpolated.
+    Entered { id: &'a str, position: Option<LatLon> }
+}
+
+/// The last position given to the tracker
+#[derive(Debug, Clone)]
+struct Fix {
+    position: LatLon,
+    /// index of the cell the position is in
+    cell: usize,
+    /// the regions the position is in, ordered by size
+    ids: Vec<RegionIndex>
+}
+
+impl<'a> Tracker<'a> {
+
+    /// Creates a tracker that has not seen any position yet
+    pub fn new(boundaries: &'a CountryBoundaries) -> Tracker<'a> {
+        Tracker { boundaries, interpolate_crossings: false, last: None }
+    }
+
+    /// Whether to find out where exactly the straight line between two consecutive positions
+    /// crosses the border of a region that was entered or exited. Off by default, as it is
+    /// slower.
+    pub fn interpolate_crossings(mut self, interpolate: bool) -> Tracker<'a> {
+        self.interpolate_crossings = interpolate;
+        self
+    }
+
+    /// Returns the ids of the regions the last position is in, ordered by size like
+    /// [`CountryBoundaries::ids`]
+    pub fn ids(&self) -> Vec<&'a str> {
+        self.last.iter()
+            .flat_map(|last| last.ids.iter())
+            .map(|&index| self.boundaries.regions.id(index))
+            .collect()
+    }
+
+    /// Forgets the last position, e.g. when a new trip starts
+    pub fn reset(&mut self) {
+        self.last = None;
+    }
+
+    /// Moves the tracked position to `position` and returns which regions it exited and entered
+    /// since the last position. The exits come first, each ordered by size, so a subdivision is
+    /// exited before its country and a country is entered before its subdivision.
+    ///
+    /// The very first position enters all the regions it is in. Only the regions of the last and
+    /// the given position are compared, so regions that were entered and exited again in between
+    /// are not reported.
+    pub fn update(&mut self, position: LatLon) -> Vec<BorderEvent<'a>> {
+        let raster = self.boundaries.raster();
+        let (index, point) = raster.cell_and_local_point(position);
+        let cell = &self.boundaries.raster[index];
+
+        let last = self.last.take();
+        if let Some(last) = last.as_ref() {
+            // no need to test any polygons if the position is still in the same cell and that
+            // cell is completely covered by the regions it is in
+            if last.cell == index && cell.intersecting_areas.is_empty() {
+                self.last = Some(Fix { position, cell: index, ids: last.ids.clone() });
+                return Vec::new();
+            }
+        }
+
+        let mut ids = cell.get_ids(point);
+        self.boundaries.sort_by_size(&mut ids);
+
+        let previous_ids = last.as_ref().map_or(&[] as &[RegionIndex], |last| &last.ids);
+        let exited: Vec<&str> = previous_ids.iter()
+            .filter(|id| !ids.contains(id))
+            .map(|&id| self.boundaries.regions.id(id))
+            .collect();
+        let mut entered: Vec<&str> = ids.iter()
+            .filter(|id| !previous_ids.contains(id))
+            .map(|&id| self.boundaries.regions.id(id))
+            .collect();
+        entered.reverse();
+
+        let segments = match &last {
+            Some(last) if self.interpolate_crossings && !(exited.is_empty() && entered.is_empty()) =>
+                route_segments(self.boundaries, &[last.position, position]),
+            _ => Vec::new()
+        };
+
+        let mut events = Vec::with_capacity(exited.len() + entered.len());
+        for id in exited {
+            // where the route first leaves the region
+            let position = segments.iter().skip(1)
+                .find(|segment| !segment.ids.contains(&id))
+                .map(|segment| segment.start);
+            events.push(BorderEvent::Exited { id, position });
+        }
+        for id in entered {
+            // where the route last enters the region
+            let position = segments.iter()
+                .rposition(|segment| !segment.ids.contains(&id))
+                .and_then(|i| segments.get(i + 1))
+                .map(|segment| segment.start);
+            events.push(BorderEvent::Entered { id, position });
+        }
+
+        self.last = Some(Fix { position, cell: index, ids });
+        events
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::cell::multipolygon::Multipolygon;
+    use crate::cell::point::Point;
+    use super::*;
+
+    fn latlon(latitude: f64, longitude: f64) -> LatLon {
+        LatLon::new(latitude, longitude).unwrap()
+    }
+
+    fn boundaries() -> CountryBoundaries {
+        // the world, with A covering the right half of the cell -180..0 and B and its
+        // subdivision B-1 covering the cell 0..180:
+        // ┌──┬──┬──┐
+        // │  │ A│BB│
+        // └──┴──┴──┘
+        CountryBoundaries::from_cells(
+            vec![
+                (vec![], vec![("A", Multipolygon {
+                    outer: vec![vec![
+                        Point { x: 0x8000, y: 0 },
+                        Point { x: 0xffff, y: 0 },
+                        Point { x: 0xffff, y: 0xffff },
+                        Point { x: 0x8000, y: 0xffff },
+                    ]],
+                    inner: vec![]
+                })]),
+                (vec!["B", "B-1"], vec![])
+            ],
+            2,
+            &[("A", 10.0), ("B", 20.0), ("B-1", 5.0)]
+        )
+    }
+
+    fn describe(events: &[BorderEvent]) -> Vec<String> {
+        events.iter().map(|event| match event {
+            BorderEvent::Exited { id, .. } => format!("-{id}"),
+            BorderEvent::Entered { id, .. } => format!("+{id}")
+        }).collect()
+    }
+
+    #[test]
+    fn first_position_enters_its_regions() {
+        let boundaries = boundaries();
+        let mut tracker = Tracker::new(&boundaries);
+        assert!(tracker.ids().is_empty());
+        assert_eq!(vec!["+B", "+B-1"], describe(&tracker.update(latlon(0.0, 10.0))));
+        assert_eq!(vec!["B-1", "B"], tracker.ids());
+    }
+
+    #[test]
+    fn enter_and_exit() {
+        let boundaries = boundaries();
+        let mut tracker = Tracker::new(&boundaries);
+        tracker.update(latlon(0.0, -100.0));
+        assert!(tracker.update(latlon(1.0, -100.0)).is_empty());
+        assert_eq!(vec!["+A"], describe(&tracker.update(latlon(0.0, -80.0))));
+        assert!(tracker.update(latlon(1.0, -10.0)).is_empty());
+        assert_eq!(vec!["-A", "+B", "+B-1"], describe(&tracker.update(latlon(0.0, 10.0))));
+        assert!(tracker.update(latlon(5.0, 20.0)).is_empty());
+        assert_eq!(vec!["-B-1", "-B"], describe(&tracker.update(latlon(0.0, -170.0))));
+        assert!(tracker.ids().is_empty());
+    }
+
+    #[test]
+    fn reset() {
+        let boundaries = boundaries();
+        let mut tracker = Tracker::new(&boundaries);
+        tracker.update(latlon(0.0, 10.0));
+        tracker.reset();
+        assert!(tracker.ids().is_empty());
+        assert_eq!(vec!["+B", "+B-1"], describe(&tracker.update(latlon(0.0, 10.0))));
+    }
+
+    #[test]
+    fn crossing_positions_are_only_interpolated_if_enabled() {
+        let boundaries = boundaries();
+        let mut tracker = Tracker::new(&boundaries);
+        tracker.update(latlon(0.0, -100.0));
+        let events = tracker.update(latlon(0.0, -80.0));
+        assert!(matches!(events[0], BorderEvent::Entered { id: "A", position: None }));
+
+        let mut tracker = Tracker::new(&boundaries).interpolate_crossings(true);
+        tracker.update(latlon(0.0, -100.0));
+        let events = tracker.update(latlon(0.0, -80.0));
+        let BorderEvent::Entered { id: "A", position: Some(position) } = events[0] else {
+            panic!("{events:?}")
+        };
+        assert!((position.longitude() - -90.0).abs() < 0.01);
+
+        // across the 180th meridian
+        let events = tracker.update(latlon(0.0, -170.0));
+        let events = [events, tracker.update(latlon(0.0, 170.0))].concat();
+        let BorderEvent::Exited { id: "A", position: Some(exited) } = events[0] else {
+            panic!("{events:?}")
+        };
+        assert!((exited.longitude() - -90.0).abs() < 0.01);
+        let BorderEvent::Entered { id: "B", position: Some(entered) } = events[1] else {
+            panic!("{events:?}")
+        };
+        assert_eq!(-180.0, entered.longitude());
+    }
+}
@@ -0,0 +1,69 @@
+use crate::LoadError;
+
+/// Check that a raster of the given `width` can consist of `size` cells and is not empty
+pub(crate) fn check_raster(width: usize, size: usize) -> Result<(), LoadError> {
+    if size == 0 {
+        return Err(LoadError::EmptyRaster);
+    }
+    // also true for a width of 0, as it can only have 0 cells
+    if size.is_multiple_of(width) { Ok(()) } else { Err(LoadError::InconsistentRaster { width, size }) }
+}
+
+/// Check that a ring at the given byte `offset` with the given number of points is a polygon
+pub(crate) fn check_ring(offset: usize, length: usize) -> Result<(), LoadError> {
+    if length >= 3 { Ok(()) } else { Err(LoadError::InvalidRing { offset, length }) }
+}
+
+/// Check that the given `id` at the given byte `offset` has a size and is not already one of the
+/// given ids of the same cell, then add it to these
+pub(crate) fn check_id<'a>(
+    offset: usize,
+    id: &'a str,
+    has_size: bool,
+    cell_ids: &mut Vec<&'a str>
+) -> Result<(), LoadError> {
+    if !has_size {
+        return Err(LoadError::UnknownId { offset, id: String::from(id) });
+    }
+    if cell_ids.contains(&id) {
+        return Err(LoadError::DuplicateId { offset, id: String::from(id) });
+    }
+    cell_ids.push(id);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn raster() {
+        assert!(check_raster(2, 4).is_ok());
+        assert!(matches!(check_raster(0, 0), Err(LoadError::EmptyRaster)));
+        assert!(matches!(check_raster(2, 0), Err(LoadError::EmptyRaster)));
+        assert!(matches!(check_raster(0, 1), Err(LoadError::InconsistentRaster { width: 0, size: 1 })));
+        assert!(matches!(check_raster(2, 3), Err(LoadError::InconsistentRaster { width: 2, size: 3 })));
+    }
+
+    #[test]
+    fn ring() {
+        assert!(check_ring(0, 3).is_ok());
+        assert!(matches!(check_ring(5, 2), Err(LoadError::InvalidRing { offset: 5, length: 2 })));
+        assert!(matches!(check_ring(5, 0), Err(LoadError::InvalidRing { offset: 5, length: 0 })));
+    }
+
+    #[test]
+    fn id() {
+        let mut cell_ids = Vec::new();
+        assert!(check_id(0, "A", true, &mut cell_ids).is_ok());
+        assert!(check_id(3, "B", true, &mut cell_ids).is_ok());
+        assert!(matches!(
+            check_id(6, "C", false, &mut cell_ids),
+            Err(LoadError::UnknownId { offset: 6, id }) if id == "C"
+        ));
+        assert!(matches!(
+            check_id(9, "A", true, &mut cell_ids),
+            Err(LoadError::DuplicateId { offset: 9, id }) if id == "A"
+        ));
+    }
+}
@@ -1,6 +1,6 @@
 use std::collections::HashSet;
 use std::fs;
-use country_boundaries::{self, BoundingBox, CountryBoundaries, LatLon};
+use country_boundaries::{self, BorderEvent, BoundingBox, CountryBoundaries, CountryBoundariesRef, LatLon, LoadOptions, Tracker};
 
 #[test]
 fn return_correct_results_at_cell_edges() {
@@ -19,6 +19,27 @@ fn return_correct_results_at_cell_edges() {
     assert_eq!(vec!["BA"], boundaries.ids(latlon(45.0, 16.0)));
 }
 
+#[test]
+fn return_correct_results_for_other_raster_sizes() {
+    for file in ["./data/boundaries180x90.ser", "./data/boundaries60x30.ser"] {
+        let buf = fs::read(file);
+        let boundaries = CountryBoundaries::from_reader(buf.unwrap().as_slice()).unwrap();
+
+        // Dallas
+        assert_eq!(vec!["US-TX", "US"], boundaries.ids(latlon(33.0, -97.0)));
+        // Freilassing and Salzburg, on either side of the German-Austrian border
+        assert_eq!(vec!["DE"], boundaries.ids(latlon(47.838, 12.977)));
+        assert_eq!(vec!["AT"], boundaries.ids(latlon(47.800, 13.045)));
+        // Vaals and Aachen, on either side of the Dutch-German border
+        assert_eq!(vec!["NL"], boundaries.ids(latlon(50.776, 6.012)));
+        assert_eq!(vec!["DE"], boundaries.ids(latlon(50.776, 6.084)));
+        // Büsingen, a German exclave in Switzerland
+        assert!(boundaries.is_in(latlon(47.6973, 8.6910), "DE"));
+        // Canberra, Australia
+        assert_eq!(vec!["AU-ACT", "AU"], boundaries.ids(latlon(-35.28, 149.13)));
+    }
+}
+
 #[test]
 fn containing_ids_at_180th_meridian() {
     let buf = fs::read("./data/boundaries360x180.ser");
@@ -30,6 +51,634 @@ fn containing_ids_at_180th_meridian() {
     );
 }
 
+#[test]
+fn region_sizes_are_in_square_degrees() {
+    let buf = fs::read("./data/boundaries360x180.ser");
+    let boundaries = CountryBoundaries::from_reader(buf.unwrap().as_slice()).unwrap();
+
+    assert!((boundaries.region_size("DE").unwrap() - 51.0).abs() < 0.1);
+    assert!((boundaries.region_size("RU").unwrap() - 4511.4).abs() < 0.1);
+    assert!(boundaries.region_size("US-TX").unwrap() < boundaries.region_size("US").unwrap());
+    assert_eq!(None, boundaries.region_size("XX"));
+}
+
+#[test]
+fn all_regions_are_listed() {
+    for file in [
+        "./data/boundaries60x30.ser",
+        "./data/boundaries180x90.ser",
+        "./data/boundaries360x180.ser"
+    ] {
+        let buf = fs::read(file).unwrap();
+        let boundaries = CountryBoundaries::from_reader(buf.as_slice()).unwrap();
+
+        let region_ids: HashSet<&str> = boundaries.region_ids().collect();
+        assert_eq!(boundaries.region_ids().count(), region_ids.len());
+        assert!(region_ids.contains("DE"));
+        assert!(region_ids.contains("US-TX"));
+        assert!(!region_ids.contains("XX"));
+        for id in region_ids.iter() {
+            assert!(boundaries.contains_region(id));
+            assert!(boundaries.region_size(id).is_some());
+        }
+        assert!(!boundaries.contains_region("XX"));
+
+        let world = BoundingBox::new(-90.0, -180.0, 90.0, 179.9).unwrap();
+        assert_eq!(region_ids, boundaries.intersecting_ids(world));
+
+        // ids() is ordered by region size
+        let ids = boundaries.ids(latlon(33.0, -97.0));
+        let sizes: Vec<f64> = ids.iter().map(|id| boundaries.region_size(id).unwrap()).collect();
+        assert!(sizes.is_sorted());
+    }
+}
+
+#[test]
+fn bounding_boxes_of_regions() {
+    let buf = fs::read("./data/boundaries360x180.ser");
+    let boundaries = CountryBoundaries::from_reader(buf.unwrap().as_slice()).unwrap();
+
+    let germany = boundaries.bbox_of("DE").unwrap();
+    assert!((47.0..47.5).contains(&germany.min_latitude()));
+    assert!((54.9..55.2).contains(&germany.max_latitude()));
+    assert!((5.4..6.0).contains(&germany.min_longitude()));
+    assert!((15.0..15.1).contains(&germany.max_longitude()));
+
+    // regions that cross the 180th meridian wrap around it
+    let russia = boundaries.bbox_of("RU").unwrap();
+    assert!((18.0..20.0).contains(&russia.min_longitude()));
+    assert!((-170.0..-168.0).contains(&russia.max_longitude()));
+    let fiji = boundaries.bbox_of("FJ").unwrap();
+    assert!((173.0..178.0).contains(&fiji.min_longitude()));
+    assert!((-179.0..-176.0).contains(&fiji.max_longitude()));
+
+    // the westernmost Aleutian Islands are in US but not in US-AK in this dataset
+    let alaska = boundaries.bbox_of("US-AK").unwrap();
+    assert!((-180.0..-179.0).contains(&alaska.min_longitude()));
+    assert!((-131.0..-129.0).contains(&alaska.max_longitude()));
+    let usa = boundaries.bbox_of("US").unwrap();
+    assert!(usa.min_longitude() > usa.max_longitude());
+
+    assert_eq!(None, boundaries.bbox_of("XX"));
+}
+
+#[test]
+fn positions_are_within_bounding_boxes_of_their_regions() {
+    let buf = fs::read("./data/boundaries180x90.ser");
+    let boundaries = CountryBoundaries::from_reader(buf.unwrap().as_slice()).unwrap();
+
+    for latitude in (-89..90).step_by(2) {
+        for longitude in (-179..180).step_by(2) {
+            let latitude = latitude as f64 + 0.3;
+            let longitude = longitude as f64 + 0.7;
+            for id in boundaries.ids(latlon(latitude, longitude)) {
+                let bounds = boundaries.bbox_of(id).unwrap();
+                assert!(bounds.min_latitude() <= latitude && latitude <= bounds.max_latitude());
+                let in_longitudes = if bounds.min_longitude() <= bounds.max_longitude() {
+                    bounds.min_longitude() <= longitude && longitude <= bounds.max_longitude()
+                } else {
+                    bounds.min_longitude() <= longitude || longitude <= bounds.max_longitude()
+                };
+                assert!(in_longitudes, "{latitude},{longitude} in {id} but not in {bounds}");
+            }
+        }
+    }
+}
+
+#[test]
+fn exact_intersecting_ids_are_between_ids_and_intersecting_ids() {
+    let buf = fs::read("./data/boundaries360x180.ser");
+    let boundaries = CountryBoundaries::from_reader(buf.unwrap().as_slice()).unwrap();
+
+    for latitude in (-80..80).step_by(3) {
+        for longitude in (-180..180).step_by(3) {
+            let latitude = latitude as f64 + 0.37;
+            let longitude = longitude as f64 + 0.71;
+            let bounds = BoundingBox::new(latitude - 0.2, longitude - 0.3, latitude + 0.2, longitude + 0.3).unwrap();
+            let exact = boundaries.intersecting_ids_exact(bounds);
+            let cells = boundaries.intersecting_ids(bounds);
+            assert!(exact.is_subset(&cells), "{bounds}");
+            let ids: HashSet<&str> = boundaries.ids(latlon(latitude, longitude)).into_iter().collect();
+            assert!(ids.is_subset(&exact), "{bounds}");
+        }
+    }
+
+    // Vaalserberg, which is in the Belgian region of Wallonia
+    assert_eq!(
+        HashSet::from(["DE", "BE", "BE-WAL", "NL"]),
+        boundaries.intersecting_ids_exact(BoundingBox::new(50.7358, 5.9865, 50.7679, 6.0599).unwrap())
+    );
+    // wrapping around the 180th meridian
+    assert_eq!(
+        HashSet::from(["RU"]),
+        boundaries.intersecting_ids_exact(BoundingBox::new(66.0, 178.0, 68.0, -178.0).unwrap())
+    );
+}
+
+#[test]
+fn exact_containing_ids_are_between_containing_ids_and_ids() {
+    let buf = fs::read("./data/boundaries360x180.ser");
+    let boundaries = CountryBoundaries::from_reader(buf.unwrap().as_slice()).unwrap();
+
+    let mut found_more = 0;
+    for latitude in (-80..80).step_by(3) {
+        for longitude in (-180..180).step_by(3) {
+            let latitude = latitude as f64 + 0.37;
+            let longitude = longitude as f64 + 0.71;
+            let bounds = BoundingBox::new(latitude - 0.05, longitude - 0.05, latitude + 0.05, longitude + 0.05).unwrap();
+            let exact = boundaries.containing_ids_exact(bounds);
+            let cells = boundaries.containing_ids(bounds);
+            assert!(cells.is_subset(&exact), "{bounds}");
+            let ids: HashSet<&str> = boundaries.ids(latlon(latitude, longitude)).into_iter().collect();
+            assert!(exact.is_subset(&ids), "{bounds}");
+            if exact.len() > cells.len() { found_more += 1; }
+        }
+    }
+    assert!(found_more > 0);
+
+    // Aachen, close to the border
+    assert_eq!(
+        HashSet::from(["DE"]),
+        boundaries.containing_ids_exact(BoundingBox::new(50.74, 6.05, 50.80, 6.15).unwrap())
+    );
+    // Vaalserberg, the tripoint of Germany, Belgium and the Netherlands
+    assert!(boundaries.containing_ids_exact(BoundingBox::new(50.7358, 5.9865, 50.7679, 6.0599).unwrap()).is_empty());
+}
+
+#[test]
+fn ids_of_rectangular_polygons_are_same_as_exact_ids_of_bounding_boxes() {
+    let buf = fs::read("./data/boundaries360x180.ser");
+    let boundaries = CountryBoundaries::from_reader(buf.unwrap().as_slice()).unwrap();
+
+    let mut mismatches = Vec::new();
+    for latitude in (-80..80).step_by(3) {
+        for longitude in (-180..180).step_by(3) {
+            let latitude = latitude as f64 + 0.37;
+            let longitude = longitude as f64 + 0.71;
+            let bounds = BoundingBox::new(latitude - 0.05, longitude - 0.05, latitude + 0.05, longitude + 0.05).unwrap();
+            let polygon = [
+                latlon(bounds.min_latitude(), bounds.min_longitude()),
+                latlon(bounds.min_latitude(), bounds.max_longitude()),
+                latlon(bounds.max_latitude(), bounds.max_longitude()),
+                latlon(bounds.max_latitude(), bounds.min_longitude()),
+            ];
+            if boundaries.intersecting_ids_polygon(&polygon, &[]) != boundaries.intersecting_ids_exact(bounds) {
+                mismatches.push(format!("intersecting {bounds}"));
+            }
+            if boundaries.containing_ids_polygon(&polygon, &[]) != boundaries.containing_ids_exact(bounds) {
+                mismatches.push(format!("containing {bounds}"));
+            }
+        }
+    }
+    assert!(mismatches.is_empty(), "{mismatches:?}");
+}
+
+#[test]
+fn ids_of_polygon_across_180th_meridian() {
+    let buf = fs::read("./data/boundaries360x180.ser");
+    let boundaries = CountryBoundaries::from_reader(buf.unwrap().as_slice()).unwrap();
+
+    // Taveuni, Fiji, which the 180th meridian runs through
+    let taveuni = [
+        latlon(-16.95, 179.88),
+        latlon(-16.85, -179.88),
+        latlon(-16.75, -179.92),
+        latlon(-16.80, 179.95),
+    ];
+    assert_eq!(HashSet::from(["FJ"]), boundaries.intersecting_ids_polygon(&taveuni, &[]));
+
+    // the Bering Strait between Russia and Alaska
+    let bering_strait = [
+        latlon(65.0, 170.0),
+        latlon(65.0, -165.0),
+        latlon(67.0, -165.0),
+        latlon(67.0, 170.0),
+    ];
+    let ids = boundaries.intersecting_ids_polygon(&bering_strait, &[]);
+    assert!(ids.contains("RU"));
+    assert!(ids.contains("US-AK"));
+    assert!(boundaries.containing_ids_polygon(&bering_strait, &[]).is_empty());
+}
+
+#[test]
+fn route_segments() {
+    let buf = fs::read("./data/boundaries360x180.ser");
+    let boundaries = CountryBoundaries::from_reader(buf.unwrap().as_slice()).unwrap();
+
+    // from Berlin to Paris
+    let segments = boundaries.route_segments(&[latlon(52.52, 13.405), latlon(48.8566, 2.3522)]);
+    let ids: Vec<Vec<&str>> = segments.iter().map(|segment| segment.ids.clone()).collect();
+    assert_eq!(vec![vec!["DE"], vec!["LU"], vec!["BE-WAL", "BE"], vec!["FR"]], ids);
+    // a bit longer than the great-circle distance, as the route follows the straight line in
+    // longitude and latitude
+    let length: f64 = segments.iter().map(|segment| segment.length).sum();
+    assert!((877_500.0..880_000.0).contains(&length));
+
+    // from Chukotka to Alaska across the 180th meridian
+    let segments = boundaries.route_segments(&[latlon(65.0, 170.0), latlon(66.0, -160.0)]);
+    assert_eq!(vec!["RU"], segments.first().unwrap().ids);
+    assert_eq!(vec!["US-AK", "US"], segments.last().unwrap().ids);
+    assert_eq!(170.0, segments.first().unwrap().start.longitude());
+    assert_eq!(-160.0, segments.last().unwrap().end.longitude());
+
+    // the segments are continuous and each is in the same regions all the way
+    let route: Vec<LatLon> = (0..100)
+        .map(|i| latlon(((i * 37) % 140) as f64 - 60.0 + 0.13, ((i * 71) % 360) as f64 - 180.0 + 0.29))
+        .collect();
+    for pair in route.windows(2) {
+        let segments = boundaries.route_segments(pair);
+        for (a, b) in segments.iter().zip(segments.iter().skip(1)) {
+            assert_ne!(a.ids, b.ids);
+            assert_eq!(a.end.latitude(), b.start.latitude());
+            assert_eq!(a.end.longitude(), b.start.longitude());
+        }
+        for segment in segments.iter() {
+            let middle = latlon(
+                (segment.start.latitude() + segment.end.latitude()) / 2.0,
+                segment.start.longitude() + normalize(segment.end.longitude() - segment.start.longitude()) / 2.0
+            );
+            assert_eq!(segment.ids, boundaries.ids(middle), "{} - {}", segment.start, segment.end);
+        }
+    }
+}
+
+#[test]
+fn tracker() {
+    let buf = fs::read("./data/boundaries360x180.ser");
+    let boundaries = CountryBoundaries::from_reader(buf.unwrap().as_slice()).unwrap();
+
+    // from Dallas to Oklahoma City
+    let mut tracker = Tracker::new(&boundaries).interpolate_crossings(true);
+    tracker.update(latlon(32.78, -96.80));
+    let events = tracker.update(latlon(35.47, -97.52));
+    assert_eq!(2, events.len());
+    let BorderEvent::Exited { id: "US-TX", position: Some(exited) } = events[0] else { panic!("{events:?}") };
+    let BorderEvent::Entered { id: "US-OK", position: Some(entered) } = events[1] else { panic!("{events:?}") };
+    // at the Red River
+    assert!((33.6..33.9).contains(&exited.latitude()));
+    assert_eq!(exited.latitude(), entered.latitude());
+
+    // the tracked regions are always the regions of the last position
+    let mut tracker = Tracker::new(&boundaries);
+    let mut ids: Vec<&str> = Vec::new();
+    for i in 0..2000 {
+        let position = latlon(45.0 + (i as f64 * 0.013).sin() * 10.0, -20.0 + i as f64 * 0.031);
+        for event in tracker.update(position) {
+            match event {
+                BorderEvent::Exited { id, .. } => ids.retain(|&other| other != id),
+                BorderEvent::Entered { id, .. } => ids.push(id)
+            }
+        }
+        let expected = boundaries.ids(position);
+        assert_eq!(expected, tracker.ids());
+        let mut ids = ids.clone();
+        let mut expected = expected.clone();
+        ids.sort();
+        expected.sort();
+        assert_eq!(expected, ids);
+    }
+}
+
+#[test]
+fn distance_to_border() {
+    let buf = fs::read("./data/boundaries360x180.ser");
+    let boundaries = CountryBoundaries::from_reader(buf.unwrap().as_slice()).unwrap();
+
+    // Vaals in the Netherlands is right at the German border
+    let vaals = latlon(50.7706, 6.0166);
+    assert!(boundaries.distance_to_border(vaals, "DE").unwrap().distance < 1000.0);
+    assert!(boundaries.distance_to_border(vaals, "NL").unwrap().distance < 1000.0);
+    // ...but far away from the Spanish one
+    assert!(boundaries.distance_to_border(vaals, "ES").unwrap().distance > 800_000.0);
+
+    // nothing is closer than the nearest border
+    for latitude in (-60..70).step_by(7) {
+        for longitude in (-180..180).step_by(11) {
+            let position = latlon(latitude as f64 + 0.37, longitude as f64 + 0.71);
+            let Some(nearest) = boundaries.nearest_border(position) else { continue };
+            for id in boundaries.ids(position) {
+                let border = boundaries.distance_to_border(position, id).unwrap();
+                assert!(nearest.distance <= border.distance, "{position}");
+            }
+            // half way to the nearest border, the regions are still the same
+            let half_way = latlon(
+                (position.latitude() + nearest.position.latitude()) / 2.0,
+                position.longitude() + normalize(nearest.position.longitude() - position.longitude()) / 2.0
+            );
+            assert_eq!(boundaries.ids(position), boundaries.ids(half_way), "{position}");
+        }
+    }
+}
+
+#[test]
+fn distance_to_border_along_side_of_cell() {
+    // the border between Northern Territory and Queensland at 138°E is a side of a cell of 6°
+    let buf = fs::read("./data/boundaries60x30.ser");
+    let boundaries = CountryBoundaries::from_reader(buf.unwrap().as_slice()).unwrap();
+    assert_eq!(vec!["AU-NT", "AU"], boundaries.ids(latlon(-24.74, 137.99)));
+    assert_eq!(vec!["AU-QLD", "AU"], boundaries.ids(latlon(-24.74, 138.01)));
+    for id in ["AU-QLD", "AU-NT"] {
+        let border = boundaries.distance_to_border(latlon(-24.74, 138.21), id).unwrap();
+        assert!((border.distance - 21_200.0).abs() < 100.0, "{id}");
+        assert!((border.position.longitude() - 138.0).abs() < 0.001, "{id}");
+    }
+
+    // the 180th meridian is a side of a cell, Russia continues on the other side
+    let buf = fs::read("./data/boundaries360x180.ser");
+    let boundaries = CountryBoundaries::from_reader(buf.unwrap().as_slice()).unwrap();
+    let position = latlon(64.53, 179.31);
+    let border = boundaries.distance_to_border(position, "RU").unwrap();
+    assert!((border.distance - 33_000.0).abs() < 100.0);
+    assert!(boundaries.is_in(position, "RU"));
+    assert!((border.position.longitude().abs() - 180.0).abs() < 0.001);
+    assert!(!boundaries.is_in(latlon(64.53, -179.99), "RU"));
+}
+
+#[test]
+fn nearest_ids() {
+    let buf = fs::read("./data/boundaries360x180.ser");
+    let boundaries = CountryBoundaries::from_reader(buf.unwrap().as_slice()).unwrap();
+
+    // in the Baltic Sea between Sweden and Latvia
+    let nearest = boundaries.nearest_ids(latlon(56.0, 19.5), 100_000.0);
+    assert_eq!(1, nearest.len());
+    assert_eq!("LV", nearest[0].0);
+
+    let mut found_nearest = 0;
+    for latitude in (-60..70).step_by(3) {
+        for longitude in (-180..180).step_by(5) {
+            let position = latlon(latitude as f64 + 0.37, longitude as f64 + 0.71);
+            let ids = boundaries.ids(position);
+            let nearest = boundaries.nearest_ids(position, 200_000.0);
+            if !ids.is_empty() {
+                assert_eq!(ids, nearest.iter().map(|(id, _)| *id).collect::<Vec<_>>());
+                assert!(nearest.iter().all(|(_, distance)| *distance == 0.0));
+            } else {
+                for (id, distance) in nearest.iter() {
+                    assert!(*distance <= 200_000.0);
+                    let border = boundaries.distance_to_border(position, id).unwrap();
+                    assert!((border.distance - distance).abs() < 10.0, "{position}");
+                }
+                if !nearest.is_empty() { found_nearest += 1; }
+            }
+        }
+    }
+    assert!(found_nearest > 0);
+}
+
+#[test]
+fn ids_with_confidence() {
+    let buf = fs::read("./data/boundaries360x180.ser");
+    let boundaries = CountryBoundaries::from_reader(buf.unwrap().as_slice()).unwrap();
+
+    let mut found_uncertain = 0;
+    for latitude in (-60..70).step_by(3) {
+        for longitude in (-180..180).step_by(5) {
+            let position = latlon(latitude as f64 + 0.37, longitude as f64 + 0.71);
+            let ids = boundaries.ids(position);
+            let result = boundaries.ids_with_confidence(position, 20_000.0);
+            for id in result.certain.iter() {
+                assert!(ids.contains(id));
+                assert!(boundaries.distance_to_border(position, id).unwrap().distance > 20_000.0);
+            }
+            for id in result.uncertain.iter() {
+                assert!(boundaries.distance_to_border(position, id).unwrap().distance <= 20_000.0);
+            }
+            for id in ids.iter() {
+                assert!(result.certain.contains(id) || result.uncertain.contains(id));
+            }
+            if !result.uncertain.is_empty() { found_uncertain += 1; }
+
+            // without tolerance, nothing is uncertain
+            assert_eq!(ids, boundaries.ids_with_confidence(position, 0.0).certain);
+        }
+    }
+    assert!(found_uncertain > 0);
+}
+
+#[test]
+fn ids_with_confidence_near_border_along_side_of_cell() {
+    // about 10 km east of the border between Northern Territory and Queensland at 138°E, which
+    // is a side of a cell of 6°
+    let buf = fs::read("./data/boundaries60x30.ser");
+    let boundaries = CountryBoundaries::from_reader(buf.unwrap().as_slice()).unwrap();
+    let result = boundaries.ids_with_confidence(latlon(-24.74, 138.1), 20_000.0);
+    assert_eq!(vec!["AU"], result.certain);
+    assert_eq!(HashSet::from(["AU-NT", "AU-QLD"]), result.uncertain.into_iter().collect());
+
+    let result = boundaries.ids_with_confidence(latlon(-24.74, 138.1), 5_000.0);
+    assert_eq!(vec!["AU-QLD", "AU"], result.certain);
+    assert!(result.uncertain.is_empty());
+}
+
+#[test]
+fn intersecting_ids_within() {
+    let buf = fs::read("./data/boundaries360x180.ser");
+    let boundaries = CountryBoundaries::from_reader(buf.unwrap().as_slice()).unwrap();
+
+    for latitude in (-60..70).step_by(7) {
+        for longitude in (-180..180).step_by(11) {
+            let position = latlon(latitude as f64 + 0.37, longitude as f64 + 0.71);
+            let result = boundaries.intersecting_ids_within(position, 50_000.0);
+            // the position itself is within the circle
+            for id in boundaries.ids(position) {
+                assert!(result.contains(id));
+            }
+            // and the circle is within a bounding box of ±1°
+            let bbox = BoundingBox::new(
+                position.latitude() - 1.0, normalize(position.longitude() - 1.0),
+                position.latitude() + 1.0, normalize(position.longitude() + 1.0)
+            ).unwrap();
+            assert!(result.is_subset(&boundaries.intersecting_ids(bbox)));
+            // and a region is within the circle if its border is or if it contains the position
+            for id in boundaries.intersecting_ids(bbox) {
+                let within = boundaries.ids(position).contains(&id)
+                    || boundaries.distance_to_border(position, id).unwrap().distance <= 50_000.0;
+                assert_eq!(within, result.contains(id), "{id} at {position:?}");
+            }
+        }
+    }
+
+    // around the north pole
+    let result = boundaries.intersecting_ids_within(latlon(90.0, 0.0), 1_000_000.0);
+    assert!(result.contains("GL"));
+    assert!(result.contains("CA"));
+    assert!(!result.contains("IS"));
+    // across the 180th meridian in the Bering Strait
+    let result = boundaries.intersecting_ids_within(latlon(65.7, -168.5), 50_000.0);
+    assert_eq!(HashSet::from(["RU", "US", "US-AK"]), result);
+}
+
+#[test]
+fn intersecting_ids_within_near_border_along_side_of_cell() {
+    // on either side of the border between Northern Territory and Queensland at 138°E, which is
+    // a side of a cell of 6°
+    let buf = fs::read("./data/boundaries60x30.ser");
+    let boundaries = CountryBoundaries::from_reader(buf.unwrap().as_slice()).unwrap();
+    // about 15 km west of the border
+    let position = latlon(-24.74, 137.85);
+    assert_eq!(HashSet::from(["AU-NT", "AU-QLD", "AU"]), boundaries.intersecting_ids_within(position, 25_000.0));
+    assert_eq!(HashSet::from(["AU-NT", "AU"]), boundaries.intersecting_ids_within(position, 10_000.0));
+    // about 10 km east of the border
+    let position = latlon(-24.74, 138.1);
+    assert_eq!(HashSet::from(["AU-NT", "AU-QLD", "AU"]), boundaries.intersecting_ids_within(position, 20_000.0));
+    assert_eq!(HashSet::from(["AU-QLD", "AU"]), boundaries.intersecting_ids_within(position, 5_000.0));
+}
+
+#[test]
+fn batch_is_same_as_one_by_one() {
+    let buf = fs::read("./data/boundaries360x180.ser");
+    let boundaries = CountryBoundaries::from_reader(buf.unwrap().as_slice()).unwrap();
+    // xorshift, so that the test is deterministic
+    let mut state: u64 = 0x2545f4914f6cdd1d;
+    let mut random = || {
+        state ^= state << 13;
+        state ^= state >> 7;
+        state ^= state << 17;
+        (state >> 11) as f64 / (1u64 << 53) as f64
+    };
+
+    // more than fit into one chunk, half of them clustered in Europe
+    let positions: Vec<LatLon> = (0..150_000).map(|i| if i % 2 == 0 {
+        latlon(random() * 180.0 - 90.0, random() * 360.0 - 180.0)
+    } else {
+        latlon(45.0 + random() * 10.0, 5.0 + random() * 10.0)
+    }).collect();
+
+    let ids = boundaries.ids_batch(&positions);
+    let eu = HashSet::from(["AT", "BE", "DE", "FR", "IT", "LU", "NL"]);
+    let is_in_eu = boundaries.is_in_any_batch(&positions, &eu);
+    assert_eq!(positions.len(), ids.len());
+    assert_eq!(positions.len(), is_in_eu.len());
+    for (i, &position) in positions.iter().enumerate() {
+        assert_eq!(boundaries.ids(position), ids[i]);
+        assert_eq!(boundaries.is_in_any(position, &eu), is_in_eu[i]);
+    }
+}
+
+#[test]
+fn ids_into_and_iter_are_same_as_ids() {
+    let buf = fs::read("./data/boundaries360x180.ser");
+    let boundaries = CountryBoundaries::from_reader(buf.unwrap().as_slice()).unwrap();
+
+    let mut ids = Vec::new();
+    let mut latitude = -89.0;
+    while latitude < 90.0 {
+        let mut longitude = -179.5;
+        while longitude < 180.0 {
+            let position = latlon(latitude, longitude);
+            ids.clear();
+            boundaries.ids_into(position, &mut ids);
+            assert_eq!(boundaries.ids(position), ids);
+            assert_eq!(boundaries.ids(position), boundaries.ids_iter(position).collect::<Vec<_>>());
+            longitude += 0.7;
+        }
+        latitude += 0.3;
+    }
+}
+
+fn normalize(longitude: f64) -> f64 {
+    (longitude + 540.0) % 360.0 - 180.0
+}
+
 fn latlon(latitude: f64, longitude: f64) -> LatLon {
     LatLon::new(latitude, longitude).unwrap()
 }
+
+#[test]
+fn serialization_round_trip() {
+    for file in [
+        "./data/boundaries60x30.ser",
+        "./data/boundaries180x90.ser",
+        "./data/boundaries360x180.ser"
+    ] {
+        let buf = fs::read(file).unwrap();
+        let boundaries = CountryBoundaries::from_reader(buf.as_slice()).unwrap();
+
+        let mut written = Vec::new();
+        boundaries.to_writer(&mut written).unwrap();
+        assert!(buf == written, "{file} is not written byte for byte as it was read");
+    }
+}
+
+#[test]
+fn borrowed_boundaries_return_same_results() {
+    for file in [
+        "./data/boundaries60x30.ser",
+        "./data/boundaries180x90.ser",
+        "./data/boundaries360x180.ser"
+    ] {
+        let buf = fs::read(file).unwrap();
+        let boundaries = CountryBoundaries::from_reader(buf.as_slice()).unwrap();
+        let boundaries_ref = CountryBoundariesRef::from_slice(&buf).unwrap();
+
+        let ids = HashSet::from(["DE", "US-TX", "RU"]);
+        let mut latitude = -89.5;
+        while latitude < 90.0 {
+            let mut longitude = -179.7;
+            while longitude < 180.0 {
+                let position = latlon(latitude, longitude);
+                assert_eq!(boundaries.ids(position), boundaries_ref.ids(position));
+                assert_eq!(boundaries.is_in(position, "DE"), boundaries_ref.is_in(position, "DE"));
+                assert_eq!(boundaries.is_in_any(position, &ids), boundaries_ref.is_in_any(position, &ids));
+
+                let bounds = BoundingBox::new(latitude, longitude, (latitude + 0.5).min(90.0), longitude + 3.0).unwrap();
+                assert_eq!(boundaries.containing_ids(bounds), boundaries_ref.containing_ids(bounds));
+                assert_eq!(boundaries.intersecting_ids(bounds), boundaries_ref.intersecting_ids(bounds));
+                longitude += 1.3;
+            }
+            latitude += 1.1;
+        }
+    }
+}
+
+#[test]
+fn corrupted_data_is_error_or_can_be_queried() {
+    let buf = fs::read("./data/boundaries60x30.ser").unwrap();
+    // xorshift, so that the test is deterministic
+    let mut state: u64 = 0x2545f4914f6cdd1d;
+    let mut random = |max: usize| {
+        state ^= state << 13;
+        state ^= state >> 7;
+        state ^= state << 17;
+        state as usize % max
+    };
+
+    for _ in 0..30 {
+        let mut data = buf.clone();
+        for _ in 0..1 + random(4) {
+            // mostly corrupt the header and the first cells, that is where the counts are
+            let index = if random(2) == 0 { random(data.len()) } else { random(8000) };
+            data[index] = random(256) as u8;
+        }
+        data.truncate(data.len() - random(100));
+
+        for validate in [true, false] {
+            let options = LoadOptions { validate, max_ring_length: 1000, ..LoadOptions::default() };
+            if let Ok(boundaries) = CountryBoundaries::from_reader_with_options(data.as_slice(), &options) {
+                boundaries.ids(latlon(33.0, -97.0));
+                boundaries.intersecting_ids(BoundingBox::new(-10.0, 170.0, 10.0, -170.0).unwrap());
+            }
+            if let Ok(boundaries) = CountryBoundariesRef::from_slice_with_options(&data, &options) {
+                boundaries.ids(latlon(33.0, -97.0));
+                boundaries.intersecting_ids(BoundingBox::new(-10.0, 170.0, 10.0, -170.0).unwrap());
+            }
+        }
+    }
+}
+
+#[test]
+fn empty_raster_is_error() {
+    // previously found to load successfully but panic when queried
+    let inputs: [&[u8]; 2] = [
+        &[0x00, 0x02, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00, 0x00],
+        &[0x00, 0x02, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00],
+    ];
+    for data in inputs {
+        for validate in [true, false] {
+            let options = LoadOptions { validate, ..LoadOptions::default() };
+            assert!(CountryBoundaries::from_reader_with_options(data, &options).is_err());
+            assert!(CountryBoundariesRef::from_slice_with_options(data, &options).is_err());
+        }
+    }
+}
@@ -0,0 +1,30 @@
+use std::collections::HashSet;
+use country_boundaries::{BoundingBox, LatLon, StaticCountryBoundaries};
+
+/// Generated by `codegen::write_rust`, the unit tests of the codegen module make sure it is up to
+/// date. Cell 0 (the western hemisphere) is completely in "A", cell 1 has a triangle of "B" in its
+/// south-west corner
+static BOUNDARIES: StaticCountryBoundaries = include!("static_boundaries.in");
+
+#[test]
+fn query_generated_static_boundaries() {
+    assert_eq!(vec!["A"], BOUNDARIES.ids(latlon(10.0, -90.0)));
+    assert_eq!(vec!["B"], BOUNDARIES.ids(latlon(-45.0, 45.0)));
+    assert!(BOUNDARIES.ids(latlon(45.0, 135.0)).is_empty());
+
+    assert!(BOUNDARIES.is_in(latlon(-45.0, 45.0), "B"));
+    assert!(!BOUNDARIES.is_in(latlon(-45.0, 45.0), "A"));
+    assert!(BOUNDARIES.is_in_any(latlon(10.0, -90.0), &HashSet::from(["A", "B"])));
+
+    let bounds = BoundingBox::new(-10.0, -10.0, 10.0, 10.0).unwrap();
+    assert_eq!(HashSet::from(["A", "B"]), BOUNDARIES.intersecting_ids(bounds));
+    assert_eq!(HashSet::<&str>::new(), BOUNDARIES.containing_ids(bounds));
+    assert_eq!(
+        HashSet::from(["A"]),
+        BOUNDARIES.containing_ids(BoundingBox::new(-10.0, -100.0, 10.0, -80.0).unwrap())
+    );
+}
+
+fn latlon(latitude: f64, longitude: f64) -> LatLon {
+    LatLon::new(latitude, longitude).unwrap()
+}